@@ -1,10 +1,17 @@
 //! Editor actions such as move, insert, delete
+pub mod command;
+pub mod comment;
+pub mod completion;
 pub mod cpaste;
 pub mod delete;
+pub mod diagnostic;
+pub mod fold;
 pub mod insert;
 pub mod motion;
+pub mod multi_cursor;
 pub mod search;
 pub mod select;
+pub mod snippet;
 use crate::state::selection::Selection;
 use crate::{EditorMode, EditorState};
 use cpaste::PasteOverSelection;
@@ -12,20 +19,33 @@ use delete::DeleteToEndOfLine;
 use enum_dispatch::enum_dispatch;
 use motion::{MoveToFirstRow, MoveToLastRow};
 
+pub use self::command::{
+    AppendCharToCommand, ExecuteCommand, RemoveCharFromCommand, StartCommand, StopCommand,
+};
+pub use self::comment::{ToggleComment, ToggleCommentMotion, ToggleCommentSelection};
+pub use self::completion::{AcceptCompletion, CloseCompletion, NextCompletion, PreviousCompletion};
 pub use self::cpaste::{CopyLine, CopySelection, Paste};
 pub use self::delete::{
     DeleteChar, DeleteLine, DeleteSelection, JoinLineWithLineBelow, RemoveChar, ReplaceChar,
 };
+pub use self::diagnostic::{NextDiagnostic, PreviousDiagnostic};
+pub use self::fold::{
+    CloseAllFolds, CloseFold, CreateFoldMotion, CreateFoldSelection, OpenAllFolds, OpenFold,
+    ToggleFold,
+};
 pub use self::insert::{AppendNewline, InsertChar, InsertNewline, LineBreak};
 pub use self::motion::{
-    MoveBackward, MoveDown, MoveForward, MoveToEndOfLine, MoveToFirst, MoveToMatchinBracket,
-    MoveToStartOfLine, MoveUp, MoveWordBackward, MoveWordForward, MoveWordForwardToEndOfWord,
+    MoveBackward, MoveDown, MoveForward, MoveParagraphBackward, MoveParagraphForward,
+    MoveToEndOfLine, MoveToFirst, MoveToMatchinBracket, MoveToStartOfLine, MoveUp,
+    MoveWordBackward, MoveWordForward, MoveWordForwardToEndOfWord,
 };
+pub use self::multi_cursor::{AddCursorOnNextMatch, AddCursorsToSelection, RemoveSecondaryCursors};
 use self::search::StartSearch;
 pub use self::search::{
     AppendCharToSearch, FindNext, FindPrevious, RemoveCharFromSearch, StopSearch, TriggerSearch,
 };
-pub use self::select::{ChangeInnerBetween, SelectInnerBetween, SelectLine};
+pub use self::select::{ChangeInnerBetween, SelectInnerBetween, SelectLine, SelectParagraph};
+pub use self::snippet::{ExpandSnippet, NextTabstop, PreviousTabstop};
 
 #[enum_dispatch(Execute)]
 #[derive(Clone, Debug)]
@@ -45,6 +65,8 @@ pub enum Action {
     MoveToFirstRow(MoveToFirstRow),
     MoveToLastRow(MoveToLastRow),
     MoveToMatchingBracket(MoveToMatchinBracket),
+    MoveParagraphForward(MoveParagraphForward),
+    MoveParagraphBackward(MoveParagraphBackward),
     InsertChar(InsertChar),
     LineBreak(LineBreak),
     AppendNewline(AppendNewline),
@@ -59,8 +81,11 @@ pub enum Action {
     SelectInnerBetween(SelectInnerBetween),
     ChangeInnerBetween(ChangeInnerBetween),
     SelectLine(SelectLine),
+    SelectParagraph(SelectParagraph),
     Undo(Undo),
     Redo(Redo),
+    UndoOlder(UndoOlder),
+    UndoNewer(UndoNewer),
     Paste(Paste),
     PasteOverSelection(PasteOverSelection),
     CopySelection(CopySelection),
@@ -73,6 +98,116 @@ pub enum Action {
     FindPrevious(FindPrevious),
     AppendCharToSearch(AppendCharToSearch),
     RemoveCharFromSearch(RemoveCharFromSearch),
+    StartCommand(StartCommand),
+    StopCommand(StopCommand),
+    AppendCharToCommand(AppendCharToCommand),
+    RemoveCharFromCommand(RemoveCharFromCommand),
+    ExecuteCommand(ExecuteCommand),
+    AddCursorOnNextMatch(AddCursorOnNextMatch),
+    AddCursorsToSelection(AddCursorsToSelection),
+    RemoveSecondaryCursors(RemoveSecondaryCursors),
+    NextCompletion(NextCompletion),
+    PreviousCompletion(PreviousCompletion),
+    AcceptCompletion(AcceptCompletion),
+    CloseCompletion(CloseCompletion),
+    ExpandSnippet(ExpandSnippet),
+    NextTabstop(NextTabstop),
+    PreviousTabstop(PreviousTabstop),
+    ToggleComment(ToggleComment),
+    ToggleCommentMotion(ToggleCommentMotion),
+    ToggleCommentSelection(ToggleCommentSelection),
+    NextDiagnostic(NextDiagnostic),
+    PreviousDiagnostic(PreviousDiagnostic),
+    CreateFoldMotion(CreateFoldMotion),
+    CreateFoldSelection(CreateFoldSelection),
+    ToggleFold(ToggleFold),
+    OpenFold(OpenFold),
+    CloseFold(CloseFold),
+    OpenAllFolds(OpenAllFolds),
+    CloseAllFolds(CloseAllFolds),
+}
+
+impl Action {
+    /// Whether the action is executed once at every cursor if there are
+    /// multiple cursors. Actions that operate on the editor as a whole,
+    /// such as undo, search or copy, are only executed once.
+    #[must_use]
+    pub fn is_per_cursor(&self) -> bool {
+        !matches!(
+            self,
+            Self::Undo(_)
+                | Self::Redo(_)
+                | Self::UndoOlder(_)
+                | Self::UndoNewer(_)
+                | Self::CopySelection(_)
+                | Self::CopyLine(_)
+                | Self::StartSearch(_)
+                | Self::StopSearch(_)
+                | Self::TriggerSearch(_)
+                | Self::FindNext(_)
+                | Self::FindPrevious(_)
+                | Self::AppendCharToSearch(_)
+                | Self::RemoveCharFromSearch(_)
+                | Self::StartCommand(_)
+                | Self::StopCommand(_)
+                | Self::AppendCharToCommand(_)
+                | Self::RemoveCharFromCommand(_)
+                | Self::ExecuteCommand(_)
+                | Self::AddCursorOnNextMatch(_)
+                | Self::AddCursorsToSelection(_)
+                | Self::RemoveSecondaryCursors(_)
+                | Self::NextCompletion(_)
+                | Self::PreviousCompletion(_)
+                | Self::AcceptCompletion(_)
+                | Self::CloseCompletion(_)
+                | Self::ExpandSnippet(_)
+                | Self::NextTabstop(_)
+                | Self::PreviousTabstop(_)
+                | Self::ToggleComment(_)
+                | Self::ToggleCommentMotion(_)
+                | Self::ToggleCommentSelection(_)
+                | Self::NextDiagnostic(_)
+                | Self::PreviousDiagnostic(_)
+                | Self::CreateFoldMotion(_)
+                | Self::CreateFoldSelection(_)
+                | Self::ToggleFold(_)
+                | Self::OpenFold(_)
+                | Self::CloseFold(_)
+                | Self::OpenAllFolds(_)
+                | Self::CloseAllFolds(_)
+        )
+    }
+
+    /// Whether the action only moves the cursor and can therefore be the
+    /// target of an operator in normal mode, e.g. the `j` in `gcj`.
+    #[must_use]
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Self::MoveForward(_)
+                | Self::MoveBackward(_)
+                | Self::MoveUp(_)
+                | Self::MoveDown(_)
+                | Self::MoveWordForward(_)
+                | Self::MoveWordForwardToEndOfWord(_)
+                | Self::MoveWordBackward(_)
+                | Self::MoveToStartOfLine(_)
+                | Self::MoveToFirst(_)
+                | Self::MoveToEndOfLine(_)
+                | Self::MoveToFirstRow(_)
+                | Self::MoveToLastRow(_)
+                | Self::MoveToMatchingBracket(_)
+                | Self::MoveParagraphForward(_)
+                | Self::MoveParagraphBackward(_)
+        )
+    }
+
+    /// Whether the action selects a text object and can therefore be the
+    /// target of an operator, e.g. the `ip` in `gcip`.
+    #[must_use]
+    pub fn is_text_object(&self) -> bool {
+        matches!(self, Self::SelectInnerBetween(_) | Self::SelectParagraph(_))
+    }
 }
 
 #[enum_dispatch]
@@ -86,6 +221,11 @@ pub struct SwitchMode(pub EditorMode);
 impl Execute for SwitchMode {
     fn execute(&mut self, state: &mut EditorState) {
         state.clamp_column();
+        if state.mode == EditorMode::Insert && self.0 != EditorMode::Insert {
+            state.end_insert_session();
+            state.completion.close();
+            state.snippets.session = None;
+        }
         match self.0 {
             EditorMode::Normal => {
                 state.selection = None;
@@ -96,9 +236,10 @@ impl Execute for SwitchMode {
             EditorMode::Insert => {
                 if state.mode != EditorMode::Insert {
                     state.capture();
+                    state.begin_insert_session();
                 }
             }
-            EditorMode::Search => {}
+            EditorMode::Search | EditorMode::Command => {}
         }
         state.mode = self.0;
     }
@@ -133,6 +274,28 @@ impl Execute for Redo {
     }
 }
 
+/// Goes to the previous text state in chronological order, across
+/// branches of the undo history.
+#[derive(Clone, Debug)]
+pub struct UndoOlder;
+
+impl Execute for UndoOlder {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.undo_older();
+    }
+}
+
+/// Goes to the next text state in chronological order, across
+/// branches of the undo history.
+#[derive(Clone, Debug)]
+pub struct UndoNewer;
+
+impl Execute for UndoNewer {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.undo_newer();
+    }
+}
+
 /// Executes multiple actions one after the other.
 #[derive(Clone, Debug)]
 pub struct Composed(pub Vec<Action>);
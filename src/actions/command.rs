@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use crate::{EditorMode, EditorState};
+
+use super::Execute;
+
+/// Command to clear the command line and switch into command mode.
+#[derive(Clone, Debug)]
+pub struct StartCommand;
+
+impl Execute for StartCommand {
+    /// Executes the command, clearing the command line and switching to command mode.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.mode = EditorMode::Command;
+        state.command.clear();
+    }
+}
+
+/// Command to clear the command line and switch to normal mode.
+#[derive(Clone, Debug)]
+pub struct StopCommand;
+
+impl Execute for StopCommand {
+    /// Executes the command, clearing the command line and switching to normal mode.
+    fn execute(&mut self, state: &mut EditorState) {
+        state.mode = EditorMode::Normal;
+        state.command.clear();
+    }
+}
+
+/// Command to append a single character to the command line.
+#[derive(Clone, Debug, Copy)]
+pub struct AppendCharToCommand(pub char);
+
+impl Execute for AppendCharToCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.command.push(self.0);
+    }
+}
+
+/// Command to remove the last character from the command line.
+#[derive(Clone, Debug, Copy)]
+pub struct RemoveCharFromCommand;
+
+impl Execute for RemoveCharFromCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.command.pop();
+    }
+}
+
+/// Command to execute the command line and switch to normal mode.
+///
+/// Supported commands:
+/// - `:earlier {N}`, `:earlier {N}s`: Go to an older text state, `N` changes
+///   or `N` seconds before. Besides `s`, the units `m`, `h` and `d` are supported.
+/// - `:later {N}`, `:later {N}s`: Go to a newer text state.
+#[derive(Clone, Debug)]
+pub struct ExecuteCommand;
+
+impl Execute for ExecuteCommand {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.mode = EditorMode::Normal;
+        let command = std::mem::take(&mut state.command);
+        let mut args = command.split_whitespace();
+        let (Some(name), arg) = (args.next(), args.next()) else {
+            return;
+        };
+
+        let Some(amount) = UndoAmount::parse(arg.unwrap_or("1")) else {
+            return;
+        };
+        match (name, amount) {
+            ("ea" | "earlier", UndoAmount::Changes(count)) => {
+                for _ in 0..count {
+                    state.undo_older();
+                }
+            }
+            ("ea" | "earlier", UndoAmount::Time(duration)) => state.earlier(duration),
+            ("lat" | "later", UndoAmount::Changes(count)) => {
+                for _ in 0..count {
+                    state.undo_newer();
+                }
+            }
+            ("lat" | "later", UndoAmount::Time(duration)) => state.later(duration),
+            _ => {}
+        }
+    }
+}
+
+/// The argument of `:earlier` and `:later`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum UndoAmount {
+    Changes(usize),
+    Time(Duration),
+}
+
+impl UndoAmount {
+    /// Parses a count, e.g. `3`, or a duration, e.g. `30s`, `2m`, `1h` or `1d`.
+    fn parse(arg: &str) -> Option<Self> {
+        let split = arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len());
+        let (value, unit) = arg.split_at(split);
+        let value: u64 = value.parse().ok()?;
+        let seconds = match unit {
+            "" => return Some(Self::Changes(usize::try_from(value).ok()?)),
+            "s" => value,
+            "m" => value.checked_mul(60)?,
+            "h" => value.checked_mul(60 * 60)?,
+            "d" => value.checked_mul(24 * 60 * 60)?,
+            _ => return None,
+        };
+        Some(Self::Time(Duration::from_secs(seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::RemoveChar;
+    use crate::Lines;
+
+    fn execute_command(state: &mut EditorState, command: &str) {
+        StartCommand.execute(state);
+        for ch in command.chars() {
+            AppendCharToCommand(ch).execute(state);
+        }
+        ExecuteCommand.execute(state);
+    }
+
+    #[test]
+    fn test_parse_undo_amount() {
+        assert_eq!(UndoAmount::parse("3"), Some(UndoAmount::Changes(3)));
+        assert_eq!(
+            UndoAmount::parse("30s"),
+            Some(UndoAmount::Time(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            UndoAmount::parse("2m"),
+            Some(UndoAmount::Time(Duration::from_secs(120)))
+        );
+        assert_eq!(UndoAmount::parse("2x"), None);
+        assert_eq!(UndoAmount::parse("s"), None);
+    }
+
+    #[test]
+    fn test_earlier_later() {
+        let mut state = EditorState::new(Lines::from("abc"));
+        RemoveChar(1).execute(&mut state);
+        RemoveChar(1).execute(&mut state);
+
+        execute_command(&mut state, "earlier 2");
+        assert_eq!(state.lines, Lines::from("abc"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        execute_command(&mut state, "later 1");
+        assert_eq!(state.lines, Lines::from("bc"));
+
+        execute_command(&mut state, "later 1h");
+        assert_eq!(state.lines, Lines::from("c"));
+    }
+}
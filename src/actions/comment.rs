@@ -0,0 +1,113 @@
+use super::{Action, Execute};
+use crate::{EditorMode, EditorState};
+
+/// Toggles the comments of the current line and the lines below, e.g. `gcc`.
+#[derive(Clone, Debug, Copy)]
+pub struct ToggleComment(pub usize);
+
+impl Execute for ToggleComment {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.capture();
+        let row = state.cursor.row;
+        state.toggle_comment(row..=row + self.0.saturating_sub(1));
+    }
+}
+
+/// Toggles the comments of the lines the cursor moves over when executing
+/// a motion, or of the lines a text object selects, e.g. `gcj` or `gcip`.
+/// The cursor is not moved.
+#[derive(Clone, Debug)]
+pub struct ToggleCommentMotion(pub Box<Action>);
+
+impl ToggleCommentMotion {
+    #[must_use]
+    pub fn new<A: Into<Action>>(motion: A) -> Self {
+        Self(Box::new(motion.into()))
+    }
+}
+
+impl Execute for ToggleCommentMotion {
+    fn execute(&mut self, state: &mut EditorState) {
+        let cursor = state.cursor;
+        let mode = state.mode;
+        let selection = state.selection.take();
+        self.0.execute(state);
+        let rows = match state.selection.take() {
+            Some(target) => target.start().row..=target.end().row,
+            None => state.cursor.row.min(cursor.row)..=state.cursor.row.max(cursor.row),
+        };
+        state.cursor = cursor;
+        state.mode = mode;
+        state.selection = selection;
+
+        state.capture();
+        state.toggle_comment(rows);
+    }
+}
+
+/// Toggles the comments of the selected lines and switches to normal mode.
+#[derive(Clone, Debug)]
+pub struct ToggleCommentSelection;
+
+impl Execute for ToggleCommentSelection {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(selection) = state.selection.take() else {
+            return;
+        };
+        state.capture();
+        state.toggle_comment(selection.start().row..=selection.end().row);
+        state.mode = EditorMode::Normal;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{MoveDown, SelectParagraph};
+    use crate::state::selection::Selection;
+    use crate::{CommentTokens, Index2, Lines};
+
+    fn test_state() -> EditorState {
+        let mut state = EditorState::new(Lines::from("a\nb\nc"));
+        state.set_comment_tokens(Some(CommentTokens::line("//")));
+        state
+    }
+
+    #[test]
+    fn test_toggle_comment_text_object() {
+        let mut state = EditorState::new(Lines::from("a\nb\n\nc"));
+        state.set_comment_tokens(Some(CommentTokens::line("//")));
+        state.cursor = Index2::new(1, 0);
+
+        ToggleCommentMotion::new(SelectParagraph::inner()).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("// a\n// b\n\nc"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
+        assert_eq!(state.mode, EditorMode::Normal);
+        assert_eq!(state.selection, None);
+    }
+
+    #[test]
+    fn test_toggle_comment_motion() {
+        let mut state = test_state();
+        ToggleCommentMotion::new(MoveDown(1)).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("// a\n// b\nc"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        ToggleComment(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("a\n// b\nc"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("// a\n// b\nc"));
+    }
+
+    #[test]
+    fn test_toggle_comment_selection() {
+        let mut state = test_state();
+        state.mode = EditorMode::Visual;
+        state.selection = Some(Selection::new(Index2::new(2, 0), Index2::new(1, 0)));
+
+        ToggleCommentSelection.execute(&mut state);
+        assert_eq!(state.lines, Lines::from("a\n// b\n// c"));
+        assert_eq!(state.mode, EditorMode::Normal);
+    }
+}
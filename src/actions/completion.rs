@@ -0,0 +1,151 @@
+use jagged::index::RowIndex;
+
+use super::{delete::delete_selection, Execute};
+use crate::{helper::insert_char, snippet::Snippet, EditorMode, EditorState};
+
+/// Selects the next completion item. Opens the completion popup for the
+/// word in front of the cursor if it is closed.
+#[derive(Clone, Debug)]
+pub struct NextCompletion;
+
+impl Execute for NextCompletion {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.mode != EditorMode::Insert {
+            return;
+        }
+        if !state.completion.is_open() {
+            state.refresh_completion();
+        }
+        state.completion.select_next();
+    }
+}
+
+/// Selects the previous completion item. Opens the completion popup for
+/// the word in front of the cursor if it is closed.
+#[derive(Clone, Debug)]
+pub struct PreviousCompletion;
+
+impl Execute for PreviousCompletion {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.mode != EditorMode::Insert {
+            return;
+        }
+        if !state.completion.is_open() {
+            state.refresh_completion();
+        }
+        state.completion.select_previous();
+    }
+}
+
+/// Replaces the word in front of every cursor, or the selected snippet
+/// placeholder, with the selected completion item and closes the completion
+/// popup. Snippet items are expanded at the primary cursor.
+#[derive(Clone, Debug)]
+pub struct AcceptCompletion;
+
+impl Execute for AcceptCompletion {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(item) = state.completion.selected_item().cloned() else {
+            state.completion.close();
+            return;
+        };
+        let prefix: Vec<char> = state.completion.prefix.chars().collect();
+        state.completion.close();
+
+        let snippet = item
+            .is_snippet
+            .then(|| Snippet::new(item.label.clone(), item.insert_text.clone()).ok())
+            .flatten();
+        if snippet.is_some() {
+            state.clear_cursors();
+        }
+
+        state.for_each_cursor(|state| {
+            if let Some(selection) = state.selection.take() {
+                delete_selection(state, &selection);
+            }
+            let row = state.cursor.row;
+            state.modify_rows(row..row + 1, |state| {
+                let col = state.cursor.col;
+                let line = state.lines.get(RowIndex::new(row));
+                if line.is_some_and(|line| line[..col.min(line.len())].ends_with(&prefix)) {
+                    for _ in 0..prefix.len() {
+                        state.cursor.col -= 1;
+                        state.lines.remove(state.cursor);
+                    }
+                }
+                if snippet.is_none() {
+                    for ch in item.insert_text.chars() {
+                        insert_char(&mut state.lines, &mut state.cursor, ch, false);
+                    }
+                }
+            });
+        });
+
+        if let Some(snippet) = snippet {
+            state.insert_snippet(&snippet);
+        }
+    }
+}
+
+/// Closes the completion popup.
+#[derive(Clone, Debug)]
+pub struct CloseCompletion;
+
+impl Execute for CloseCompletion {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.completion.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{InsertChar, SwitchMode};
+    use crate::{Index2, Lines};
+
+    #[test]
+    fn test_complete_buffer_word() {
+        let mut state = EditorState::new(Lines::from("foobar foobaz\n"));
+        state.cursor = Index2::new(1, 0);
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('f').execute(&mut state);
+
+        NextCompletion.execute(&mut state);
+        NextCompletion.execute(&mut state);
+        assert_eq!(state.completion.items.len(), 2);
+        assert_eq!(state.completion.selected, Some(1));
+
+        AcceptCompletion.execute(&mut state);
+        assert_eq!(state.lines, Lines::from("foobar foobaz\nfoobaz"));
+        assert_eq!(state.cursor, Index2::new(1, 6));
+        assert!(!state.completion.is_open());
+    }
+
+    #[test]
+    fn test_previous_completion_wraps() {
+        let mut state = EditorState::new(Lines::from("ab ac a"));
+        state.mode = EditorMode::Insert;
+        state.cursor = Index2::new(0, 7);
+
+        PreviousCompletion.execute(&mut state);
+        assert_eq!(state.completion.selected, Some(1));
+        PreviousCompletion.execute(&mut state);
+        PreviousCompletion.execute(&mut state);
+        assert_eq!(state.completion.selected, Some(1));
+    }
+
+    #[test]
+    fn test_complete_snippet() {
+        let mut state = EditorState::new(Lines::from("pr"));
+        state.mode = EditorMode::Insert;
+        state.cursor = Index2::new(0, 2);
+        state.add_snippet(Snippet::new("println", "println!(\"$1\");").unwrap());
+
+        NextCompletion.execute(&mut state);
+        assert_eq!(state.completion.items[0].label, "println");
+        AcceptCompletion.execute(&mut state);
+        assert_eq!(state.lines, Lines::from("println!(\"\");"));
+        assert_eq!(state.cursor, Index2::new(0, 10));
+    }
+}
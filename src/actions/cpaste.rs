@@ -22,14 +22,20 @@ impl Execute for Paste {
 
         let s = if let Some(stripped) = s.strip_prefix('\n') {
             state.cursor = Index2::new(state.cursor.row + 1, 0);
-            state.lines.insert(RowIndex::new(state.cursor.row), vec![]);
+            let row = state.cursor.row;
+            state.modify_rows(row..row, |state| {
+                state.lines.insert(RowIndex::new(row), vec![]);
+            });
             stripped
         } else {
             state.clamp_column();
             &s
         };
 
-        append_str(&mut state.lines, &mut state.cursor, s);
+        let row = state.cursor.row;
+        state.modify_rows(row..row + 1, |state| {
+            append_str(&mut state.lines, &mut state.cursor, s);
+        });
         SwitchMode(EditorMode::Normal).execute(state);
     }
 }
@@ -43,7 +49,11 @@ impl Execute for PasteOverSelection {
             state.capture();
             state.clamp_column();
             let _ = delete_selection(state, &selection);
-            insert_str(&mut state.lines, &mut state.cursor, &state.clip.get_text());
+            let text = state.clip.get_text();
+            let row = state.cursor.row;
+            state.modify_rows(row..row + 1, |state| {
+                insert_str(&mut state.lines, &mut state.cursor, &text);
+            });
         }
 
         SwitchMode(EditorMode::Normal).execute(state);
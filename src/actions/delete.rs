@@ -3,13 +3,14 @@ use jagged::index::RowIndex;
 use super::Execute;
 use crate::{
     clipboard::ClipboardTrait,
-    helper::{is_out_of_bounds, max_col_insert},
+    helper::{grapheme_end, grapheme_start, is_out_of_bounds, max_col_insert},
     state::selection::Selection,
     EditorMode, EditorState, Index2, Lines,
 };
 
 /// Deletes a character at the current cursor position. Does not
-/// move the cursor position unless it is at the end of the line
+/// move the cursor position unless it is at the end of the line.
+/// Deletes the whole grapheme cluster at the cursor.
 #[derive(Clone, Debug, Copy)]
 pub struct RemoveChar(pub usize);
 
@@ -18,23 +19,27 @@ impl Execute for RemoveChar {
         state.capture();
         state.clamp_column();
         for _ in 0..self.0 {
-            let lines = &mut state.lines;
-            let index = &mut state.cursor;
-            if lines.len_col(index.row).unwrap_or_default() == 0 {
+            if state.lines.len_col(state.cursor.row).unwrap_or_default() == 0 {
                 return;
             }
-            let _ = lines.remove(*index);
-            index.col = index.col.min(
-                lines
-                    .len_col(index.row)
-                    .unwrap_or_default()
-                    .saturating_sub(1),
-            );
+            let row = state.cursor.row;
+            state.modify_rows(row..row + 1, |state| {
+                let lines = &mut state.lines;
+                let index = &mut state.cursor;
+                remove_grapheme(lines, *index);
+                index.col = index.col.min(
+                    lines
+                        .len_col(index.row)
+                        .unwrap_or_default()
+                        .saturating_sub(1),
+                );
+            });
         }
     }
 }
 
-/// Replaces the character under the cursor with a given character.
+/// Replaces the character under the cursor with a given character. The
+/// whole grapheme cluster under the cursor is replaced.
 #[derive(Clone, Debug, Copy)]
 pub struct ReplaceChar(pub char);
 
@@ -45,22 +50,33 @@ impl Execute for ReplaceChar {
             return;
         }
         state.capture();
-        if let Some(ch) = state.lines.get_mut(index) {
-            *ch = self.0;
-        };
+        state.modify_rows(index.row..index.row + 1, |state| {
+            remove_grapheme(&mut state.lines, index);
+            state.lines.insert(index, self.0);
+        });
     }
 }
 
 /// Deletes a character to the left of the current cursor. Deletes
-/// the line break if the the cursor is in column zero.
+/// the line break if the the cursor is in column zero. In insert mode,
+/// deletes the selection instead, e.g. a snippet placeholder.
 #[derive(Clone, Debug, Copy)]
 pub struct DeleteChar(pub usize);
 
 impl Execute for DeleteChar {
     fn execute(&mut self, state: &mut EditorState) {
         state.capture();
+        if state.mode == EditorMode::Insert {
+            if let Some(selection) = state.selection.take() {
+                delete_selection(state, &selection);
+                return;
+            }
+        }
         for _ in 0..self.0 {
-            delete_char(&mut state.lines, &mut state.cursor);
+            let row = state.cursor.row;
+            state.modify_rows(row.saturating_sub(1)..row + 1, |state| {
+                delete_char(&mut state.lines, &mut state.cursor);
+            });
         }
     }
 }
@@ -92,7 +108,21 @@ fn delete_char(lines: &mut Lines, index: &mut Index2) {
         let max_col = max_col_insert(lines, index);
         index.col = index.col.min(max_col);
         move_left(lines, index);
-        let _ = lines.remove(*index);
+        if let Some(line) = lines.get(RowIndex::new(index.row)) {
+            index.col = grapheme_start(line, index.col);
+        }
+        remove_grapheme(lines, *index);
+    }
+}
+
+/// Removes the grapheme cluster that starts at `index`.
+fn remove_grapheme(lines: &mut Lines, index: Index2) {
+    let Some(line) = lines.get(RowIndex::new(index.row)) else {
+        return;
+    };
+    let start = grapheme_start(line, index.col);
+    for _ in start..grapheme_end(line, index.col) {
+        let _ = lines.remove(Index2::new(index.row, start));
     }
 }
 
@@ -107,8 +137,10 @@ impl Execute for DeleteLine {
             if state.cursor.row >= state.lines.len() {
                 break;
             }
-            let row_index = RowIndex::new(state.cursor.row);
-            let deleted_line = state.lines.remove(row_index).iter().collect::<String>();
+            let row = state.cursor.row;
+            let deleted_line = state.modify_rows(row..row + 1, |state| {
+                state.lines.remove(RowIndex::new(row)).iter().collect::<String>()
+            });
             state.clip.set_text(String::from('\n') + &deleted_line);
             state.cursor.col = 0;
             state.cursor.row = state.cursor.row.min(state.lines.len().saturating_sub(1));
@@ -126,12 +158,16 @@ impl Execute for DeleteToEndOfLine {
             return;
         }
         state.capture();
-        let Some(row) = state.lines.get_mut(RowIndex::new(state.cursor.row)) else {
-            return;
-        };
-        let deleted_chars = row.drain(state.cursor.col..);
-        state.cursor.col = state.cursor.col.saturating_sub(1);
-        state.clip.set_text(deleted_chars.collect());
+        let Index2 { row, col } = state.cursor;
+        let deleted_chars = state.modify_rows(row..row + 1, |state| {
+            let line = state.lines.get_mut(RowIndex::new(row))?;
+            let deleted_chars: String = line.drain(col..).collect();
+            state.cursor.col = col.saturating_sub(1);
+            Some(deleted_chars)
+        });
+        if let Some(deleted_chars) = deleted_chars {
+            state.clip.set_text(deleted_chars);
+        }
     }
 }
 
@@ -152,9 +188,18 @@ impl Execute for DeleteSelection {
 }
 
 pub(crate) fn delete_selection(state: &mut EditorState, selection: &Selection) -> Lines {
-    state.cursor = selection.start();
-    state.clamp_column();
-    selection.extract_from(&mut state.lines)
+    // Extracting may join the last selected row with the row below.
+    let rows = selection.start().row..selection.end().row + 2;
+    state.modify_rows(rows, |state| {
+        state.cursor = selection.start();
+        state.clamp_column();
+        let drained = selection.extract_from(&mut state.lines);
+        // Keep an empty row to insert into if all text was deleted.
+        if state.lines.is_empty() {
+            state.lines.push(Vec::new());
+        }
+        drained
+    })
 }
 
 /// Joins line below to the current line.
@@ -167,7 +212,8 @@ impl Execute for JoinLineWithLineBelow {
             return;
         }
         state.capture();
-        state.lines.join_lines(state.cursor.row);
+        let row = state.cursor.row;
+        state.modify_rows(row..row + 2, |state| state.lines.join_lines(row));
     }
 }
 
@@ -250,6 +296,28 @@ mod tests {
         assert_eq!(state.lines, Lines::from("\nb"));
     }
 
+    #[test]
+    fn test_delete_grapheme_clusters() {
+        let mut state = EditorState::new(Lines::from("ae\u{301}\u{1f1e9}\u{1f1ea}b"));
+
+        state.cursor = Index2::new(0, 1);
+        RemoveChar(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("a\u{1f1e9}\u{1f1ea}b"));
+
+        ReplaceChar('x').execute(&mut state);
+        assert_eq!(state.lines, Lines::from("axb"));
+
+        let mut state = EditorState::new(Lines::from("ae\u{301}\u{1f1e9}\u{1f1ea}b"));
+        state.mode = EditorMode::Insert;
+        state.cursor = Index2::new(0, 5);
+        DeleteChar(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 3));
+        assert_eq!(state.lines, Lines::from("ae\u{301}b"));
+        DeleteChar(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 1));
+        assert_eq!(state.lines, Lines::from("ab"));
+    }
+
     #[test]
     fn test_delete_line() {
         let mut state = test_state();
@@ -0,0 +1,28 @@
+use super::Execute;
+use crate::EditorState;
+
+/// Moves the cursor to the start of the next diagnostic, e.g. `]d`.
+#[derive(Clone, Debug)]
+pub struct NextDiagnostic;
+
+impl Execute for NextDiagnostic {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(position) = state.find_diagnostic(true) {
+            state.cursor = position;
+            state.clamp_column();
+        }
+    }
+}
+
+/// Moves the cursor to the start of the previous diagnostic, e.g. `[d`.
+#[derive(Clone, Debug)]
+pub struct PreviousDiagnostic;
+
+impl Execute for PreviousDiagnostic {
+    fn execute(&mut self, state: &mut EditorState) {
+        if let Some(position) = state.find_diagnostic(false) {
+            state.cursor = position;
+            state.clamp_column();
+        }
+    }
+}
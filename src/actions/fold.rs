@@ -0,0 +1,118 @@
+use super::{Action, Execute};
+use crate::{EditorMode, EditorState};
+
+/// Folds the lines the cursor moves over when executing a motion, e.g.
+/// `zfj`.
+#[derive(Clone, Debug)]
+pub struct CreateFoldMotion(pub Box<Action>);
+
+impl CreateFoldMotion {
+    #[must_use]
+    pub fn new<A: Into<Action>>(motion: A) -> Self {
+        Self(Box::new(motion.into()))
+    }
+}
+
+impl Execute for CreateFoldMotion {
+    fn execute(&mut self, state: &mut EditorState) {
+        let cursor = state.cursor;
+        self.0.execute(state);
+        let row = state.cursor.row;
+        state.cursor = cursor;
+
+        state.add_fold(row.min(cursor.row)..=row.max(cursor.row));
+    }
+}
+
+/// Folds the selected lines and switches to normal mode.
+#[derive(Clone, Debug)]
+pub struct CreateFoldSelection;
+
+impl Execute for CreateFoldSelection {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(selection) = state.selection.take() else {
+            return;
+        };
+        state.mode = EditorMode::Normal;
+        state.add_fold(selection.start().row..=selection.end().row);
+    }
+}
+
+/// Opens the closed fold at the cursor or closes the open fold at the
+/// cursor (`za`).
+#[derive(Clone, Debug)]
+pub struct ToggleFold;
+
+impl Execute for ToggleFold {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.toggle_fold();
+    }
+}
+
+/// Opens the fold at the cursor (`zo`).
+#[derive(Clone, Debug)]
+pub struct OpenFold;
+
+impl Execute for OpenFold {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.open_fold();
+    }
+}
+
+/// Closes the fold at the cursor (`zc`).
+#[derive(Clone, Debug)]
+pub struct CloseFold;
+
+impl Execute for CloseFold {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.close_fold();
+    }
+}
+
+/// Opens all folds (`zR`).
+#[derive(Clone, Debug)]
+pub struct OpenAllFolds;
+
+impl Execute for OpenAllFolds {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.set_all_folds_closed(false);
+    }
+}
+
+/// Closes all folds (`zM`).
+#[derive(Clone, Debug)]
+pub struct CloseAllFolds;
+
+impl Execute for CloseAllFolds {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.set_all_folds_closed(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{MoveDown, MoveToLastRow};
+    use crate::{Index2, Lines};
+
+    #[test]
+    fn test_create_and_toggle_folds() {
+        let mut state = EditorState::new(Lines::from("a\nb\nc\nd"));
+        state.cursor = Index2::new(1, 0);
+
+        CreateFoldMotion::new(MoveDown(1)).execute(&mut state);
+        assert_eq!(state.folds().len(), 1);
+        assert_eq!((state.folds()[0].start, state.folds()[0].end), (1, 2));
+        assert!(state.folds()[0].closed);
+
+        ToggleFold.execute(&mut state);
+        assert!(!state.folds()[0].closed);
+
+        state.cursor = Index2::new(0, 0);
+        CreateFoldMotion::new(MoveToLastRow()).execute(&mut state);
+        CloseAllFolds.execute(&mut state);
+        assert!(state.folds().iter().all(|fold| fold.closed));
+        OpenAllFolds.execute(&mut state);
+        assert!(state.folds().iter().all(|fold| !fold.closed));
+    }
+}
@@ -1,18 +1,27 @@
 use jagged::index::RowIndex;
 
-use super::{Execute, SwitchMode};
+use super::{delete::delete_selection, Execute, SwitchMode};
 use crate::{
     helper::{insert_char, line_break},
     EditorMode, EditorState,
 };
 
-/// Inserts a single character at the current cursor position
+/// Inserts a single character at the current cursor position. In insert
+/// mode, replaces the selection, e.g. a snippet placeholder.
 #[derive(Clone, Debug, Copy)]
 pub struct InsertChar(pub char);
 
 impl Execute for InsertChar {
     fn execute(&mut self, state: &mut EditorState) {
-        insert_char(&mut state.lines, &mut state.cursor, self.0, false);
+        if state.mode == EditorMode::Insert {
+            if let Some(selection) = state.selection.take() {
+                delete_selection(state, &selection);
+            }
+        }
+        let row = state.cursor.row;
+        state.modify_rows(row..row + 1, |state| {
+            insert_char(&mut state.lines, &mut state.cursor, self.0, false);
+        });
     }
 }
 
@@ -23,10 +32,13 @@ pub struct LineBreak(pub usize);
 impl Execute for LineBreak {
     fn execute(&mut self, state: &mut EditorState) {
         if state.lines.is_empty() {
-            state.lines.push(Vec::new());
+            state.modify_rows(0..0, |state| state.lines.push(Vec::new()));
         }
         for _ in 0..self.0 {
-            line_break(&mut state.lines, &mut state.cursor);
+            let row = state.cursor.row;
+            state.modify_rows(row..row + 1, |state| {
+                line_break(&mut state.lines, &mut state.cursor);
+            });
         }
     }
 }
@@ -44,11 +56,14 @@ impl Execute for AppendNewline {
             if !state.lines.is_empty() {
                 state.cursor.row += 1;
             }
-            if state.cursor.row < state.lines.len() {
-                state.lines.insert(RowIndex::new(state.cursor.row), vec![]);
-            } else {
-                state.lines.push(vec![]);
-            }
+            let row = state.cursor.row;
+            state.modify_rows(row..row, |state| {
+                if row < state.lines.len() {
+                    state.lines.insert(RowIndex::new(row), vec![]);
+                } else {
+                    state.lines.push(vec![]);
+                }
+            });
         }
     }
 }
@@ -62,8 +77,11 @@ impl Execute for InsertNewline {
     fn execute(&mut self, state: &mut EditorState) {
         SwitchMode(EditorMode::Insert).execute(state);
         state.cursor.col = 0;
+        let row = state.cursor.row;
         for _ in 0..self.0 {
-            state.lines.insert(RowIndex::new(state.cursor.row), vec![]);
+            state.modify_rows(row..row, |state| {
+                state.lines.insert(RowIndex::new(row), vec![]);
+            });
         }
     }
 }
@@ -76,7 +94,8 @@ pub struct PushLine<'a>(pub &'a str);
 impl Execute for PushLine<'_> {
     fn execute(&mut self, state: &mut EditorState) {
         let chars: Vec<char> = self.0.chars().collect();
-        state.lines.push(chars);
+        let len = state.lines.len();
+        state.modify_rows(len..len, |state| state.lines.push(chars));
     }
 }
 
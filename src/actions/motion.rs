@@ -6,20 +6,29 @@ use jagged::Index2;
 
 use super::Execute;
 use crate::{
-    helper::{max_col, max_col_normal, skip_whitespace, skip_whitespace_rev},
+    helper::{
+        grapheme_end, grapheme_start, max_col, max_col_normal, skip_whitespace, skip_whitespace_rev,
+    },
     EditorMode, EditorState,
 };
+use jagged::index::RowIndex;
 
 #[derive(Clone, Debug, Copy)]
 pub struct MoveForward(pub usize);
 
 impl Execute for MoveForward {
     fn execute(&mut self, state: &mut EditorState) {
+        let line = state.lines.get(RowIndex::new(state.cursor.row));
         for _ in 0..self.0 {
-            if state.cursor.col >= max_col(&state.lines, &state.cursor, state.mode) {
+            let max_col = max_col(&state.lines, &state.cursor, state.mode);
+            if state.cursor.col >= max_col {
                 break;
             }
-            state.cursor.col += 1;
+            // Step over the whole grapheme cluster.
+            let next = line.map_or(state.cursor.col + 1, |line| {
+                grapheme_end(line, state.cursor.col)
+            });
+            state.cursor.col = next.max(state.cursor.col + 1).min(max_col);
         }
         if state.mode == EditorMode::Visual {
             set_selection(&mut state.selection, state.cursor);
@@ -40,7 +49,11 @@ impl Execute for MoveBackward {
             if state.cursor.col > max_col {
                 state.cursor.col = max_col;
             }
-            state.cursor.col = state.cursor.col.saturating_sub(1);
+            let col = state.cursor.col.saturating_sub(1);
+            state.cursor.col = match state.lines.get(RowIndex::new(state.cursor.row)) {
+                Some(line) => grapheme_start(line, col),
+                None => col,
+            };
         }
         if state.mode == EditorMode::Visual {
             set_selection(&mut state.selection, state.cursor);
@@ -54,10 +67,12 @@ pub struct MoveUp(pub usize);
 impl Execute for MoveUp {
     fn execute(&mut self, state: &mut EditorState) {
         for _ in 0..self.0 {
-            if state.cursor.row == 0 {
+            // A closed fold is skipped as a whole.
+            let row = state.folds.fold_start(state.cursor.row);
+            if row == 0 {
                 break;
             }
-            state.cursor.row = state.cursor.row.saturating_sub(1);
+            state.cursor.row = state.folds.fold_start(row - 1);
         }
         if state.mode == EditorMode::Visual {
             set_selection(&mut state.selection, state.cursor);
@@ -71,10 +86,12 @@ pub struct MoveDown(pub usize);
 impl Execute for MoveDown {
     fn execute(&mut self, state: &mut EditorState) {
         for _ in 0..self.0 {
-            if state.cursor.row >= state.lines.len().saturating_sub(1) {
+            // A closed fold is skipped as a whole.
+            let row = state.folds.fold_end(state.cursor.row);
+            if row >= state.lines.len().saturating_sub(1) {
                 break;
             }
-            state.cursor.row += 1;
+            state.cursor.row = row + 1;
         }
         if state.mode == EditorMode::Visual {
             set_selection(&mut state.selection, state.cursor);
@@ -306,6 +323,63 @@ impl Execute for MoveToLastRow {
     }
 }
 
+// Move the cursor to the empty line behind the paragraph, or to the end of
+// the buffer, e.g. `}`.
+#[derive(Clone, Debug, Copy)]
+pub struct MoveParagraphForward(pub usize);
+
+impl Execute for MoveParagraphForward {
+    fn execute(&mut self, state: &mut EditorState) {
+        let is_empty = |row: usize| state.lines.len_col(row).unwrap_or(0) == 0;
+        let last_row = state.lines.len().saturating_sub(1);
+        for _ in 0..self.0 {
+            let mut row = state.cursor.row;
+            while row <= last_row && is_empty(row) {
+                row += 1;
+            }
+            while row <= last_row && !is_empty(row) {
+                row += 1;
+            }
+            state.cursor = if row <= last_row {
+                Index2::new(row, 0)
+            } else {
+                let mut end = Index2::new(last_row, 0);
+                end.col = max_col(&state.lines, &end, state.mode);
+                end
+            };
+        }
+
+        if state.mode == EditorMode::Visual {
+            set_selection(&mut state.selection, state.cursor);
+        }
+    }
+}
+
+// Move the cursor to the empty line in front of the paragraph, or to the
+// start of the buffer, e.g. `{`.
+#[derive(Clone, Debug, Copy)]
+pub struct MoveParagraphBackward(pub usize);
+
+impl Execute for MoveParagraphBackward {
+    fn execute(&mut self, state: &mut EditorState) {
+        let is_empty = |row: usize| state.lines.len_col(row).unwrap_or(0) == 0;
+        for _ in 0..self.0 {
+            let mut row = state.cursor.row;
+            while row > 0 && is_empty(row) {
+                row -= 1;
+            }
+            while row > 0 && !is_empty(row) {
+                row -= 1;
+            }
+            state.cursor = Index2::new(row, 0);
+        }
+
+        if state.mode == EditorMode::Visual {
+            set_selection(&mut state.selection, state.cursor);
+        }
+    }
+}
+
 // Move the cursor to the closing bracket.
 #[derive(Clone, Debug, Copy)]
 pub struct MoveToMatchinBracket();
@@ -370,6 +444,25 @@ mod tests {
         EditorState::new(Lines::from("Hello World!\n\n123."))
     }
 
+    #[test]
+    fn test_move_paragraph() {
+        let mut state = EditorState::new(Lines::from("a\nb\n\n\nc\n\nd"));
+
+        MoveParagraphForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(2, 0));
+        MoveParagraphForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(5, 0));
+        MoveParagraphForward(2).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(6, 0));
+
+        MoveParagraphBackward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(5, 0));
+        MoveParagraphBackward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(3, 0));
+        MoveParagraphBackward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
     #[test]
     fn test_move_forward() {
         let mut state = test_state();
@@ -399,6 +492,24 @@ mod tests {
         assert_eq!(state.cursor, Index2::new(0, 0));
     }
 
+    #[test]
+    fn test_move_over_grapheme_clusters() {
+        let text = "e\u{301}\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}x";
+        let mut state = EditorState::new(Lines::from(text));
+
+        MoveForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 2));
+        MoveForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 7));
+        MoveForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 7));
+
+        MoveBackward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 2));
+        MoveBackward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
     #[test]
     fn test_move_down() {
         let mut state = test_state();
@@ -0,0 +1,109 @@
+use jagged::index::RowIndex;
+
+use super::{Execute, SwitchMode};
+use crate::state::multi_cursor::{find_next_word, word_at};
+use crate::{EditorMode, EditorState, Index2};
+
+/// Adds a cursor on the next occurrence of the word under the most recently
+/// added cursor. The new cursor has the same offset into the word as the
+/// previous one.
+#[derive(Clone, Debug)]
+pub struct AddCursorOnNextMatch;
+
+impl Execute for AddCursorOnNextMatch {
+    fn execute(&mut self, state: &mut EditorState) {
+        let last = state.cursors.last().map_or(state.cursor, |c| c.cursor);
+        let Some((word, start)) = word_at(state, last) else {
+            return;
+        };
+        let offset = last.col - start;
+
+        let mut from = Index2::new(last.row, start);
+        while let Some(found) = find_next_word(state, &word, from) {
+            let cursor = Index2::new(found.row, found.col + offset);
+            if !state.cursors().contains(&cursor) {
+                state.add_cursor(cursor);
+                return;
+            }
+            if found.row == last.row && found.col == start {
+                return;
+            }
+            from = found;
+        }
+    }
+}
+
+/// Adds a cursor on every line of the visual selection, at the leftmost
+/// column of the selection, and switches to insert mode.
+#[derive(Clone, Debug)]
+pub struct AddCursorsToSelection;
+
+impl Execute for AddCursorsToSelection {
+    fn execute(&mut self, state: &mut EditorState) {
+        let Some(selection) = state.selection.take() else {
+            return;
+        };
+        let (start, end) = (selection.start(), selection.end());
+        let col = start.col.min(end.col);
+
+        state.mode = EditorMode::Normal;
+        state.clear_cursors();
+        for row in start.row..=end.row {
+            let len = state.lines.get(RowIndex::new(row)).map_or(0, Vec::len);
+            let cursor = Index2::new(row, col.min(len));
+            if row == start.row {
+                state.cursor = cursor;
+            } else {
+                state.add_cursor(cursor);
+            }
+        }
+        SwitchMode(EditorMode::Insert).execute(state);
+    }
+}
+
+/// Removes all cursors except the primary cursor.
+#[derive(Clone, Debug)]
+pub struct RemoveSecondaryCursors;
+
+impl Execute for RemoveSecondaryCursors {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.clear_cursors();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::InsertChar;
+    use crate::state::selection::Selection;
+    use crate::Lines;
+
+    #[test]
+    fn test_add_cursor_on_next_match() {
+        let mut state = EditorState::new(Lines::from("let foo = foobar(foo);"));
+        state.cursor = Index2::new(0, 5);
+
+        AddCursorOnNextMatch.execute(&mut state);
+        assert_eq!(state.cursors(), vec![Index2::new(0, 5), Index2::new(0, 18)]);
+
+        AddCursorOnNextMatch.execute(&mut state);
+        assert_eq!(state.cursors(), vec![Index2::new(0, 5), Index2::new(0, 18)]);
+    }
+
+    #[test]
+    fn test_add_cursors_to_selection() {
+        let mut state = EditorState::new(Lines::from("abc\nd\nefg"));
+        state.mode = EditorMode::Visual;
+        state.selection = Some(Selection::new(Index2::new(2, 2), Index2::new(0, 1)));
+
+        AddCursorsToSelection.execute(&mut state);
+        assert_eq!(state.mode, EditorMode::Insert);
+        assert_eq!(
+            state.cursors(),
+            vec![Index2::new(0, 1), Index2::new(1, 1), Index2::new(2, 1)]
+        );
+
+        state.for_each_cursor(|state| InsertChar('-').execute(state));
+        assert_eq!(state.lines, Lines::from("a-bc\nd-\ne-fg"));
+    }
+}
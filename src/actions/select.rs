@@ -89,6 +89,7 @@ impl Execute for ChangeInnerBetween {
             state.capture();
             let deleted = delete_selection(state, &selection);
             state.clip.set_text(deleted.into());
+            state.begin_insert_session();
             state.mode = EditorMode::Insert;
         }
     }
@@ -109,6 +110,67 @@ impl Execute for SelectLine {
     }
 }
 
+/// Selects the paragraph under the cursor, e.g. `ip` and `ap`.
+///
+/// A paragraph is a run of non-empty lines, or a run of empty lines when the
+/// cursor is on an empty line. The `around` variant also selects the empty
+/// lines after the paragraph, or the ones before it if there are none after.
+#[derive(Clone, Debug, Copy)]
+pub struct SelectParagraph {
+    around: bool,
+}
+
+impl SelectParagraph {
+    #[must_use]
+    pub fn inner() -> Self {
+        Self { around: false }
+    }
+
+    #[must_use]
+    pub fn around() -> Self {
+        Self { around: true }
+    }
+}
+
+impl Execute for SelectParagraph {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.lines.is_empty() {
+            return;
+        }
+        let is_empty = |row: usize| state.lines.len_col(row).unwrap_or(0) == 0;
+        let last_row = state.lines.len().saturating_sub(1);
+        let row = state.cursor.row.min(last_row);
+        let empty = is_empty(row);
+
+        let mut start = row;
+        while start > 0 && is_empty(start - 1) == empty {
+            start -= 1;
+        }
+        let mut end = row;
+        while end < last_row && is_empty(end + 1) == empty {
+            end += 1;
+        }
+
+        if self.around {
+            if end < last_row {
+                end += 1;
+                while end < last_row && is_empty(end + 1) != empty {
+                    end += 1;
+                }
+            } else {
+                while start > 0 && is_empty(start - 1) != empty {
+                    start -= 1;
+                }
+            }
+        }
+
+        let end = Index2::new(end, state.lines.len_col(end).unwrap_or(0).saturating_sub(1));
+        state.selection = Some(Selection::new(Index2::new(start, 0), end));
+        state.cursor = end;
+        state.mode = EditorMode::Visual;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::state::selection::Selection;
@@ -136,4 +198,31 @@ mod tests {
         assert_eq!(state.mode, EditorMode::Visual);
         assert_eq!(state.cursor, Index2::new(0, 4));
     }
+
+    #[test]
+    fn test_select_paragraph() {
+        let mut state = EditorState::new(Lines::from("a\nbc\n\n\nd"));
+
+        state.cursor = Index2::new(1, 0);
+        SelectParagraph::inner().execute(&mut state);
+        assert_eq!(
+            state.selection,
+            Some(Selection::new(Index2::new(0, 0), Index2::new(1, 1)))
+        );
+        assert_eq!(state.mode, EditorMode::Visual);
+
+        state.cursor = Index2::new(0, 0);
+        SelectParagraph::around().execute(&mut state);
+        assert_eq!(
+            state.selection,
+            Some(Selection::new(Index2::new(0, 0), Index2::new(3, 0)))
+        );
+
+        state.cursor = Index2::new(4, 0);
+        SelectParagraph::around().execute(&mut state);
+        assert_eq!(
+            state.selection,
+            Some(Selection::new(Index2::new(2, 0), Index2::new(4, 0)))
+        );
+    }
 }
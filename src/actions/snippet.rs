@@ -0,0 +1,34 @@
+use super::Execute;
+use crate::{EditorMode, EditorState};
+
+/// Expands the snippet whose trigger is in front of the cursor.
+#[derive(Clone, Debug)]
+pub struct ExpandSnippet;
+
+impl Execute for ExpandSnippet {
+    fn execute(&mut self, state: &mut EditorState) {
+        if state.mode == EditorMode::Insert {
+            state.expand_snippet();
+        }
+    }
+}
+
+/// Jumps to the next tabstop of the snippet that is edited.
+#[derive(Clone, Debug)]
+pub struct NextTabstop;
+
+impl Execute for NextTabstop {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.next_tabstop();
+    }
+}
+
+/// Jumps to the previous tabstop of the snippet that is edited.
+#[derive(Clone, Debug)]
+pub struct PreviousTabstop;
+
+impl Execute for PreviousTabstop {
+    fn execute(&mut self, state: &mut EditorState) {
+        state.previous_tabstop();
+    }
+}
@@ -0,0 +1,152 @@
+//! Completion of words in insert mode
+//!
+//! In insert mode, `<ctrl>+n` and `<ctrl>+p` open a popup with completion
+//! items for the word in front of the cursor. While the popup is open,
+//! `<ctrl>+n`, `<ctrl>+p` and `Tab` select an item, `Enter` inserts it and
+//! typing further characters narrows down the items.
+//!
+//! The items are provided by a [`CompletionProvider`]. By default, the
+//! [`BufferWords`] provider offers the words of the current buffer. The
+//! registered [snippets](crate::snippet) are offered in addition.
+//!
+//! ## Example: A custom provider
+//!
+//! ```
+//! use edtui::completion::{CompletionItem, CompletionProvider};
+//! use edtui::{EditorState, Index2, Lines};
+//!
+//! struct Keywords;
+//!
+//! impl CompletionProvider for Keywords {
+//!     fn complete(&mut self, _: &Lines, _: Index2, prefix: &str) -> Vec<CompletionItem> {
+//!         ["fn", "for", "let", "loop"]
+//!             .into_iter()
+//!             .filter(|keyword| keyword.starts_with(prefix))
+//!             .map(|keyword| CompletionItem::new(keyword).detail("keyword"))
+//!             .collect()
+//!     }
+//! }
+//!
+//! let mut state = EditorState::default();
+//! state.set_completion_provider(Keywords);
+//! ```
+use jagged::index::RowIndex;
+
+use crate::helper::is_word_char;
+use crate::{Index2, Lines};
+
+/// An item in the completion popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionItem {
+    /// The text displayed in the popup.
+    pub label: String,
+    /// Additional information displayed next to the label.
+    pub detail: Option<String>,
+    /// The text that replaces the word in front of the cursor.
+    pub insert_text: String,
+    /// Whether the insert text is a [`Snippet`](crate::snippet::Snippet)
+    /// body.
+    pub is_snippet: bool,
+}
+
+impl CompletionItem {
+    /// Creates a new completion item that inserts its label.
+    #[must_use]
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        let label = label.into();
+        Self {
+            insert_text: label.clone(),
+            label,
+            detail: None,
+            is_snippet: false,
+        }
+    }
+
+    /// Sets the detail displayed next to the label.
+    #[must_use]
+    pub fn detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the text that is inserted if the item is accepted.
+    #[must_use]
+    pub fn insert_text<S: Into<String>>(mut self, insert_text: S) -> Self {
+        self.insert_text = insert_text.into();
+        self
+    }
+
+    /// Sets a snippet body that is expanded if the item is accepted. An
+    /// invalid snippet body is inserted as is.
+    #[must_use]
+    pub fn snippet<S: Into<String>>(mut self, body: S) -> Self {
+        self.insert_text = body.into();
+        self.is_snippet = true;
+        self
+    }
+}
+
+/// Trait defining a source of completion items.
+pub trait CompletionProvider {
+    /// Returns the completion items for the word `prefix` that ends at the
+    /// `cursor`. The prefix may be empty.
+    fn complete(&mut self, lines: &Lines, cursor: Index2, prefix: &str) -> Vec<CompletionItem>;
+}
+
+/// Offers the words of the current buffer that start with the prefix, in
+/// the order of their first occurrence.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BufferWords;
+
+impl CompletionProvider for BufferWords {
+    fn complete(&mut self, lines: &Lines, cursor: Index2, prefix: &str) -> Vec<CompletionItem> {
+        let mut words: Vec<String> = Vec::new();
+        for (row, line) in lines.iter_row().enumerate() {
+            let mut start = 0;
+            for word in line.split(|&ch| !is_word_char(ch)) {
+                let end = start + word.len();
+                // Skip the word that is being completed.
+                let at_cursor = row == cursor.row && (start..=end).contains(&cursor.col);
+                start = end + 1;
+                if word.is_empty() || at_cursor {
+                    continue;
+                }
+                let word: String = word.iter().collect();
+                if word.starts_with(prefix) && !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        words.into_iter().map(CompletionItem::new).collect()
+    }
+}
+
+/// Returns the word in front of `cursor`.
+pub(crate) fn prefix_at(lines: &Lines, cursor: Index2) -> String {
+    let Some(line) = lines.get(RowIndex::new(cursor.row)) else {
+        return String::new();
+    };
+    let end = cursor.col.min(line.len());
+    let start = line[..end]
+        .iter()
+        .rposition(|&ch| !is_word_char(ch))
+        .map_or(0, |i| i + 1);
+    line[start..end].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_words() {
+        let lines = Lines::from("foo bar foobar\nfo baz foo");
+        let cursor = Index2::new(1, 2);
+        let prefix = prefix_at(&lines, cursor);
+        assert_eq!(prefix, "fo");
+
+        let items = BufferWords.complete(&lines, cursor, &prefix);
+        let labels: Vec<_> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["foo", "foobar"]);
+    }
+}
@@ -2,15 +2,28 @@ use crate::actions::cpaste::PasteOverSelection;
 use crate::actions::delete::DeleteToEndOfLine;
 use crate::actions::motion::{MoveToFirstRow, MoveToLastRow};
 use crate::actions::search::StartSearch;
+use crate::actions::PreviousTabstop;
+use crate::actions::{AcceptCompletion, NextCompletion, PreviousCompletion};
 use crate::actions::{
     Action, Append, AppendCharToSearch, AppendNewline, ChangeInnerBetween, Composed, CopyLine,
     CopySelection, DeleteChar, DeleteLine, DeleteSelection, Execute, FindNext, FindPrevious,
     InsertChar, InsertNewline, JoinLineWithLineBelow, LineBreak, MoveBackward, MoveDown,
-    MoveForward, MoveToEndOfLine, MoveToFirst, MoveToMatchinBracket, MoveToStartOfLine, MoveUp,
-    MoveWordBackward, MoveWordForward, MoveWordForwardToEndOfWord, Paste, Redo, RemoveChar,
-    RemoveCharFromSearch, SelectInnerBetween, SelectLine, StopSearch, SwitchMode, TriggerSearch,
-    Undo,
+    MoveForward, MoveParagraphBackward, MoveParagraphForward, MoveToEndOfLine, MoveToFirst,
+    MoveToMatchinBracket, MoveToStartOfLine, MoveUp, MoveWordBackward, MoveWordForward,
+    MoveWordForwardToEndOfWord, Paste, Redo, RemoveChar, RemoveCharFromSearch, SelectInnerBetween,
+    SelectLine, SelectParagraph, StopSearch, SwitchMode, TriggerSearch, Undo,
 };
+use crate::actions::{AddCursorOnNextMatch, AddCursorsToSelection, RemoveSecondaryCursors};
+use crate::actions::{
+    AppendCharToCommand, ExecuteCommand, RemoveCharFromCommand, StartCommand, StopCommand,
+    UndoNewer, UndoOlder,
+};
+use crate::actions::{
+    CloseAllFolds, CloseFold, CreateFoldMotion, CreateFoldSelection, OpenAllFolds, OpenFold,
+    ToggleFold,
+};
+use crate::actions::{NextDiagnostic, PreviousDiagnostic};
+use crate::actions::{ToggleComment, ToggleCommentMotion, ToggleCommentSelection};
 use crate::{EditorMode, EditorState};
 use ratatui::crossterm::event::{KeyCode, KeyEvent as CTKeyEvent, KeyModifiers};
 use std::collections::HashMap;
@@ -27,6 +40,7 @@ pub enum KeyEvent {
     Esc,
     Backspace,
     Tab,
+    BackTab,
     Ctrl(char),
     None,
 }
@@ -50,6 +64,7 @@ impl From<CTKeyEvent> for KeyEvent {
             KeyCode::Esc => KeyEvent::Esc,
             KeyCode::Backspace => KeyEvent::Backspace,
             KeyCode::Tab => KeyEvent::Tab,
+            KeyCode::BackTab => KeyEvent::BackTab,
             _ => KeyEvent::None,
         }
     }
@@ -59,6 +74,47 @@ impl From<CTKeyEvent> for KeyEvent {
 pub struct KeyEventHandler {
     lookup: Vec<KeyEvent>,
     register: HashMap<KeyEventRegister, Action>,
+    operators: HashMap<KeyEventRegister, Operator>,
+    pending: Option<PendingOperator>,
+}
+
+/// An operator that acts on the lines covered by a motion or a text object,
+/// e.g. the `gc` in `gcj` or `gcip`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Operator {
+    /// Toggles the comments of the lines.
+    ToggleComment,
+}
+
+impl Operator {
+    /// The action for repeating the last key of the operator, e.g. `gcc`.
+    fn lines(self, count: usize) -> Action {
+        match self {
+            Self::ToggleComment => ToggleComment(count).into(),
+        }
+    }
+
+    /// The action for applying the operator to a motion or text object.
+    fn apply(self, target: Action, count: usize) -> Action {
+        let target = if count > 1 {
+            Composed(vec![target; count]).into()
+        } else {
+            target
+        };
+        match self {
+            Self::ToggleComment => ToggleCommentMotion(Box::new(target)).into(),
+        }
+    }
+}
+
+/// An operator waiting for its count and motion.
+#[derive(Clone, Debug)]
+struct PendingOperator {
+    operator: Operator,
+    last_key: KeyEvent,
+    count: Option<usize>,
+    motion: Vec<KeyEvent>,
 }
 
 impl Default for KeyEventHandler {
@@ -278,6 +334,23 @@ impl Default for KeyEventHandler {
                 KeyEventRegister::v(vec![KeyEvent::Char('%')]),
                 MoveToMatchinBracket().into(),
             ),
+            // Move cursor to the next/previous paragraph
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('}')]),
+                MoveParagraphForward(1).into(),
+            ),
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('}')]),
+                MoveParagraphForward(1).into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('{')]),
+                MoveParagraphBackward(1).into(),
+            ),
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('{')]),
+                MoveParagraphBackward(1).into(),
+            ),
             // Append/insert new line and switch into insert mode
             (
                 KeyEventRegister::n(vec![KeyEvent::Char('o')]),
@@ -363,6 +436,15 @@ impl Default for KeyEventHandler {
                 KeyEventRegister::v(vec![KeyEvent::Char('i'), KeyEvent::Char(']')]),
                 SelectInnerBetween::new('[', ']').into(),
             ),
+            // Select the paragraph under the cursor
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('i'), KeyEvent::Char('p')]),
+                SelectParagraph::inner().into(),
+            ),
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('a'), KeyEvent::Char('p')]),
+                SelectParagraph::around().into(),
+            ),
             // Change inner word between delimiters
             (
                 KeyEventRegister::n(vec![
@@ -445,6 +527,141 @@ impl Default for KeyEventHandler {
             (KeyEventRegister::n(vec![KeyEvent::Char('u')]), Undo.into()),
             // Redo
             (KeyEventRegister::n(vec![KeyEvent::Ctrl('r')]), Redo.into()),
+            // Go to older/newer text state across undo branches
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('g'), KeyEvent::Char('-')]),
+                UndoOlder.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('g'), KeyEvent::Char('+')]),
+                UndoNewer.into(),
+            ),
+            // Go into command mode
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char(':')]),
+                StartCommand.into(),
+            ),
+            // Execute the command
+            (
+                KeyEventRegister::c(vec![KeyEvent::Enter]),
+                ExecuteCommand.into(),
+            ),
+            // Leave command mode
+            (KeyEventRegister::c(vec![KeyEvent::Esc]), StopCommand.into()),
+            // Delete last character from the command line
+            (
+                KeyEventRegister::c(vec![KeyEvent::Backspace]),
+                RemoveCharFromCommand.into(),
+            ),
+            // Open the completion popup or select the next/previous item
+            (
+                KeyEventRegister::i(vec![KeyEvent::Ctrl('n')]),
+                NextCompletion.into(),
+            ),
+            (
+                KeyEventRegister::i(vec![KeyEvent::Ctrl('p')]),
+                PreviousCompletion.into(),
+            ),
+            // Toggle the comments of the selected lines, `gc{motion}` is an operator
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('g'), KeyEvent::Char('c')]),
+                ToggleCommentSelection.into(),
+            ),
+            // Folds
+            (
+                KeyEventRegister::n(vec![
+                    KeyEvent::Char('z'),
+                    KeyEvent::Char('f'),
+                    KeyEvent::Char('j'),
+                ]),
+                CreateFoldMotion::new(MoveDown(1)).into(),
+            ),
+            (
+                KeyEventRegister::n(vec![
+                    KeyEvent::Char('z'),
+                    KeyEvent::Char('f'),
+                    KeyEvent::Char('k'),
+                ]),
+                CreateFoldMotion::new(MoveUp(1)).into(),
+            ),
+            (
+                KeyEventRegister::n(vec![
+                    KeyEvent::Char('z'),
+                    KeyEvent::Char('f'),
+                    KeyEvent::Char('g'),
+                    KeyEvent::Char('g'),
+                ]),
+                CreateFoldMotion::new(MoveToFirstRow()).into(),
+            ),
+            (
+                KeyEventRegister::n(vec![
+                    KeyEvent::Char('z'),
+                    KeyEvent::Char('f'),
+                    KeyEvent::Char('G'),
+                ]),
+                CreateFoldMotion::new(MoveToLastRow()).into(),
+            ),
+            (
+                KeyEventRegister::n(vec![
+                    KeyEvent::Char('z'),
+                    KeyEvent::Char('f'),
+                    KeyEvent::Char('%'),
+                ]),
+                CreateFoldMotion::new(MoveToMatchinBracket()).into(),
+            ),
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('z'), KeyEvent::Char('f')]),
+                CreateFoldSelection.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('z'), KeyEvent::Char('a')]),
+                ToggleFold.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('z'), KeyEvent::Char('o')]),
+                OpenFold.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('z'), KeyEvent::Char('c')]),
+                CloseFold.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('z'), KeyEvent::Char('R')]),
+                OpenAllFolds.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('z'), KeyEvent::Char('M')]),
+                CloseAllFolds.into(),
+            ),
+            // Jump to the next/previous diagnostic
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char(']'), KeyEvent::Char('d')]),
+                NextDiagnostic.into(),
+            ),
+            (
+                KeyEventRegister::n(vec![KeyEvent::Char('['), KeyEvent::Char('d')]),
+                PreviousDiagnostic.into(),
+            ),
+            // Jump to the previous tabstop of a snippet
+            (
+                KeyEventRegister::i(vec![KeyEvent::BackTab]),
+                PreviousTabstop.into(),
+            ),
+            // Add a cursor on the next match of the word under the cursor
+            (
+                KeyEventRegister::n(vec![KeyEvent::Ctrl('n')]),
+                AddCursorOnNextMatch.into(),
+            ),
+            // Add a cursor on every line of the selection
+            (
+                KeyEventRegister::v(vec![KeyEvent::Char('I')]),
+                AddCursorsToSelection.into(),
+            ),
+            // Remove all but the primary cursor
+            (
+                KeyEventRegister::n(vec![KeyEvent::Esc]),
+                RemoveSecondaryCursors.into(),
+            ),
             // Copy
             (
                 KeyEventRegister::v(vec![KeyEvent::Char('y')]),
@@ -462,9 +679,16 @@ impl Default for KeyEventHandler {
             ),
         ]);
 
+        let operators = HashMap::from([(
+            KeyEventRegister::n(vec![KeyEvent::Char('g'), KeyEvent::Char('c')]),
+            Operator::ToggleComment,
+        )]);
+
         Self {
             lookup: Vec::new(),
             register,
+            operators,
+            pending: None,
         }
     }
 }
@@ -476,9 +700,17 @@ impl KeyEventHandler {
         Self {
             lookup: Vec::new(),
             register,
+            operators: HashMap::new(),
+            pending: None,
         }
     }
 
+    /// Insert a new operator to the registry. An operator is followed by
+    /// an optional count and a motion or text object, e.g. `gc3j`.
+    pub fn insert_operator(&mut self, key: KeyEventRegister, operator: Operator) {
+        self.operators.insert(key, operator);
+    }
+
     /// Insert a new callback to the registry
     pub fn insert<T>(&mut self, key: KeyEventRegister, action: T)
     where
@@ -503,14 +735,20 @@ impl KeyEventHandler {
     /// is appended to the lookup vector.
     /// If there is an exact match or if none of the keys in the registry
     /// starts with the current sequence, the lookup sequence is reset.
+    /// An exact match of an operator waits for its motion.
     #[must_use]
     fn get(&mut self, c: KeyEvent, mode: EditorMode) -> Option<Action> {
+        if self.pending.is_some() {
+            return self.resolve_operator(c);
+        }
+
         self.lookup.push(c);
         let key = KeyEventRegister::new(self.lookup.clone(), mode);
 
         match self
             .register
             .keys()
+            .chain(self.operators.keys())
             .filter(|k| k.mode == key.mode && k.keys.starts_with(&key.keys))
             .count()
         {
@@ -518,13 +756,80 @@ impl KeyEventHandler {
                 self.lookup.clear();
                 None
             }
-            1 => self.register.get(&key).map(|action| {
-                self.lookup.clear();
-                action.clone()
-            }),
+            1 => {
+                if let Some(&operator) = self.operators.get(&key) {
+                    self.lookup.clear();
+                    self.pending = Some(PendingOperator {
+                        operator,
+                        last_key: c,
+                        count: None,
+                        motion: Vec::new(),
+                    });
+                    return None;
+                }
+                self.register.get(&key).map(|action| {
+                    self.lookup.clear();
+                    action.clone()
+                })
+            }
             _ => None,
         }
     }
+
+    /// Resolves the count and the motion or text object of a pending
+    /// operator. Motions are looked up in normal mode and text objects
+    /// in visual mode. The operator is cancelled on any other key.
+    fn resolve_operator(&mut self, c: KeyEvent) -> Option<Action> {
+        let mut pending = self.pending.take()?;
+
+        if pending.motion.is_empty() {
+            if let KeyEvent::Char(digit @ '0'..='9') = c {
+                if digit != '0' || pending.count.is_some() {
+                    let digit = digit.to_digit(10).unwrap_or_default() as usize;
+                    let count = pending.count.unwrap_or_default();
+                    pending.count = Some(count.saturating_mul(10).saturating_add(digit));
+                    self.pending = Some(pending);
+                    return None;
+                }
+            }
+            if c == pending.last_key {
+                let count = pending.count.unwrap_or(1);
+                return Some(pending.operator.lines(count));
+            }
+        }
+
+        pending.motion.push(c);
+        let targets = self
+            .register
+            .iter()
+            .filter(|(k, action)| match k.mode {
+                EditorMode::Normal => action.is_motion(),
+                EditorMode::Visual => action.is_text_object(),
+                _ => false,
+            })
+            .filter(|(k, _)| k.keys.starts_with(&pending.motion));
+
+        let mut exact = None;
+        let mut matches = 0;
+        for (k, action) in targets {
+            matches += 1;
+            if k.keys == pending.motion {
+                exact = Some(action);
+            }
+        }
+
+        match (matches, exact) {
+            (1, Some(target)) => {
+                let count = pending.count.unwrap_or(1);
+                Some(pending.operator.apply(target.clone(), count))
+            }
+            (0, _) => None,
+            _ => {
+                self.pending = Some(pending);
+                None
+            }
+        }
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
@@ -576,6 +881,13 @@ impl KeyEventRegister {
     {
         Self::new(key.into(), EditorMode::Search)
     }
+
+    pub fn c<T>(key: T) -> Self
+    where
+        T: Into<Vec<KeyEvent>>,
+    {
+        Self::new(key.into(), EditorMode::Command)
+    }
 }
 
 impl KeyEventHandler {
@@ -584,19 +896,163 @@ impl KeyEventHandler {
         T: Into<KeyEvent> + Copy,
     {
         let mode = state.mode;
+        let completion_open = state.completion.is_open();
+        // Enter accepts the selected item, without a selection it closes the
+        // popup and breaks the line.
+        let accept_completion = completion_open && state.completion.selected_item().is_some();
+
+        // Moving the cursor in insert mode deselects snippet placeholders
+        if mode == EditorMode::Insert
+            && matches!(
+                key.into(),
+                KeyEvent::Up | KeyEvent::Down | KeyEvent::Left | KeyEvent::Right
+            )
+        {
+            state.selection = None;
+            for cursor in &mut state.cursors {
+                cursor.selection = None;
+            }
+        }
 
         match key.into() {
+            // Navigate and accept items of the completion popup
+            KeyEvent::Tab if completion_open => NextCompletion.execute(state),
+            KeyEvent::Enter if accept_completion => AcceptCompletion.execute(state),
             // Always insert characters in insert mode
-            KeyEvent::Char(c) if mode == EditorMode::Insert => InsertChar(c).execute(state),
-            KeyEvent::Tab if mode == EditorMode::Insert => InsertChar('\t').execute(state),
+            KeyEvent::Char(c) if mode == EditorMode::Insert => {
+                state.for_each_cursor(|state| InsertChar(c).execute(state));
+            }
+            // Expand a snippet or jump to the next tabstop before inserting a tab
+            KeyEvent::Tab if mode == EditorMode::Insert => {
+                if !state.expand_snippet() && !state.next_tabstop() {
+                    state.for_each_cursor(|state| InsertChar('\t').execute(state));
+                }
+            }
             // Always add characters to search in search mode
             KeyEvent::Char(c) if mode == EditorMode::Search => AppendCharToSearch(c).execute(state),
+            // Always add characters to the command line in command mode
+            KeyEvent::Char(c) if mode == EditorMode::Command => {
+                AppendCharToCommand(c).execute(state);
+            }
             // Else lookup an action from the register
             _ => {
                 if let Some(mut action) = self.get(key.into(), mode) {
-                    action.execute(state);
+                    if action.is_per_cursor() {
+                        state.for_each_cursor(|state| action.execute(state));
+                    } else {
+                        action.execute(state);
+                    }
                 }
             }
         }
+
+        state.snap_cursors_to_graphemes();
+
+        // Update the completion items after the word in front of the cursor
+        // changed, close the popup on any other key.
+        if completion_open {
+            match key.into() {
+                KeyEvent::Char(_) | KeyEvent::Backspace => state.update_completion(),
+                KeyEvent::Tab | KeyEvent::BackTab | KeyEvent::Ctrl('n' | 'p') => {}
+                KeyEvent::Enter if accept_completion => {}
+                _ => state.completion.close(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Index2, Lines};
+
+    fn on_keys(state: &mut EditorState, keys: &[KeyEvent]) {
+        let mut handler = KeyEventHandler::default();
+        for &key in keys {
+            handler.on_event(key, state);
+        }
+    }
+
+    fn chars(text: &str) -> Vec<KeyEvent> {
+        text.chars().map(KeyEvent::Char).collect()
+    }
+
+    #[test]
+    fn test_enter_accepts_selected_completion() {
+        let mut state = EditorState::new(Lines::from("foobar\n"));
+        state.cursor = Index2::new(1, 0);
+        on_keys(&mut state, &[KeyEvent::Char('i')]);
+        on_keys(&mut state, &chars("fo"));
+        on_keys(&mut state, &[KeyEvent::Ctrl('n'), KeyEvent::Enter]);
+        assert_eq!(state.lines, Lines::from("foobar\nfoobar"));
+        assert!(!state.completion.is_open());
+    }
+
+    #[test]
+    fn test_enter_without_selected_completion_breaks_line() {
+        let mut state = EditorState::new(Lines::from("foobar fooqux\n"));
+        state.cursor = Index2::new(1, 0);
+        on_keys(&mut state, &[KeyEvent::Char('i')]);
+        on_keys(&mut state, &chars("foo"));
+        on_keys(&mut state, &[KeyEvent::Ctrl('n')]);
+        assert_eq!(state.completion.selected_item().unwrap().label, "foobar");
+        // The selected item no longer matches and is deselected.
+        on_keys(&mut state, &[KeyEvent::Char('q')]);
+        assert!(state.completion.is_open());
+        assert!(state.completion.selected_item().is_none());
+
+        on_keys(&mut state, &[KeyEvent::Enter]);
+        assert_eq!(state.lines, Lines::from("foobar fooqux\nfooq\n"));
+        assert!(!state.completion.is_open());
+    }
+
+    fn comment_state(text: &str) -> EditorState {
+        let mut state = EditorState::new(Lines::from(text));
+        state.set_comment_tokens(Some(crate::CommentTokens::line("//")));
+        state
+    }
+
+    #[test]
+    fn test_toggle_comment_lines() {
+        let mut state = comment_state("a\nb\nc");
+        on_keys(&mut state, &chars("gcc"));
+        assert_eq!(state.lines, Lines::from("// a\nb\nc"));
+
+        on_keys(&mut state, &chars("gc2c"));
+        assert_eq!(state.lines, Lines::from("// // a\n// b\nc"));
+    }
+
+    #[test]
+    fn test_toggle_comment_with_count() {
+        let mut state = comment_state("a\nb\nc\nd\ne");
+        on_keys(&mut state, &chars("gc3j"));
+        assert_eq!(state.lines, Lines::from("// a\n// b\n// c\n// d\ne"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_toggle_comment_paragraph() {
+        let mut state = comment_state("a\nb\n\nc");
+        state.cursor = Index2::new(1, 0);
+        on_keys(&mut state, &chars("gcip"));
+        assert_eq!(state.lines, Lines::from("// a\n// b\n\nc"));
+        assert_eq!(state.mode, EditorMode::Normal);
+
+        state.cursor = Index2::new(0, 0);
+        on_keys(&mut state, &chars("gc}"));
+        assert_eq!(state.lines, Lines::from("a\nb\n\nc"));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+    }
+
+    #[test]
+    fn test_toggle_comment_cancelled() {
+        let mut state = comment_state("a\nb");
+        on_keys(
+            &mut state,
+            &[KeyEvent::Char('g'), KeyEvent::Char('c'), KeyEvent::Esc],
+        );
+        on_keys(&mut state, &chars("j"));
+        assert_eq!(state.lines, Lines::from("a\nb"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
     }
 }
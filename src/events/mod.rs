@@ -3,7 +3,7 @@ mod key;
 #[cfg(feature = "mouse-support")]
 pub(crate) mod mouse;
 
-pub use key::{KeyEvent, KeyEventHandler, KeyEventRegister};
+pub use key::{KeyEvent, KeyEventHandler, KeyEventRegister, Operator};
 
 #[cfg(feature = "mouse-support")]
 pub use mouse::{MouseEvent, MouseEventHandler};
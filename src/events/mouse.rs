@@ -1,11 +1,18 @@
-use jagged::Index2;
-use ratatui::crossterm::event::{MouseEvent as CTMouseEvent, MouseEventKind};
+use jagged::{index::RowIndex, Index2};
+use ratatui::{
+    crossterm::event::{KeyModifiers, MouseEvent as CTMouseEvent, MouseEventKind},
+    layout::Position,
+};
 
 use crate::{
     actions::{Execute, SwitchMode},
-    helper::char_width,
-    state::selection::set_selection,
-    view::line_wrapper::LineWrapper,
+    helper::grapheme_widths,
+    state::inlay_hint::{data_col, display_line, visible_inlay_hints},
+    state::{selection::set_selection, view::ScrollDrag},
+    view::{
+        line_wrapper::{LineWrapper, WrappedRow},
+        scrollbar,
+    },
     EditorMode, EditorState,
 };
 
@@ -23,8 +30,23 @@ impl MouseEventHandler {
             return;
         }
 
+        if scroll_with_mouse(event, state) {
+            return;
+        }
+
+        if let MouseEvent::AddCursor(mouse) = event {
+            let cursor = mouse_position_to_cursor_position(state, &mouse, state.view.tab_width);
+            let last_row = state.lines.last_row_index();
+            if cursor.row <= last_row {
+                let last_col = state.lines.last_col_index(cursor.row);
+                state.add_cursor(Index2::new(cursor.row, cursor.col.min(last_col)));
+            }
+            return;
+        }
+
         if let MouseEvent::Down(_) = event {
             state.selection = None;
+            state.clear_cursors();
             if state.mode == EditorMode::Visual {
                 SwitchMode(EditorMode::Normal).execute(state);
             }
@@ -59,11 +81,59 @@ impl MouseEventHandler {
                     set_selection(&mut state.selection, state.cursor);
                 }
             }
-            MouseEvent::None => (),
+            MouseEvent::AddCursor(_) | MouseEvent::None => (),
         };
     }
 }
 
+/// Scrolls the editor if the mouse presses or drags the scrollbar or the
+/// minimap. Returns whether the event was handled.
+fn scroll_with_mouse(event: MouseEvent, state: &mut EditorState) -> bool {
+    let mouse = match event {
+        MouseEvent::Down(mouse) => {
+            let position = Position::new(mouse.col as u16, mouse.row as u16);
+            state.view.scroll_drag = if state.view.scrollbar_area.contains(position) {
+                Some(ScrollDrag::Scrollbar)
+            } else if state.view.minimap.area.contains(position) {
+                Some(ScrollDrag::Minimap)
+            } else {
+                None
+            };
+            mouse
+        }
+        MouseEvent::Drag(mouse) => mouse,
+        MouseEvent::Up(_) => return state.view.scroll_drag.take().is_some(),
+        MouseEvent::AddCursor(_) | MouseEvent::None => return false,
+    };
+
+    let y = mouse.row as u16;
+    let row = match state.view.scroll_drag {
+        Some(ScrollDrag::Scrollbar) => {
+            scrollbar::row_at(state.view.scrollbar_area, y, state.lines.len())
+        }
+        Some(ScrollDrag::Minimap) => state.view.minimap.row_at(y),
+        None => return false,
+    };
+    scroll_to_row(state, row);
+    true
+}
+
+/// Scrolls the viewport such that `row` is in its center, and moves the
+/// cursor into the viewport.
+fn scroll_to_row(state: &mut EditorState, row: usize) {
+    let last_row = state.lines.len().saturating_sub(1);
+    let num_rows = state.view.num_rows.max(1);
+    let top = state
+        .folds
+        .fold_start(row.saturating_sub(num_rows / 2).min(last_row));
+    state.view.viewport.y = top;
+
+    let margin = state.view.scroll_off.min((num_rows - 1) / 2);
+    let first = (top + margin).min(last_row);
+    let last = (top + num_rows - 1 - margin).clamp(first, last_row.max(first));
+    state.cursor.row = state.folds.fold_start(state.cursor.row.clamp(first, last));
+}
+
 fn mouse_position_to_cursor_position(
     state: &EditorState,
     mouse: &MousePosition,
@@ -79,23 +149,40 @@ fn mouse_position_to_cursor_position(
     );
 
     if !state.view.wrap {
-        return Index2::new(
-            mouse.row.saturating_add(row_index),
-            mouse.col.saturating_add(col_index),
-        );
+        let folds = &state.folds;
+        let row = folds.row_at_visible(folds.visible_row(row_index) + mouse.row);
+        if let Some(fold) = folds.closed_fold(row) {
+            return Index2::new(fold.start, 0);
+        }
+        let hints = visible_inlay_hints(&state.inlay_hints, row, col_index);
+        return Index2::new(row, data_col(hints, mouse.col, col_index));
     }
 
     let mut row_screen_index = 0;
-    for line in state.lines.iter_row().skip(row_index) {
-        let wrapped_line = LineWrapper::wrap_line(
-            line,
+    while let Some(line) = state.lines.get(RowIndex::new(row_index)) {
+        // A closed fold is displayed as a single row.
+        if let Some(fold) = state.folds.closed_fold(row_index) {
+            if row_screen_index == mouse.row {
+                return Index2::new(fold.start, 0);
+            }
+            row_screen_index += 1;
+            row_index = fold.end + 1;
+            continue;
+        }
+
+        let hints = visible_inlay_hints(&state.inlay_hints, row_index, 0);
+        let line = display_line(line, hints);
+        let wrapped_line = LineWrapper::layout(
+            &line,
             state.view.screen_area.width.into(),
             state.view.tab_width,
+            &state.view.wrap_options,
         );
-        let wrapped_line_len = wrapped_line.len().max(1);
+        let wrapped_line_len = wrapped_line.len();
         if row_screen_index + wrapped_line_len > mouse.row {
             mouse.row = mouse.row.saturating_sub(row_screen_index);
-            col_index = find_cursor_column_in_wrapped_line(&wrapped_line, &mouse, tab_width);
+            let col = find_cursor_column_in_wrapped_line(&line, &wrapped_line, &mouse, tab_width);
+            col_index = data_col(hints, col, 0);
             break;
         }
         row_screen_index += wrapped_line_len;
@@ -105,23 +192,25 @@ fn mouse_position_to_cursor_position(
     Index2::new(row_index, col_index)
 }
 
+/// Returns the column of the char at the mouse position, where `mouse` is
+/// relative to the first row of the wrapped line. A click on the indentation
+/// of a continuation row selects the first char of the row.
 fn find_cursor_column_in_wrapped_line(
-    line: &[Vec<char>],
+    line: &[char],
+    rows: &[WrappedRow],
     mouse: &Index2,
     tab_width: usize,
 ) -> usize {
-    let Some(l) = line.get(mouse.row) else {
+    let Some(row) = rows.get(mouse.row) else {
         return 0;
     };
 
-    let col_offset: usize = line.iter().take(mouse.row).map(Vec::len).sum();
+    let mouse_col = mouse.col.saturating_sub(row.indent);
     let mut current_width = 0;
     let mut col_index = 0;
 
-    for &ch in l {
-        let char_width = char_width(ch, tab_width);
-
-        if current_width + char_width > mouse.col {
+    for char_width in grapheme_widths(&line[row.start..row.end], tab_width) {
+        if current_width + char_width > mouse_col {
             break;
         }
 
@@ -129,7 +218,7 @@ fn find_cursor_column_in_wrapped_line(
         col_index += 1;
     }
 
-    col_offset + col_index
+    row.start + col_index
 }
 
 /// Represents a mouse event.
@@ -144,6 +233,9 @@ pub enum MouseEvent {
     /// A mouse Drag event.
     Drag(MousePosition),
 
+    /// A mouse press event with the alt modifier. Adds a cursor.
+    AddCursor(MousePosition),
+
     /// A mouse event that is handled by the editor.
     None,
 }
@@ -151,6 +243,9 @@ pub enum MouseEvent {
 impl From<CTMouseEvent> for MouseEvent {
     fn from(event: CTMouseEvent) -> Self {
         match event.kind {
+            MouseEventKind::Down(_) if event.modifiers.contains(KeyModifiers::ALT) => {
+                Self::AddCursor(MousePosition::new(event.row, event.column))
+            }
             MouseEventKind::Down(_) => Self::Down(MousePosition::new(event.row, event.column)),
             MouseEventKind::Up(_) => Self::Up(MousePosition::new(event.row, event.column)),
             MouseEventKind::Drag(_) => Self::Drag(MousePosition::new(event.row, event.column)),
@@ -176,3 +271,169 @@ impl MousePosition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::line_wrapper::WrapMode;
+    use crate::{EditorMinimap, EditorTheme, EditorView, InlayHint, LineNumbers, Lines};
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    #[test]
+    fn test_mouse_position_in_word_wrapped_line() {
+        let mut state = EditorState::new(Lines::from("  one two three four\nx"));
+        state.view.screen_area = Rect::new(0, 0, 12, 6);
+        state.view.wrap_options.mode = WrapMode::Word;
+        state.view.wrap_options.break_indent = true;
+        state.view.wrap_options.show_break = "> ".to_string();
+
+        // The second row starts with the prefix `  > ` followed by `three `.
+        let position = |row, col| {
+            let mouse = MousePosition { row, col };
+            mouse_position_to_cursor_position(&state, &mouse, 2)
+        };
+        assert_eq!(position(1, 4), Index2::new(0, 10));
+        assert_eq!(position(1, 9), Index2::new(0, 15));
+        assert_eq!(position(1, 1), Index2::new(0, 10));
+        assert_eq!(position(2, 5), Index2::new(0, 17));
+        assert_eq!(position(3, 0), Index2::new(1, 0));
+    }
+
+    #[test]
+    fn test_mouse_position_with_line_numbers() {
+        let mut state = EditorState::new(Lines::from("abcdefghij\nxyz"));
+        let area = Rect::new(0, 0, 10, 4);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .line_numbers(LineNumbers::Absolute)
+            .render(area, &mut buf);
+
+        // The number is only displayed on the first row of a wrapped line.
+        let row = |y| (0..10).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "  1 abcdef");
+        assert_eq!(row(1), "    ghij  ");
+        assert_eq!(row(2), "  2 xyz   ");
+
+        let position = |row, col| {
+            let mouse = MousePosition { row, col };
+            mouse_position_to_cursor_position(&state, &mouse, 2)
+        };
+        assert_eq!(position(0, 5), Index2::new(0, 1));
+        assert_eq!(position(1, 4), Index2::new(0, 6));
+        assert_eq!(position(2, 1), Index2::new(1, 0));
+    }
+
+    #[test]
+    fn test_mouse_position_with_inlay_hints() {
+        let mut state = EditorState::new(Lines::from("let x = 1;"));
+        state.set_inlay_hints(vec![InlayHint::new(Index2::new(0, 5), ": i32")]);
+        state.cursor = Index2::new(0, 6);
+        let area = Rect::new(0, 0, 8, 3);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buf);
+
+        // The hint is wrapped with the text and the cursor skips it.
+        let row = |y| (0..8).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "let x: i");
+        assert_eq!(row(1), "32 = 1; ");
+        assert_eq!(
+            buf[(3, 1)].bg,
+            EditorTheme::default().cursor_style.bg.unwrap()
+        );
+
+        let position = |row, col| {
+            let mouse = MousePosition { row, col };
+            mouse_position_to_cursor_position(&state, &mouse, 2)
+        };
+        assert_eq!(position(0, 4), Index2::new(0, 4));
+        assert_eq!(position(0, 6), Index2::new(0, 5));
+        assert_eq!(position(1, 1), Index2::new(0, 5));
+        assert_eq!(position(1, 3), Index2::new(0, 6));
+    }
+
+    #[test]
+    fn test_mouse_position_with_closed_fold() {
+        let mut state = EditorState::new(Lines::from("a\n  b\n  c\nd"));
+        state.add_fold(0..=2);
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state).render(area, &mut buf);
+
+        let row = |y| (0..20).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "+-- 3 lines: a      ");
+        assert_eq!(row(1), "d                   ");
+
+        let position = |row, col| {
+            let mouse = MousePosition { row, col };
+            mouse_position_to_cursor_position(&state, &mouse, 2)
+        };
+        assert_eq!(position(0, 5), Index2::new(0, 0));
+        assert_eq!(position(1, 0), Index2::new(3, 0));
+
+        state.view.wrap = false;
+        let mouse = MousePosition { row: 1, col: 0 };
+        let position = mouse_position_to_cursor_position(&state, &mouse, 2);
+        assert_eq!(position, Index2::new(3, 0));
+    }
+
+    #[test]
+    fn test_drag_scrollbar_and_minimap() {
+        let text: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut state = EditorState::new(Lines::from(text.join("\n")));
+        let area = Rect::new(0, 0, 6, 5);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 5));
+        let theme = EditorTheme::default().hide_status_line();
+        EditorView::new(&mut state)
+            .theme(theme)
+            .scrollbar(true)
+            .render(area, &mut buf);
+        EditorMinimap::new(&mut state).render(Rect::new(6, 0, 2, 5), &mut buf);
+
+        let mouse = |row, col| MousePosition { row, col };
+        MouseEventHandler::on_event(MouseEvent::Down(mouse(4, 5)), &mut state);
+        assert_eq!(state.view.viewport.y, 14);
+        assert_eq!(state.cursor, Index2::new(14, 0));
+
+        // Dragging beside the scrollbar keeps scrolling.
+        MouseEventHandler::on_event(MouseEvent::Drag(mouse(0, 2)), &mut state);
+        assert_eq!(state.view.viewport.y, 0);
+        assert_eq!(state.cursor, Index2::new(4, 0));
+        assert_eq!(state.mode, EditorMode::Normal);
+        MouseEventHandler::on_event(MouseEvent::Up(mouse(0, 2)), &mut state);
+        assert_eq!(state.cursor, Index2::new(4, 0));
+
+        // Every cell of the braille minimap covers four rows.
+        MouseEventHandler::on_event(MouseEvent::Down(mouse(3, 7)), &mut state);
+        assert_eq!(state.view.viewport.y, 10);
+        assert_eq!(state.cursor, Index2::new(10, 0));
+    }
+
+    #[test]
+    fn test_alt_click_adds_cursor() {
+        use ratatui::crossterm::event::MouseButton;
+
+        let mut state = EditorState::new(Lines::from("abc\ndefg\nh"));
+        let area = Rect::new(0, 0, 10, 4);
+        EditorView::new(&mut state).render(area, &mut Buffer::empty(area));
+
+        let click = |row, column, modifiers| CTMouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers,
+        };
+        MouseEventHandler::on_event(click(1, 2, KeyModifiers::ALT), &mut state);
+        assert_eq!(state.cursors(), vec![Index2::new(0, 0), Index2::new(1, 2)]);
+
+        // The column is clamped to the end of the line.
+        MouseEventHandler::on_event(click(2, 5, KeyModifiers::ALT), &mut state);
+        assert_eq!(
+            state.cursors(),
+            vec![Index2::new(0, 0), Index2::new(1, 2), Index2::new(2, 0)]
+        );
+
+        // A plain click only moves the primary cursor.
+        MouseEventHandler::on_event(click(0, 1, KeyModifiers::NONE), &mut state);
+        assert_eq!(state.cursors(), vec![Index2::new(0, 1)]);
+    }
+}
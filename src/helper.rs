@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use jagged::index::RowIndex;
 use ratatui::{layout::Rect, text::Span};
 
@@ -43,6 +45,11 @@ pub(crate) fn append_str(lines: &mut Lines, index: &mut Index2, text: &str) {
     index.col = index.col.saturating_sub(1);
 }
 
+/// Whether `ch` is part of a word, i.e. alphanumeric or an underscore.
+pub(crate) fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
 /// Inserts a line break at a given index. Forces a splitting of lines if
 /// the index is in the middle of a line.
 pub(crate) fn line_break(lines: &mut Lines, index: &mut Index2) {
@@ -77,10 +84,10 @@ pub(crate) fn max_col_normal(lines: &Lines, index: &Index2) -> usize {
     if lines.is_empty() {
         return 0;
     }
-    let Some(len_col) = lines.len_col(index.row) else {
+    let Some(line) = lines.get(RowIndex::new(index.row)) else {
         return 0;
     };
-    len_col.saturating_sub(1)
+    grapheme_start(line, line.len().saturating_sub(1))
 }
 
 /// Returns the maximum permissible column value.
@@ -205,11 +212,101 @@ pub(crate) fn char_width(ch: char, tab_width: usize) -> usize {
     ch.width().unwrap_or(0)
 }
 
-/// Determines the unicode width of chars.
+/// Determines the unicode width of chars, measuring grapheme clusters as
+/// a whole.
 pub(crate) fn chars_width(chars: &[char], tab_width: usize) -> usize {
-    chars
-        .iter()
-        .fold(0, |sum, ch| sum + char_width(*ch, tab_width))
+    grapheme_widths(chars, tab_width).iter().sum()
+}
+
+/// Returns the char ranges of the extended grapheme clusters of `chars`.
+pub(crate) fn graphemes(chars: &[char]) -> Vec<Range<usize>> {
+    use unicode_segmentation::UnicodeSegmentation;
+    let text: String = chars.iter().collect();
+    let mut start = 0;
+    text.graphemes(true)
+        .map(|grapheme| {
+            let end = start + grapheme.chars().count();
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+/// Returns the width of every char of `chars`. The width of a grapheme
+/// cluster is attributed to its first char, the other chars of the cluster
+/// are zero wide.
+pub(crate) fn grapheme_widths(chars: &[char], tab_width: usize) -> Vec<usize> {
+    use unicode_width::UnicodeWidthStr;
+    let mut widths = vec![0; chars.len()];
+    for range in graphemes(chars) {
+        widths[range.start] = match &chars[range.clone()] {
+            ['\t'] => tab_width,
+            [ch] => char_width(*ch, tab_width),
+            cluster => cluster.iter().collect::<String>().width(),
+        };
+    }
+    widths
+}
+
+/// Returns the first column of the grapheme cluster at `col`. Columns
+/// behind the end of the line are returned unchanged.
+pub(crate) fn grapheme_start(line: &[char], col: usize) -> usize {
+    grapheme_at(line, col).map_or(col, |range| range.start)
+}
+
+/// Returns the column behind the grapheme cluster at `col`. Columns behind
+/// the end of the line are returned unchanged.
+pub(crate) fn grapheme_end(line: &[char], col: usize) -> usize {
+    grapheme_at(line, col).map_or(col, |range| range.end)
+}
+
+/// Returns the grapheme cluster that contains `col`, or `None` if `col` is
+/// behind the end of the line.
+///
+/// Only a window of chars around `col` is segmented. The window is widened
+/// if the boundaries depend on chars outside of it, e.g. on a long run of
+/// regional indicators.
+fn grapheme_at(line: &[char], col: usize) -> Option<Range<usize>> {
+    use unicode_segmentation::GraphemeCursor;
+    if col >= line.len() {
+        return None;
+    }
+
+    let mut margin = 8;
+    loop {
+        let lo = col.saturating_sub(margin);
+        let hi = (col + margin).min(line.len());
+        let chunk: String = line[lo..hi].iter().collect();
+        // The cursor only needs to know whether there is text in front of
+        // and behind the window, not how much.
+        let chunk_start = usize::from(lo > 0);
+        let len = chunk_start + chunk.len() + usize::from(hi < line.len());
+        let offset = chunk_start + line[lo..col].iter().map(|ch| ch.len_utf8()).sum::<usize>();
+        let to_col = |byte: usize| lo + chunk[..byte - chunk_start].chars().count();
+
+        let mut cursor = GraphemeCursor::new(offset, len, true);
+        let start = match cursor.is_boundary(&chunk, chunk_start) {
+            Ok(true) => Ok(col),
+            Ok(false) => cursor
+                .prev_boundary(&chunk, chunk_start)
+                .map(|boundary| boundary.map_or(lo, to_col)),
+            Err(err) => Err(err),
+        };
+        let end = GraphemeCursor::new(offset, len, true)
+            .next_boundary(&chunk, chunk_start)
+            .map(|boundary| boundary.map_or(hi, to_col));
+
+        match (start, end) {
+            (Ok(start), Ok(end)) => return Some(start..end),
+            _ if lo == 0 && hi == line.len() => {
+                return graphemes(line)
+                    .into_iter()
+                    .find(|range| range.contains(&col));
+            }
+            _ => margin *= 4,
+        }
+    }
 }
 
 /// Determines the unicode width of a span.
@@ -354,4 +451,41 @@ mod tests {
         let closing_bracket = find_matching_bracket(&lines, cursor);
         assert_eq!(closing_bracket, Some(Index2::new(0, 0)));
     }
+
+    #[test]
+    fn test_graphemes() {
+        // An accent, a family of three and a flag.
+        let line: Vec<char> =
+            "e\u{301}\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{1f1e9}\u{1f1ea}"
+                .chars()
+                .collect();
+        assert_eq!(graphemes(&line), vec![0..2, 2..7, 7..9]);
+        assert_eq!(grapheme_widths(&line, 4), vec![1, 0, 2, 0, 0, 0, 0, 2, 0]);
+        assert_eq!(chars_width(&line, 4), 5);
+        assert_eq!(grapheme_start(&line, 5), 2);
+        assert_eq!(grapheme_end(&line, 5), 7);
+        assert_eq!(grapheme_end(&line, 9), 9);
+    }
+
+    #[test]
+    fn test_grapheme_at_long_clusters() {
+        // Clusters that reach beyond the scanned window: many accents and
+        // a run of flags, whose pairs depend on all flags in front.
+        let text = format!(
+            "a{}b{}c",
+            "e\u{301}".repeat(20),
+            "\u{1f1e9}\u{1f1ea}".repeat(15)
+        );
+        let line: Vec<char> = text.chars().collect();
+        let ranges = graphemes(&line);
+        for col in 0..line.len() {
+            let range = ranges.iter().find(|range| range.contains(&col)).unwrap();
+            assert_eq!(grapheme_start(&line, col), range.start, "start of {col}");
+            assert_eq!(grapheme_end(&line, col), range.end, "end of {col}");
+        }
+
+        let accents: Vec<char> = format!("a{}", "\u{301}".repeat(100)).chars().collect();
+        assert_eq!(grapheme_start(&accents, 90), 0);
+        assert_eq!(grapheme_end(&accents, 3), 101);
+    }
 }
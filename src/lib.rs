@@ -35,7 +35,12 @@
 //! - Vim-like keybindings and editing modes for efficient text manipulation.
 //! - Copy paste using the systems clipboard.
 //! - Line wrapping.
+//! - Line numbers (absolute, relative or hybrid).
+//! - Cursor line, colour columns and visible whitespace (listchars).
+//! - Scrollbar and minimap.
+//! - Matching bracket highlight and rainbow brackets.
 //! - Syntax highlighting (experimental).
+//! - Tree-sitter highlighting (optional).
 //! - Mouse support (experimental).
 //!
 //! ## Demo
@@ -57,6 +62,8 @@
 //! | `b`                       | Move backward to the start of a word         |
 //! | `x`                       | Delete the character under the cursor        |
 //! | `u`, `<ctrl>+r`           | Undo/Redo last action                        |
+//! | `g-`, `g+`                | Go to older/newer text state (undo tree)     |
+//! | `:earlier 30s`, `:later 2m` | Go back/forward in time (also `:earlier 3`) |
 //! | `Esc`                     | Escape Visual mode                           |
 //! | `0`                       | Move cursor to start of line                 |
 //! | `^`                       | Move cursor to first non-blank character     |
@@ -64,6 +71,7 @@
 //! | `gg`                      | Move cursor to the first row                 |
 //! | `G `                      | Move cursor to the last row                  |
 //! | `%`                       | Move cursor to closing/opening bracket       |
+//! | `{`, `}`                  | Move cursor to the previous/next paragraph   |
 //! | `a`                       | Append after the cursor                      |
 //! | `A`                       | Append at the end of the line                |
 //! | `o`                       | Add a new line below and enter Insert mode   |
@@ -74,12 +82,22 @@
 //! | `D`                       | Delete to the end of the line                |
 //! | `viw`                     | Select between delimiters. Supported: [`"`]  |
 //! | `vi` + `", ', (, [ or {`  | Select between delimiter `", ', (, [ or {`   |
+//! | `vip`, `vap`              | Select the inner/around paragraph            |
 //! | `ci` + `", ', (, [ or {`  | Change between delimiter `", ', (, [ or {`   |
 //! | `u`                       | Undo the last change                         |
 //! | `r`                       | Redo the last undone action                  |
 //! | `y`                       | Copy the selected text in visual mode        |
 //! | `yy`                      | Copy the current line in normal mode         |
 //! | `p`                       | Paste the copied text                        |
+//! | `<ctrl>+n`                | Add a cursor on the next match of the word   |
+//! | `I`                       | Add a cursor on each selected line (Visual)  |
+//! | `Esc`                     | Remove all but the primary cursor            |
+//! | `gcc`, `gc` + motion      | Toggle comments of lines, e.g. `gc3j`, `gcip` |
+//! | `gc`                      | Toggle comments of selected lines (Visual)   |
+//! | `]d`, `[d`                | Jump to the next/previous diagnostic         |
+//! | `zf` + `j, k, gg, G or %` | Create a fold (`zf` in Visual mode)          |
+//! | `za`, `zo`, `zc`          | Toggle/open/close the fold at the cursor     |
+//! | `zR`, `zM`                | Open/close all folds                         |
 //!
 //! #### Insert Mode:
 //!
@@ -89,6 +107,10 @@
 //! | `Backspace` | Delete the previous character           |
 //! | `Enter`     | Insert line break                       |
 //! | `Arrows`    | Navigation                              |
+//! | `<ctrl>+n`, `<ctrl>+p` | Open completion popup, select next/previous item |
+//! | `Tab`, `Enter` | Select next/accept completion item (popup open) |
+//! | `Tab` | Expand the snippet in front of the cursor, or jump to the next tabstop |
+//! | `Shift+Tab` | Jump to the previous snippet tabstop |
 //!
 //! For more keybindings and customization options, refer to the code.
 //!
@@ -110,6 +132,10 @@
 //! If you want to use a custom theme, see [`SyntaxHighlighter::custom_theme`]. Check [syntect](https://github.com/trishume/syntect)
 //! for more details about themes and extensions.
 //!
+//! Use [`SyntaxHighlighter::try_new`] to handle unknown themes and extensions, and [`SyntaxAssets`] to detect
+//! the syntax of a file by its name or first line, or to load `.sublime-syntax` and `.tmTheme` files from disk.
+//! [`EditorTheme::from_syntax_theme`] matches the colours of the editor to the highlighting theme.
+//!
 //!```ignore
 //! use edtui::EditorState;
 //! use edtui::EditorView;
@@ -125,9 +151,39 @@
 //!
 //!![](resources/syntax_highlighting.gif)
 //!
+//! With the `tree-sitter` feature, text can be highlighted with a [tree-sitter](https://tree-sitter.github.io) grammar
+//! instead, see [`TreeSitterHighlighter`]. The grammars are not bundled, register the ones you need in a [`TreeSitterGrammars`].
+//! The captures of the highlight query are styled by the [`EditorTheme::syntax_styles`] of the view.
+//!
+//!```ignore
+//! let highlighter = TreeSitterHighlighter::new(
+//!     "rust",
+//!     tree_sitter_rust::LANGUAGE,
+//!     tree_sitter_rust::HIGHLIGHTS_QUERY,
+//! )?;
+//! EditorView::new(&mut EditorState::default())
+//!         .highlighter(highlighter)
+//!         .render(area, buf);
+//!```
+//!
+//! ## Diagnostics
+//!
+//! Diagnostics, see [`EditorState::set_diagnostics`], are underlined in the colour of their severity. Ratatui cannot
+//! ask the terminal for an undercurl, only for a straight underline. To undercurl them on terminals that support it,
+//! render with [`EditorView::diagnostic_undercurl`] and redraw the underlined cells after every frame:
+//!
+//!```ignore
+//! terminal.draw(|frame| {
+//!     EditorView::new(&mut state)
+//!         .diagnostic_undercurl(true)
+//!         .render(frame.area(), frame.buffer_mut());
+//! })?;
+//! state.draw_undercurls(terminal.backend_mut())?;
+//!```
+//!
 //! ### Roadmap
 //! - [ ] Support termwiz and termion
-//! - [ ] Display line numbers
+//! - [x] Display line numbers
 //! - [ ] Remap keybindings
 #![allow(
     dead_code,
@@ -136,24 +192,44 @@
 )]
 pub mod actions;
 pub mod clipboard;
+pub mod completion;
 mod debug;
 pub mod events;
 mod helper;
+pub mod snippet;
 mod state;
 mod view;
 
 #[allow(deprecated)]
 pub use events::deprecated_input::EditorInput;
 pub use events::EditorEventHandler;
-pub use state::{mode::EditorMode, EditorState};
-pub use view::{theme::EditorTheme, EditorStatusLine, EditorView};
+pub use state::{
+    mode::EditorMode, CommentTokens, Diagnostic, EditorState, Fold, FoldMethod, InlayHint,
+    Severity, UndoBranch, UndoHistoryError,
+};
+pub use view::{
+    highlighter::{EditedRows, HighlightCache, LineHighlighter},
+    theme::EditorTheme,
+    CursorShape, CursorShapes, EditorMinimap, EditorStatusLine, EditorView, LineNumbers, ListChars,
+    MinimapMarker, WrapMode,
+};
 
 #[cfg(feature = "syntax-highlighting")]
-pub use view::syntax_higlighting::SyntaxHighlighter;
+pub use view::syntax_higlighting::{
+    ColorDepth, SyntaxAssets, SyntaxHighlighter, SyntaxHighlighterError,
+};
 
 #[cfg(feature = "syntax-highlighting")]
 pub use syntect;
 
+#[cfg(feature = "tree-sitter")]
+pub use view::tree_sitter_highlighting::{
+    TreeSitterError, TreeSitterGrammars, TreeSitterHighlighter,
+};
+
+#[cfg(feature = "tree-sitter")]
+pub use tree_sitter;
+
 /// A data structure that contains chars organized in rows and columns
 pub type Lines = jagged::Jagged<char>;
 pub use jagged::index::RowIndex;
@@ -0,0 +1,378 @@
+//! Snippets with tabstops and placeholders
+//!
+//! Snippets use the syntax of the language server protocol:
+//!
+//! - `$1`, `${1}`: A tabstop. `Tab` and `Shift+Tab` jump between tabstops
+//!   in the order of their numbers. `$0` is the final cursor position.
+//! - `${1:name}`: A placeholder. The text is inserted and selected when
+//!   jumping to the tabstop, typing replaces it. Placeholders may be nested.
+//! - `${1|one,two,three|}`: A choice. The choices are offered in the
+//!   completion popup.
+//! - A tabstop that occurs several times is mirrored, i.e. all occurrences
+//!   are edited together.
+//! - `$name`, `${name:default}`: A variable. Variables are not resolved,
+//!   only the default text is inserted.
+//! - `\$`, `\}` and `\\` insert a literal `$`, `}` and `\`.
+//!
+//! Snippets are expanded by typing their trigger and pressing `Tab` in
+//! insert mode, or through the completion popup.
+//!
+//! ## Example
+//!
+//! ```
+//! use edtui::snippet::Snippet;
+//! use edtui::EditorState;
+//!
+//! let snippet = Snippet::new("fn", "fn ${1:name}(${2}) {\n\t$0\n}")
+//!     .unwrap()
+//!     .file_type("rs");
+//!
+//! let mut state = EditorState::default();
+//! state.set_file_type(Some("rs"));
+//! state.add_snippet(snippet);
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+/// An error that occurs when parsing a snippet body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnippetError {
+    /// A `${` is not closed.
+    Unterminated,
+    /// The tabstop at the given char offset is malformed.
+    InvalidTabstop(usize),
+}
+
+impl fmt::Display for SnippetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unterminated => write!(f, "unterminated snippet placeholder"),
+            Self::InvalidTabstop(offset) => write!(f, "invalid snippet tabstop at {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for SnippetError {}
+
+/// A snippet that is expanded by its trigger word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub(crate) trigger: String,
+    pub(crate) body: String,
+    pub(crate) description: Option<String>,
+    pub(crate) file_types: Vec<String>,
+    nodes: Vec<Node>,
+}
+
+impl Snippet {
+    /// Creates a new snippet that is expanded by `trigger`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `body` is not a valid snippet.
+    pub fn new<S: Into<String>, B: Into<String>>(
+        trigger: S,
+        body: B,
+    ) -> Result<Self, SnippetError> {
+        let body = body.into();
+        let nodes = Parser::new(&body).parse(false)?;
+        Ok(Self {
+            trigger: trigger.into(),
+            body,
+            description: None,
+            file_types: Vec::new(),
+            nodes,
+        })
+    }
+
+    /// Restricts the snippet to a file type, e.g. `rs`. May be called
+    /// multiple times. Without a file type, the snippet is available for
+    /// all files.
+    #[must_use]
+    pub fn file_type<S: Into<String>>(mut self, file_type: S) -> Self {
+        self.file_types.push(file_type.into());
+        self
+    }
+
+    /// Sets a description that is shown in the completion popup.
+    #[must_use]
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Returns the trigger word of the snippet.
+    #[must_use]
+    pub fn trigger(&self) -> &str {
+        &self.trigger
+    }
+
+    /// Whether the snippet is available for files of `file_type`.
+    pub(crate) fn matches_file_type(&self, file_type: Option<&str>) -> bool {
+        self.file_types.is_empty()
+            || file_type.is_some_and(|ft| self.file_types.iter().any(|f| f == ft))
+    }
+
+    /// Renders the text of the snippet and the char ranges of its tabstops.
+    pub(crate) fn expand(&self) -> Expansion {
+        let mut defaults = BTreeMap::new();
+        collect_defaults(&self.nodes, &mut defaults);
+        let mut expansion = Expansion::default();
+        expansion.render(&self.nodes, &defaults, 0);
+        expansion
+    }
+}
+
+/// The rendered text of a snippet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Expansion {
+    pub(crate) text: Vec<char>,
+    /// The char ranges of each tabstop by number.
+    pub(crate) tabstops: BTreeMap<usize, Vec<Range<usize>>>,
+    /// The choices of each tabstop by number.
+    pub(crate) choices: BTreeMap<usize, Vec<String>>,
+}
+
+impl Expansion {
+    fn render(&mut self, nodes: &[Node], defaults: &BTreeMap<usize, &[Node]>, depth: usize) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text.extend(text.chars()),
+                Node::Tabstop(index, children) => {
+                    let start = self.text.len();
+                    let children = match defaults.get(index) {
+                        Some(default) if children.is_empty() => default,
+                        _ => children.as_slice(),
+                    };
+                    // Guards against placeholders that contain themselves.
+                    if depth < 8 {
+                        self.render(children, defaults, depth + 1);
+                    }
+                    let range = start..self.text.len();
+                    self.tabstops.entry(*index).or_default().push(range);
+                }
+                Node::Choice(index, choices) => {
+                    let start = self.text.len();
+                    self.text.extend(choices[0].chars());
+                    let range = start..self.text.len();
+                    self.tabstops.entry(*index).or_default().push(range);
+                    self.choices
+                        .entry(*index)
+                        .or_insert_with(|| choices.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Collects the first placeholder text of every tabstop, which is used
+/// for its mirrors.
+fn collect_defaults<'a>(nodes: &'a [Node], defaults: &mut BTreeMap<usize, &'a [Node]>) {
+    for node in nodes {
+        if let Node::Tabstop(index, children) = node {
+            if !children.is_empty() {
+                defaults.entry(*index).or_insert(children.as_slice());
+            }
+            collect_defaults(children, defaults);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Tabstop(usize, Vec<Node>),
+    Choice(usize, Vec<String>),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(body: &str) -> Self {
+        Self {
+            chars: body.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Parses nodes until the end of the body, or until a closing `}` if
+    /// `nested` is set.
+    fn parse(&mut self, nested: bool) -> Result<Vec<Node>, SnippetError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(ch) = self.peek() {
+            match ch {
+                '}' if nested => break,
+                '\\' if matches!(self.chars.get(self.pos + 1), Some('$' | '}' | '\\')) => {
+                    text.push(self.chars[self.pos + 1]);
+                    self.pos += 2;
+                }
+                '$' => {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    nodes.extend(self.parse_dollar()?);
+                }
+                _ => {
+                    text.push(ch);
+                    self.pos += 1;
+                }
+            }
+        }
+        if nested && self.peek() != Some('}') {
+            return Err(SnippetError::Unterminated);
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(nodes)
+    }
+
+    /// Parses a tabstop, placeholder, choice or variable starting at `$`.
+    fn parse_dollar(&mut self) -> Result<Vec<Node>, SnippetError> {
+        let start = self.pos;
+        self.pos += 1;
+        let braced = self.peek() == Some('{');
+        if braced {
+            self.pos += 1;
+        }
+
+        let name = self.take_while(|ch| ch.is_alphanumeric() || ch == '_');
+        if name.is_empty() {
+            if braced {
+                return Err(SnippetError::InvalidTabstop(start));
+            }
+            // A lone `$` is inserted literally.
+            return Ok(vec![Node::Text("$".to_string())]);
+        }
+        let index = name.parse::<usize>().ok();
+        if !braced {
+            return Ok(vec![match index {
+                Some(index) => Node::Tabstop(index, Vec::new()),
+                None => Node::Text(String::new()),
+            }]);
+        }
+
+        let node = match (self.peek(), index) {
+            (Some('}'), Some(index)) => Node::Tabstop(index, Vec::new()),
+            (Some('}'), None) => Node::Text(String::new()),
+            (Some(':'), _) => {
+                self.pos += 1;
+                let children = self.parse(true)?;
+                match index {
+                    Some(index) => Node::Tabstop(index, children),
+                    // The default of a variable is inserted as is.
+                    None => {
+                        self.pos += 1;
+                        return Ok(children);
+                    }
+                }
+            }
+            (Some('|'), Some(index)) => {
+                self.pos += 1;
+                let choices = self.parse_choices(start)?;
+                Node::Choice(index, choices)
+            }
+            (None, _) => return Err(SnippetError::Unterminated),
+            _ => return Err(SnippetError::InvalidTabstop(start)),
+        };
+        if self.peek() != Some('}') {
+            return Err(SnippetError::Unterminated);
+        }
+        self.pos += 1;
+        Ok(vec![node])
+    }
+
+    /// Parses the choices of `${1|one,two|}` after the first `|`.
+    fn parse_choices(&mut self, start: usize) -> Result<Vec<String>, SnippetError> {
+        let mut choices = vec![String::new()];
+        loop {
+            match self.peek() {
+                None => return Err(SnippetError::Unterminated),
+                Some('\\') if matches!(self.chars.get(self.pos + 1), Some(',' | '|' | '\\')) => {
+                    choices.last_mut().unwrap().push(self.chars[self.pos + 1]);
+                    self.pos += 2;
+                }
+                Some(',') => {
+                    choices.push(String::new());
+                    self.pos += 1;
+                }
+                Some('|') => {
+                    self.pos += 1;
+                    if choices.iter().all(String::is_empty) {
+                        return Err(SnippetError::InvalidTabstop(start));
+                    }
+                    return Ok(choices);
+                }
+                Some(ch) => {
+                    choices.last_mut().unwrap().push(ch);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(body: &str) -> Expansion {
+        Snippet::new("", body).unwrap().expand()
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let expansion = expand("fn ${1:name}($2) {\n\t$0\n}");
+        let text: String = expansion.text.iter().collect();
+        assert_eq!(text, "fn name() {\n\t\n}");
+        assert_eq!(expansion.tabstops[&1], vec![3..7]);
+        assert_eq!(expansion.tabstops[&2], vec![8..8]);
+        assert_eq!(expansion.tabstops[&0], vec![13..13]);
+    }
+
+    #[test]
+    fn test_expand_mirrors_and_nesting() {
+        let expansion = expand("${1:a ${2:b}} $1");
+        let text: String = expansion.text.iter().collect();
+        assert_eq!(text, "a b a b");
+        assert_eq!(expansion.tabstops[&1], vec![0..3, 4..7]);
+        assert_eq!(expansion.tabstops[&2], vec![2..3, 6..7]);
+    }
+
+    #[test]
+    fn test_expand_choices_variables_and_escapes() {
+        let expansion = expand("${1|one,two|} ${TM_FILENAME:file} \\$1 $");
+        let text: String = expansion.text.iter().collect();
+        assert_eq!(text, "one file $1 $");
+        assert_eq!(expansion.choices[&1], vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_invalid_snippets() {
+        assert_eq!(
+            Snippet::new("", "${1:a").err(),
+            Some(SnippetError::Unterminated)
+        );
+        assert_eq!(
+            Snippet::new("", "a ${}").err(),
+            Some(SnippetError::InvalidTabstop(2))
+        );
+    }
+}
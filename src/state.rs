@@ -1,21 +1,46 @@
 //! The editors state
+mod comment;
+mod completion;
+mod diagnostic;
+mod fold;
+pub(crate) mod inlay_hint;
 pub mod mode;
+pub(crate) mod multi_cursor;
 mod search;
 pub mod selection;
+mod snippet;
 mod undo;
-mod view;
+pub(crate) mod view;
 
+pub use self::comment::CommentTokens;
+pub use self::diagnostic::{Diagnostic, Severity};
+pub use self::fold::{Fold, FoldMethod};
+pub use self::inlay_hint::InlayHint;
+pub use self::undo::{UndoBranch, UndoHistoryError};
+
+use self::completion::CompletionState;
+use self::fold::FoldState;
+use self::multi_cursor::SecondaryCursor;
 use self::search::SearchState;
+use self::snippet::SnippetState;
 use self::view::ViewState;
 use self::{mode::EditorMode, selection::Selection, undo::Stack};
 use crate::clipboard::{Clipboard, ClipboardTrait};
-use crate::helper::max_col;
+use crate::helper::{grapheme_start, max_col};
+use crate::view::CursorShape;
 use crate::{Index2, Lines};
+use jagged::index::RowIndex;
+use ratatui::layout::Position;
 
 /// Represents the state of an editor.
 #[derive(Clone)]
 pub struct EditorState {
     /// The text in the editor.
+    ///
+    /// Note that changes made directly to `lines` are not recorded in the
+    /// undo history. Reset the state after replacing the text, and call
+    /// [`EditorState::invalidate_highlighting`] after changing it without
+    /// changing the number of lines.
     pub lines: Lines,
 
     /// The current cursor position in the editor.
@@ -27,17 +52,44 @@ pub struct EditorState {
     /// Represents the selection in the editor, if any.
     pub selection: Option<Selection>,
 
+    /// The secondary cursors with their selections.
+    pub(crate) cursors: Vec<SecondaryCursor>,
+
     /// Internal view state of the editor.
     pub(crate) view: ViewState,
 
     /// State holding the search results in search mode.
     pub(crate) search: SearchState,
 
-    /// Stack for undo operations.
-    pub(crate) undo: Stack,
+    /// The command line in command mode.
+    pub(crate) command: String,
+
+    /// State of the completion popup in insert mode.
+    pub(crate) completion: CompletionState,
+
+    /// The registered snippets and the snippet being edited.
+    pub(crate) snippets: SnippetState,
+
+    /// The file type of the text, e.g. `rs`.
+    pub(crate) file_type: Option<String>,
 
-    /// Stack for redo operations.
-    pub(crate) redo: Stack,
+    /// The comment tokens set by the user.
+    pub(crate) comment_tokens: Option<CommentTokens>,
+
+    /// The comment tokens of the syntax of the syntax highlighter.
+    pub(crate) syntax_comment_tokens: Option<CommentTokens>,
+
+    /// The diagnostics of the text, ordered by their start.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+
+    /// The inlay hints, ordered by their position.
+    pub(crate) inlay_hints: Vec<InlayHint>,
+
+    /// The folded ranges of rows.
+    pub(crate) folds: FoldState,
+
+    /// The undo and redo history.
+    pub(crate) undo: Stack,
 
     /// Clipboard for yank and paste operations.
     pub(crate) clip: Clipboard,
@@ -67,10 +119,19 @@ impl EditorState {
             cursor: Index2::new(0, 0),
             mode: EditorMode::Normal,
             selection: None,
+            cursors: Vec::new(),
             view: ViewState::default(),
             search: SearchState::default(),
+            command: String::new(),
+            completion: CompletionState::default(),
+            snippets: SnippetState::default(),
+            file_type: None,
+            comment_tokens: None,
+            syntax_comment_tokens: None,
+            diagnostics: Vec::new(),
+            inlay_hints: Vec::new(),
+            folds: FoldState::default(),
             undo: Stack::new(),
-            redo: Stack::new(),
             clip: Clipboard::default(),
         }
     }
@@ -86,6 +147,35 @@ impl EditorState {
         self.search.pattern.clone()
     }
 
+    /// Returns the current content of the command line.
+    #[must_use]
+    pub fn command(&self) -> String {
+        self.command.clone()
+    }
+
+    /// Returns the screen position of the cursor of the last render, or
+    /// `None` if the cursor was not visible.
+    ///
+    /// Use it to place the terminal cursor if the editor is rendered with
+    /// [`EditorView::terminal_cursor`](crate::EditorView::terminal_cursor).
+    #[must_use]
+    pub fn cursor_screen_position(&self) -> Option<Position> {
+        self.view.cursor_position
+    }
+
+    /// Highlights all lines again on the next render. Required after
+    /// changing [`EditorState::lines`] directly, edits of the editor are
+    /// tracked. A change of the number of lines is noticed without it.
+    pub fn invalidate_highlighting(&mut self) {
+        self.view.highlight_cache.invalidate();
+    }
+
+    /// Returns the shape of the terminal cursor in the current mode.
+    #[must_use]
+    pub fn cursor_shape(&self) -> CursorShape {
+        self.view.cursor_shapes.get(self.mode)
+    }
+
     /// Clamps the column of the cursor if the cursor is out of bounds.
     /// In normal or visual mode, clamps on `col = len() - 1`, in insert
     /// mode on `col = len()`.
@@ -93,4 +183,19 @@ impl EditorState {
         let max_col = max_col(&self.lines, &self.cursor, self.mode);
         self.cursor.col = self.cursor.col.min(max_col);
     }
+
+    /// Moves the cursors that are inside of a grapheme cluster to its first
+    /// char, such that a cluster is never split by the cursor.
+    pub(crate) fn snap_cursors_to_graphemes(&mut self) {
+        let lines = &self.lines;
+        let snap = |cursor: &mut Index2| {
+            if let Some(line) = lines.get(RowIndex::new(cursor.row)) {
+                cursor.col = grapheme_start(line, cursor.col);
+            }
+        };
+        snap(&mut self.cursor);
+        for secondary in &mut self.cursors {
+            snap(&mut secondary.cursor);
+        }
+    }
 }
@@ -0,0 +1,198 @@
+use std::ops::RangeInclusive;
+
+use jagged::index::RowIndex;
+
+use crate::EditorState;
+
+/// The tokens that start a line comment or enclose a block comment, e.g.
+/// `//` or `/*` and `*/`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentTokens {
+    /// The token that starts a line comment.
+    pub line: Option<String>,
+    /// The tokens that start and end a block comment. Used if the language
+    /// has no line comments.
+    pub block: Option<(String, String)>,
+}
+
+impl CommentTokens {
+    /// Creates comment tokens for a language with line comments.
+    #[must_use]
+    pub fn line<S: Into<String>>(token: S) -> Self {
+        Self {
+            line: Some(token.into()),
+            block: None,
+        }
+    }
+
+    /// Creates comment tokens for a language with block comments only.
+    #[must_use]
+    pub fn block<S: Into<String>, E: Into<String>>(start: S, end: E) -> Self {
+        Self {
+            line: None,
+            block: Some((start.into(), end.into())),
+        }
+    }
+
+    /// Returns the tokens that are placed in front of and behind a line.
+    fn delimiters(&self) -> Option<(Vec<char>, Vec<char>)> {
+        let chars = |token: &str| token.trim().chars().collect::<Vec<_>>();
+        if let Some(line) = self.line.as_deref().filter(|t| !t.trim().is_empty()) {
+            return Some((chars(line), Vec::new()));
+        }
+        let (start, end) = self.block.as_ref()?;
+        Some((chars(start), chars(end)))
+    }
+}
+
+impl EditorState {
+    /// Sets the comment tokens used to toggle comments. Overrides the
+    /// tokens of the highlighter of the [`EditorView`](crate::EditorView),
+    /// if any, which are only known once the view is rendered.
+    pub fn set_comment_tokens(&mut self, tokens: Option<CommentTokens>) {
+        self.comment_tokens = tokens;
+    }
+
+    /// Returns the comment tokens used to toggle comments.
+    #[must_use]
+    pub fn comment_tokens(&self) -> Option<&CommentTokens> {
+        self.comment_tokens
+            .as_ref()
+            .or(self.syntax_comment_tokens.as_ref())
+    }
+
+    /// Comments out the non-blank lines in `rows`, or removes the comments
+    /// if all of them are commented out. Lines are commented at the
+    /// smallest indentation of the lines.
+    pub(crate) fn toggle_comment(&mut self, rows: RangeInclusive<usize>) {
+        let Some((start, end)) = self.comment_tokens().and_then(CommentTokens::delimiters) else {
+            return;
+        };
+        let last_row = self.lines.len().saturating_sub(1);
+        let rows = *rows.start()..*rows.end().min(&last_row) + 1;
+
+        let lines: Vec<&Vec<char>> = rows
+            .clone()
+            .filter_map(|row| self.lines.get(RowIndex::new(row)))
+            .filter(|line| !is_blank(line))
+            .collect();
+        if lines.is_empty() {
+            return;
+        }
+        let uncomment = lines.iter().all(|line| is_commented(line, &start, &end));
+        let indent = lines
+            .iter()
+            .map(|line| indentation(line))
+            .min()
+            .unwrap_or(0);
+
+        self.modify_rows(rows.clone(), |state| {
+            for row in rows {
+                let Some(line) = state.lines.get_mut(RowIndex::new(row)) else {
+                    continue;
+                };
+                if is_blank(line) {
+                    continue;
+                }
+                if uncomment {
+                    remove_comment(line, &start, &end);
+                } else {
+                    add_comment(line, indent, &start, &end);
+                }
+            }
+        });
+        self.clamp_column();
+    }
+}
+
+fn is_blank(line: &[char]) -> bool {
+    line.iter().all(|ch| ch.is_whitespace())
+}
+
+fn indentation(line: &[char]) -> usize {
+    line.iter().take_while(|ch| ch.is_whitespace()).count()
+}
+
+fn trim_end(line: &[char]) -> &[char] {
+    let len = line.len()
+        - line
+            .iter()
+            .rev()
+            .take_while(|ch| ch.is_whitespace())
+            .count();
+    &line[..len]
+}
+
+fn is_commented(line: &[char], start: &[char], end: &[char]) -> bool {
+    let text = trim_end(&line[indentation(line)..]);
+    text.starts_with(start) && text[start.len()..].ends_with(end)
+}
+
+fn add_comment(line: &mut Vec<char>, indent: usize, start: &[char], end: &[char]) {
+    let mut prefix = start.to_vec();
+    prefix.push(' ');
+    line.splice(indent..indent, prefix);
+    if !end.is_empty() {
+        line.push(' ');
+        line.extend_from_slice(end);
+    }
+}
+
+fn remove_comment(line: &mut Vec<char>, start: &[char], end: &[char]) {
+    if !end.is_empty() {
+        let len = trim_end(line).len();
+        let mut from = len - end.len();
+        if from > 0 && line[from - 1] == ' ' {
+            from -= 1;
+        }
+        line.drain(from..len);
+    }
+    let indent = indentation(line);
+    let mut to = indent + start.len();
+    if line.get(to) == Some(&' ') {
+        to += 1;
+    }
+    line.drain(indent..to);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lines;
+
+    #[test]
+    fn test_toggle_line_comment() {
+        let mut state = EditorState::new(Lines::from("fn a() {\n    b();\n\n  c();\n}"));
+        state.set_comment_tokens(Some(CommentTokens::line("//")));
+
+        state.toggle_comment(1..=3);
+        assert_eq!(
+            state.lines,
+            Lines::from("fn a() {\n  //   b();\n\n  // c();\n}")
+        );
+
+        state.toggle_comment(1..=3);
+        assert_eq!(state.lines, Lines::from("fn a() {\n    b();\n\n  c();\n}"));
+    }
+
+    #[test]
+    fn test_toggle_partially_commented() {
+        let mut state = EditorState::new(Lines::from("# a\nb"));
+        state.set_comment_tokens(Some(CommentTokens::line("# ")));
+
+        state.toggle_comment(0..=1);
+        assert_eq!(state.lines, Lines::from("# # a\n# b"));
+    }
+
+    #[test]
+    fn test_toggle_block_comment() {
+        let mut state = EditorState::new(Lines::from("<p>\n  text\n</p>"));
+        state.set_comment_tokens(Some(CommentTokens::block("<!--", "-->")));
+
+        state.toggle_comment(1..=1);
+        assert_eq!(state.lines, Lines::from("<p>\n  <!-- text -->\n</p>"));
+
+        state.toggle_comment(1..=1);
+        assert_eq!(state.lines, Lines::from("<p>\n  text\n</p>"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::completion::{prefix_at, BufferWords, CompletionItem, CompletionProvider};
+use crate::{EditorState, Index2, Lines};
+
+/// The state of the completion popup.
+#[derive(Clone)]
+pub(crate) struct CompletionState {
+    provider: Rc<RefCell<dyn CompletionProvider>>,
+    /// The items shown in the popup. The popup is closed if empty.
+    pub(crate) items: Vec<CompletionItem>,
+    /// The index of the selected item.
+    pub(crate) selected: Option<usize>,
+    /// The word in front of the cursor that is completed.
+    pub(crate) prefix: String,
+    /// The choices of a snippet tabstop, which are offered instead of the
+    /// items of the provider.
+    choices: Option<Vec<CompletionItem>>,
+}
+
+impl Default for CompletionState {
+    fn default() -> Self {
+        Self::new(BufferWords)
+    }
+}
+
+impl CompletionState {
+    pub(crate) fn new(provider: impl CompletionProvider + 'static) -> Self {
+        Self {
+            provider: Rc::new(RefCell::new(provider)),
+            items: Vec::new(),
+            selected: None,
+            prefix: String::new(),
+            choices: None,
+        }
+    }
+
+    /// Whether the completion popup is open.
+    pub(crate) fn is_open(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    /// Queries the provider for the word in front of the cursor and
+    /// appends the `extra` items. Keeps the selected item if it is still
+    /// available.
+    pub(crate) fn update(&mut self, lines: &Lines, cursor: Index2, extra: Vec<CompletionItem>) {
+        let selected = self.selected_item().map(|item| item.label.clone());
+        self.prefix = prefix_at(lines, cursor);
+        self.items = match &self.choices {
+            Some(choices) => choices
+                .iter()
+                .filter(|item| item.label.starts_with(&self.prefix))
+                .cloned()
+                .collect(),
+            None => {
+                let mut items = self
+                    .provider
+                    .borrow_mut()
+                    .complete(lines, cursor, &self.prefix);
+                items.extend(extra);
+                items
+            }
+        };
+        self.selected = selected.and_then(|label| self.items.iter().position(|i| i.label == label));
+    }
+
+    /// Opens the completion popup with the choices of a snippet tabstop
+    /// and selects the first choice.
+    pub(crate) fn show_choices(&mut self, choices: Vec<CompletionItem>) {
+        self.items.clone_from(&choices);
+        self.selected = Some(0);
+        self.prefix = String::new();
+        self.choices = Some(choices);
+    }
+
+    /// Closes the completion popup.
+    pub(crate) fn close(&mut self) {
+        self.items.clear();
+        self.selected = None;
+        self.choices = None;
+    }
+
+    /// Selects the next item, or the first item if none is selected.
+    pub(crate) fn select_next(&mut self) {
+        let len = self.items.len();
+        self.selected = match self.selected {
+            _ if len == 0 => None,
+            Some(i) => Some((i + 1) % len),
+            None => Some(0),
+        };
+    }
+
+    /// Selects the previous item, or the last item if none is selected.
+    pub(crate) fn select_previous(&mut self) {
+        let len = self.items.len();
+        self.selected = match self.selected {
+            _ if len == 0 => None,
+            Some(i) => Some((i + len - 1) % len),
+            None => Some(len - 1),
+        };
+    }
+
+    /// Returns the selected item.
+    pub(crate) fn selected_item(&self) -> Option<&CompletionItem> {
+        self.items.get(self.selected?)
+    }
+}
+
+impl EditorState {
+    /// Sets the provider of the items in the completion popup. By default,
+    /// the words of the current buffer are offered, see
+    /// [`BufferWords`](crate::completion::BufferWords).
+    pub fn set_completion_provider(&mut self, provider: impl CompletionProvider + 'static) {
+        self.completion = CompletionState::new(provider);
+    }
+
+    /// Updates the items of an open completion popup after the word in
+    /// front of the cursor changed. Closes the popup if there are none.
+    pub(crate) fn update_completion(&mut self) {
+        if self.completion.is_open() {
+            self.refresh_completion();
+        }
+    }
+
+    /// Queries the completion items for the word in front of the cursor,
+    /// including the snippets whose trigger starts with the word.
+    pub(crate) fn refresh_completion(&mut self) {
+        let prefix = prefix_at(&self.lines, self.cursor);
+        let snippets = self
+            .snippets
+            .completion_items(self.file_type.as_deref(), &prefix);
+        self.completion.update(&self.lines, self.cursor, snippets);
+    }
+
+    /// Returns the position where the completion popup is anchored, i.e.
+    /// the start of the completed word.
+    pub(crate) fn completion_anchor(&self) -> Index2 {
+        let len = self.completion.prefix.chars().count();
+        Index2::new(self.cursor.row, self.cursor.col.saturating_sub(len))
+    }
+}
@@ -0,0 +1,198 @@
+use super::multi_cursor::RowShift;
+use crate::{EditorState, Index2};
+
+/// The severity of a [`Diagnostic`], ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Returns the sign displayed in the sign column.
+    pub(crate) fn sign(self) -> char {
+        match self {
+            Self::Hint => 'H',
+            Self::Info => 'I',
+            Self::Warning => 'W',
+            Self::Error => 'E',
+        }
+    }
+}
+
+/// A message attached to a range of the text, e.g. a linter error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The start of the range.
+    pub start: Index2,
+    /// The end of the range, exclusive. An empty range marks the char at
+    /// `start`.
+    pub end: Index2,
+    /// The severity of the diagnostic.
+    pub severity: Severity,
+    /// The message of the diagnostic.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic for the range `start..end`.
+    #[must_use]
+    pub fn new<S: Into<String>>(
+        start: Index2,
+        end: Index2,
+        severity: Severity,
+        message: S,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the columns of `row` that are covered by the diagnostic.
+    /// `len` is the number of chars in the row.
+    pub(crate) fn columns(&self, row: usize, len: usize) -> Option<std::ops::Range<usize>> {
+        if row < self.start.row || row > self.end.row {
+            return None;
+        }
+        let start = if row == self.start.row {
+            self.start.col
+        } else {
+            0
+        };
+        let end = if row == self.end.row {
+            self.end.col
+        } else {
+            len
+        };
+        if self.start == self.end {
+            return Some(start..start + 1);
+        }
+        Some(start..end.max(start))
+    }
+
+    /// Moves the range after a change of the text. Text inserted at the
+    /// edges of the range is not added to it.
+    pub(crate) fn shift(&mut self, shift: &RowShift) {
+        let empty = self.start == self.end;
+        self.start = shift.apply(self.start, !empty);
+        self.end = shift.apply(self.end, false);
+        if (self.end.row, self.end.col) < (self.start.row, self.start.col) {
+            self.end = self.start;
+        }
+    }
+}
+
+impl EditorState {
+    /// Replaces the diagnostics of the text. The diagnostics are moved
+    /// along when the text is edited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{Diagnostic, EditorState, Index2, Lines, Severity};
+    ///
+    /// let mut state = EditorState::new(Lines::from("let x = 1"));
+    /// state.set_diagnostics(vec![Diagnostic::new(
+    ///     Index2::new(0, 4),
+    ///     Index2::new(0, 5),
+    ///     Severity::Warning,
+    ///     "unused variable: `x`",
+    /// )]);
+    /// ```
+    pub fn set_diagnostics(&mut self, mut diagnostics: Vec<Diagnostic>) {
+        diagnostics.sort_by_key(|d| (d.start.row, d.start.col));
+        self.diagnostics = diagnostics;
+    }
+
+    /// Returns the diagnostics of the text, ordered by their start.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the start of the next diagnostic after the cursor, or of
+    /// the previous one if `forward` is false. Wraps around the end of the
+    /// text.
+    pub(crate) fn find_diagnostic(&self, forward: bool) -> Option<Index2> {
+        let cursor = (self.cursor.row, self.cursor.col);
+        let starts = self.diagnostics.iter().map(|d| (d.start.row, d.start.col));
+        let target = if forward {
+            starts
+                .clone()
+                .find(|&start| start > cursor)
+                .or(starts.min())
+        } else {
+            starts
+                .clone()
+                .filter(|&start| start < cursor)
+                .max()
+                .or(starts.max())
+        };
+        target.map(|(row, col)| Index2::new(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Execute, InsertChar, LineBreak, SwitchMode};
+    use crate::{EditorMode, Lines};
+
+    fn diagnostic(start: (usize, usize), end: (usize, usize)) -> Diagnostic {
+        let start = Index2::new(start.0, start.1);
+        let end = Index2::new(end.0, end.1);
+        Diagnostic::new(start, end, Severity::Error, "error")
+    }
+
+    #[test]
+    fn test_diagnostics_shift_with_edits() {
+        let mut state = EditorState::new(Lines::from("let x = y;\nz"));
+        state.set_diagnostics(vec![diagnostic((0, 8), (0, 9)), diagnostic((1, 0), (1, 0))]);
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        state.cursor = Index2::new(0, 8);
+        InsertChar('a').execute(&mut state);
+        LineBreak(1).execute(&mut state);
+
+        let ranges: Vec<_> = state
+            .diagnostics()
+            .iter()
+            .map(|d| (d.start, d.end))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (Index2::new(1, 0), Index2::new(1, 1)),
+                (Index2::new(2, 0), Index2::new(2, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_diagnostic() {
+        let mut state = EditorState::new(Lines::from("abc\ndef\nghi"));
+        state.set_diagnostics(vec![diagnostic((2, 1), (2, 2)), diagnostic((0, 2), (1, 1))]);
+        state.cursor = Index2::new(1, 0);
+
+        assert_eq!(state.find_diagnostic(true), Some(Index2::new(2, 1)));
+        assert_eq!(state.find_diagnostic(false), Some(Index2::new(0, 2)));
+
+        state.cursor = Index2::new(2, 1);
+        assert_eq!(state.find_diagnostic(true), Some(Index2::new(0, 2)));
+    }
+
+    #[test]
+    fn test_diagnostic_columns() {
+        let d = diagnostic((0, 2), (2, 1));
+        assert_eq!(d.columns(0, 5), Some(2..5));
+        assert_eq!(d.columns(1, 3), Some(0..3));
+        assert_eq!(d.columns(2, 4), Some(0..1));
+        assert_eq!(d.columns(3, 4), None);
+        assert_eq!(diagnostic((1, 3), (1, 3)).columns(1, 3), Some(3..4));
+    }
+}
@@ -0,0 +1,351 @@
+use std::ops::RangeInclusive;
+
+use jagged::index::RowIndex;
+
+use crate::{EditorState, Lines};
+
+/// A range of rows that can be folded into a single row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+    /// The first row of the fold.
+    pub start: usize,
+    /// The last row of the fold.
+    pub end: usize,
+    /// Whether the fold is closed, i.e. displayed as a single row.
+    pub closed: bool,
+}
+
+/// Determines how folds are computed with [`EditorState::compute_folds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoldMethod {
+    /// Folds the lines that are indented deeper than the line above them.
+    Indent,
+    /// Folds the lines between an opening bracket and its matching closing
+    /// bracket.
+    Bracket,
+}
+
+/// The folds of the text, ordered by their start and by their length in
+/// decreasing order, such that enclosing folds come first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FoldState {
+    folds: Vec<Fold>,
+}
+
+impl FoldState {
+    fn insert(&mut self, fold: Fold) {
+        if fold.end <= fold.start {
+            return;
+        }
+        if let Some(existing) = self
+            .folds
+            .iter_mut()
+            .find(|f| (f.start, f.end) == (fold.start, fold.end))
+        {
+            existing.closed = fold.closed;
+            return;
+        }
+        let index = self.folds.partition_point(|f| {
+            (f.start, usize::MAX - f.end) < (fold.start, usize::MAX - fold.end)
+        });
+        self.folds.insert(index, fold);
+    }
+
+    /// Returns the outermost closed fold that contains `row`.
+    pub(crate) fn closed_fold(&self, row: usize) -> Option<&Fold> {
+        self.folds
+            .iter()
+            .take_while(|fold| fold.start <= row)
+            .find(|fold| fold.closed && row <= fold.end)
+    }
+
+    /// Returns the first row that is displayed for `row`, i.e. the start of
+    /// the closed fold that contains it.
+    pub(crate) fn fold_start(&self, row: usize) -> usize {
+        self.closed_fold(row).map_or(row, |fold| fold.start)
+    }
+
+    /// Returns the last row that is hidden behind `row`, i.e. the end of
+    /// the closed fold that contains it.
+    pub(crate) fn fold_end(&self, row: usize) -> usize {
+        self.closed_fold(row).map_or(row, |fold| fold.end)
+    }
+
+    /// Returns the number of displayed rows in front of `row`.
+    pub(crate) fn visible_row(&self, row: usize) -> usize {
+        let row = self.fold_start(row);
+        let mut hidden = 0;
+        let mut covered = 0;
+        for fold in self.folds.iter().filter(|f| f.closed && f.start < row) {
+            // Nested folds are hidden by the enclosing fold.
+            let start = (fold.start + 1).max(covered);
+            if fold.end >= start {
+                hidden += fold.end + 1 - start;
+                covered = fold.end + 1;
+            }
+        }
+        row - hidden
+    }
+
+    /// Returns the row that is displayed as the `visible_row`-th row.
+    pub(crate) fn row_at_visible(&self, visible_row: usize) -> usize {
+        let mut row = 0;
+        for _ in 0..visible_row {
+            row = self.fold_end(row) + 1;
+        }
+        row
+    }
+
+    /// Moves the folds after the rows `row..row + old_rows` were replaced
+    /// by `new_rows` rows. Folds that become smaller than two rows are
+    /// removed.
+    pub(crate) fn shift(&mut self, row: usize, old_rows: usize, new_rows: usize) {
+        if self.folds.is_empty() || old_rows == new_rows {
+            return;
+        }
+        let shift = |r: usize| {
+            if r < row + old_rows.min(new_rows) {
+                r
+            } else if r >= row + old_rows {
+                r + new_rows - old_rows
+            } else {
+                // A removed row moves to the last replacing row.
+                (row + new_rows).saturating_sub(1).max(row)
+            }
+        };
+        for fold in &mut self.folds {
+            fold.start = shift(fold.start);
+            fold.end = shift(fold.end);
+        }
+        self.folds.retain(|fold| fold.end > fold.start);
+    }
+}
+
+impl EditorState {
+    /// Adds a fold of `rows`. The fold is closed.
+    pub fn add_fold(&mut self, rows: RangeInclusive<usize>) {
+        let end = (*rows.end()).min(self.lines.len().saturating_sub(1));
+        self.folds.insert(Fold {
+            start: *rows.start(),
+            end,
+            closed: true,
+        });
+        self.cursor.row = self.folds.fold_start(self.cursor.row);
+    }
+
+    /// Returns the folds, ordered by their start.
+    #[must_use]
+    pub fn folds(&self) -> &[Fold] {
+        &self.folds.folds
+    }
+
+    /// Removes all folds.
+    pub fn clear_folds(&mut self) {
+        self.folds = FoldState::default();
+    }
+
+    /// Replaces the folds by folds that are computed from the text. The
+    /// computed folds are open.
+    pub fn compute_folds(&mut self, method: FoldMethod) {
+        let folds = match method {
+            FoldMethod::Indent => indent_folds(&self.lines),
+            FoldMethod::Bracket => bracket_folds(&self.lines),
+        };
+        self.clear_folds();
+        for fold in folds {
+            self.folds.insert(fold);
+        }
+    }
+
+    /// Opens the closed fold at the cursor, or closes the innermost fold
+    /// at the cursor if it is open (`za`).
+    pub(crate) fn toggle_fold(&mut self) {
+        if self.folds.closed_fold(self.cursor.row).is_some() {
+            self.open_fold();
+        } else {
+            self.close_fold();
+        }
+    }
+
+    /// Opens the outermost closed fold at the cursor (`zo`).
+    pub(crate) fn open_fold(&mut self) {
+        let row = self.cursor.row;
+        if let Some(fold) = self
+            .folds
+            .folds
+            .iter_mut()
+            .find(|fold| fold.closed && (fold.start..=fold.end).contains(&row))
+        {
+            fold.closed = false;
+        }
+    }
+
+    /// Closes the innermost open fold at the cursor (`zc`).
+    pub(crate) fn close_fold(&mut self) {
+        let row = self.cursor.row;
+        if let Some(fold) = self
+            .folds
+            .folds
+            .iter_mut()
+            .rev()
+            .find(|fold| !fold.closed && (fold.start..=fold.end).contains(&row))
+        {
+            fold.closed = true;
+        }
+        self.cursor.row = self.folds.fold_start(row);
+    }
+
+    /// Opens (`zR`) or closes (`zM`) all folds.
+    pub(crate) fn set_all_folds_closed(&mut self, closed: bool) {
+        for fold in &mut self.folds.folds {
+            fold.closed = closed;
+        }
+        self.cursor.row = self.folds.fold_start(self.cursor.row);
+    }
+
+    /// Returns the text that is displayed for a closed fold.
+    pub(crate) fn fold_summary(&self, fold: &Fold) -> String {
+        let text: String = self
+            .lines
+            .get(RowIndex::new(fold.start))
+            .map(|line| line.iter().collect())
+            .unwrap_or_default();
+        let num_lines = fold.end - fold.start + 1;
+        format!("+-- {num_lines} lines: {}", text.trim())
+    }
+}
+
+fn indentation(line: &[char]) -> Option<usize> {
+    let indent = line.iter().take_while(|ch| ch.is_whitespace()).count();
+    (indent < line.len()).then_some(indent)
+}
+
+/// Folds the lines below a line that are indented deeper than the line.
+fn indent_folds(lines: &Lines) -> Vec<Fold> {
+    let indents: Vec<Option<usize>> = lines.iter_row().map(|line| indentation(line)).collect();
+    let mut folds = Vec::new();
+    for (start, indent) in indents.iter().enumerate() {
+        let Some(indent) = indent else {
+            continue;
+        };
+        let mut end = start;
+        for (row, other) in indents.iter().enumerate().skip(start + 1) {
+            match other {
+                Some(other) if other > indent => end = row,
+                Some(_) => break,
+                None => {}
+            }
+        }
+        if end > start {
+            folds.push(Fold {
+                start,
+                end,
+                closed: false,
+            });
+        }
+    }
+    folds
+}
+
+/// Folds the lines from an opening bracket to its matching closing bracket.
+fn bracket_folds(lines: &Lines) -> Vec<Fold> {
+    let mut folds = Vec::new();
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    for (row, line) in lines.iter_row().enumerate() {
+        for &ch in line {
+            let opening = match ch {
+                '{' | '(' | '[' => {
+                    stack.push((ch, row));
+                    continue;
+                }
+                '}' => '{',
+                ')' => '(',
+                ']' => '[',
+                _ => continue,
+            };
+            let Some(index) = stack.iter().rposition(|&(open, _)| open == opening) else {
+                continue;
+            };
+            let (_, start) = stack[index];
+            stack.truncate(index);
+            if row > start {
+                folds.push(Fold {
+                    start,
+                    end: row,
+                    closed: false,
+                });
+            }
+        }
+    }
+    folds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{DeleteLine, Execute, MoveDown, MoveUp};
+    use crate::Index2;
+
+    fn ranges(state: &EditorState) -> Vec<(usize, usize)> {
+        state.folds().iter().map(|f| (f.start, f.end)).collect()
+    }
+
+    #[test]
+    fn test_compute_folds() {
+        let mut state = EditorState::new(Lines::from(
+            "fn a() {\n    if b {\n        c();\n\n    }\n}\nd",
+        ));
+
+        state.compute_folds(FoldMethod::Indent);
+        assert_eq!(ranges(&state), vec![(0, 4), (1, 2)]);
+
+        state.compute_folds(FoldMethod::Bracket);
+        assert_eq!(ranges(&state), vec![(0, 5), (1, 4)]);
+    }
+
+    #[test]
+    fn test_visible_rows() {
+        let mut state = EditorState::new(Lines::from("0\n1\n2\n3\n4\n5\n6"));
+        state.add_fold(1..=4);
+        state.add_fold(2..=3);
+        state.add_fold(5..=6);
+        state.folds.folds[2].closed = false;
+
+        let folds = &state.folds;
+        assert_eq!(folds.visible_row(0), 0);
+        assert_eq!(folds.visible_row(3), 1);
+        assert_eq!(folds.visible_row(5), 2);
+        assert_eq!(folds.visible_row(6), 3);
+        assert_eq!(folds.row_at_visible(1), 1);
+        assert_eq!(folds.row_at_visible(2), 5);
+    }
+
+    #[test]
+    fn test_vertical_motions_skip_closed_folds() {
+        let mut state = EditorState::new(Lines::from("0\n1\n2\n3\n4"));
+        state.add_fold(1..=3);
+
+        MoveDown(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(1, 0));
+        MoveDown(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(4, 0));
+        MoveUp(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(1, 0));
+
+        state.open_fold();
+        MoveDown(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(2, 0));
+        state.toggle_fold();
+        assert_eq!(state.cursor, Index2::new(1, 0));
+    }
+
+    #[test]
+    fn test_folds_shift_with_edits() {
+        let mut state = EditorState::new(Lines::from("0\n1\n2\n3\n4"));
+        state.add_fold(2..=4);
+        state.add_fold(0..=1);
+
+        DeleteLine(1).execute(&mut state);
+        assert_eq!(ranges(&state), vec![(1, 3)]);
+    }
+}
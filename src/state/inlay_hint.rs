@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+
+use super::multi_cursor::RowShift;
+use crate::{EditorState, Index2};
+
+/// Text that is displayed inline in front of a position of the text, e.g.
+/// a type hint or a parameter name. Inlay hints are not part of the
+/// [`Lines`](crate::Lines) and cannot be edited or moved onto.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHint {
+    /// The position of the char in front of which the hint is displayed.
+    /// A column behind the end of the line displays the hint at the end.
+    pub position: Index2,
+    /// The displayed text.
+    pub text: String,
+}
+
+impl InlayHint {
+    /// Creates a new inlay hint in front of `position`.
+    #[must_use]
+    pub fn new<S: Into<String>>(position: Index2, text: S) -> Self {
+        Self {
+            position,
+            text: text.into(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+impl EditorState {
+    /// Replaces the inlay hints. The hints are moved along when the text
+    /// is edited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edtui::{EditorState, Index2, InlayHint, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("let x = 1;"));
+    /// state.set_inlay_hints(vec![InlayHint::new(Index2::new(0, 5), ": i32")]);
+    /// ```
+    pub fn set_inlay_hints(&mut self, mut hints: Vec<InlayHint>) {
+        hints.sort_by_key(|hint| (hint.position.row, hint.position.col));
+        self.inlay_hints = hints;
+    }
+
+    /// Returns the inlay hints, ordered by their position.
+    #[must_use]
+    pub fn inlay_hints(&self) -> &[InlayHint] {
+        &self.inlay_hints
+    }
+}
+
+/// Moves the inlay hints after a change of the text. Text inserted at the
+/// position of a hint is placed in front of it.
+pub(crate) fn shift_inlay_hints(hints: &mut [InlayHint], shift: &RowShift) {
+    for hint in hints {
+        hint.position = shift.apply(hint.position, true);
+    }
+}
+
+/// Returns the inlay hints of the line `row` that are displayed when the
+/// first `col_skips` chars are scrolled out of view.
+pub(crate) fn visible_inlay_hints(
+    hints: &[InlayHint],
+    row: usize,
+    col_skips: usize,
+) -> &[InlayHint] {
+    let start =
+        hints.partition_point(|hint| (hint.position.row, hint.position.col) < (row, col_skips));
+    let end = hints.partition_point(|hint| hint.position.row <= row);
+    &hints[start..end.max(start)]
+}
+
+/// Returns the line with the text of the visible `hints` inserted.
+pub(crate) fn display_line<'a>(line: &'a [char], hints: &[InlayHint]) -> Cow<'a, [char]> {
+    if hints.is_empty() {
+        return Cow::Borrowed(line);
+    }
+    let mut display = Vec::with_capacity(line.len());
+    let mut hints = hints.iter().peekable();
+    for col in 0..=line.len() {
+        while let Some(hint) = hints.next_if(|hint| hint.position.col <= col) {
+            display.extend(hint.text.chars());
+        }
+        if let Some(&ch) = line.get(col) {
+            display.push(ch);
+        }
+    }
+    Cow::Owned(display)
+}
+
+/// Maps the column `col` of a line to its index in the displayed chars,
+/// where the first `col_skips` chars are scrolled out of view.
+pub(crate) fn display_col(hints: &[InlayHint], col: usize, col_skips: usize) -> usize {
+    let hints_len: usize = hints
+        .iter()
+        .filter(|hint| (col_skips..=col).contains(&hint.position.col))
+        .map(InlayHint::len)
+        .sum();
+    col.saturating_sub(col_skips) + hints_len
+}
+
+/// Maps an index in the displayed chars to the column of the line. An index
+/// on an inlay hint maps to the char behind the hint.
+pub(crate) fn data_col(hints: &[InlayHint], display_col: usize, col_skips: usize) -> usize {
+    let mut offset = 0;
+    for hint in hints {
+        let start = hint.position.col.saturating_sub(col_skips) + offset;
+        if display_col < start {
+            break;
+        }
+        offset += hint.len();
+        if display_col < start + hint.len() {
+            return hint.position.col;
+        }
+    }
+    display_col.saturating_sub(offset) + col_skips
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Execute, InsertChar, MoveForward, SwitchMode};
+    use crate::{EditorMode, Lines};
+
+    fn hints() -> Vec<InlayHint> {
+        vec![
+            InlayHint::new(Index2::new(0, 1), "<a>"),
+            InlayHint::new(Index2::new(0, 3), "<b>"),
+            InlayHint::new(Index2::new(1, 0), "<c>"),
+        ]
+    }
+
+    #[test]
+    fn test_display_line() {
+        let line: Vec<char> = "xyz".chars().collect();
+        let hints = hints();
+        let visible = visible_inlay_hints(&hints, 0, 0);
+        let display: String = display_line(&line, visible).iter().collect();
+        assert_eq!(display, "x<a>yz<b>");
+
+        let visible = visible_inlay_hints(&hints, 0, 2);
+        assert_eq!(visible, &hints[1..2]);
+        let visible = visible_inlay_hints(&hints, 2, 0);
+        assert!(visible.is_empty());
+    }
+
+    #[test]
+    fn test_display_and_data_col() {
+        let hints = hints();
+        let hints = visible_inlay_hints(&hints, 0, 0);
+        assert_eq!(display_col(hints, 0, 0), 0);
+        assert_eq!(display_col(hints, 1, 0), 4);
+        assert_eq!(display_col(hints, 3, 0), 9);
+        assert_eq!(display_col(hints, 2, 1), 4);
+
+        assert_eq!(data_col(hints, 0, 0), 0);
+        assert_eq!(data_col(hints, 2, 0), 1);
+        assert_eq!(data_col(hints, 4, 0), 1);
+        assert_eq!(data_col(hints, 5, 0), 2);
+        assert_eq!(data_col(hints, 7, 0), 3);
+        assert_eq!(data_col(hints, 4, 1), 2);
+    }
+
+    #[test]
+    fn test_inlay_hints_are_skipped_and_shifted() {
+        let mut state = EditorState::new(Lines::from("xyz"));
+        state.set_inlay_hints(hints());
+
+        MoveForward(1).execute(&mut state);
+        assert_eq!(state.cursor, Index2::new(0, 1));
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('-').execute(&mut state);
+        assert_eq!(state.lines, Lines::from("x-yz"));
+        assert_eq!(state.inlay_hints()[0].position, Index2::new(0, 2));
+        assert_eq!(state.inlay_hints()[1].position, Index2::new(0, 4));
+    }
+}
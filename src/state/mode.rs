@@ -1,11 +1,16 @@
 /// The editor mode.
+///
+/// Modes may be added in future versions, matches on the mode need a
+/// wildcard arm.
 #[derive(Default, Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[non_exhaustive]
 pub enum EditorMode {
     #[default]
     Normal,
     Insert,
     Visual,
     Search,
+    Command,
 }
 
 impl EditorMode {
@@ -17,6 +22,7 @@ impl EditorMode {
             Self::Insert => "Insert".to_string(),
             Self::Visual => "Visual".to_string(),
             Self::Search => "Search".to_string(),
+            Self::Command => "Command".to_string(),
         }
     }
 }
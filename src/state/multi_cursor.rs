@@ -0,0 +1,320 @@
+//! Multiple cursors.
+//!
+//! Besides the primary cursor, the editor can hold any number of secondary
+//! cursors, each with its own selection. An action is applied at every
+//! cursor by executing it once per cursor, see
+//! [`EditorState::for_each_cursor`].
+//!
+//! When the text is changed at one cursor, the other cursors are shifted
+//! so that they keep their place in the text. The change is compared to
+//! the previous text of the modified rows, and positions behind the
+//! changed region are moved by the difference in length.
+use jagged::index::RowIndex;
+
+use crate::helper::is_word_char;
+use crate::{EditorState, Index2};
+
+use super::selection::Selection;
+
+/// A cursor in addition to the primary cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SecondaryCursor {
+    pub(crate) cursor: Index2,
+    pub(crate) selection: Option<Selection>,
+}
+
+impl SecondaryCursor {
+    pub(crate) fn new(cursor: Index2, selection: Option<Selection>) -> Self {
+        Self { cursor, selection }
+    }
+}
+
+impl EditorState {
+    /// Adds a secondary cursor. Does nothing if there is already a cursor
+    /// at this position.
+    pub fn add_cursor(&mut self, cursor: Index2) {
+        if cursor != self.cursor && !self.cursors.iter().any(|c| c.cursor == cursor) {
+            self.cursors.push(SecondaryCursor::new(cursor, None));
+        }
+    }
+
+    /// Returns the positions of all cursors. The primary cursor comes
+    /// first, followed by the secondary cursors in the order they were
+    /// added.
+    #[must_use]
+    pub fn cursors(&self) -> Vec<Index2> {
+        std::iter::once(self.cursor)
+            .chain(self.cursors.iter().map(|c| c.cursor))
+            .collect()
+    }
+
+    /// Removes all secondary cursors.
+    pub fn clear_cursors(&mut self) {
+        self.cursors.clear();
+    }
+
+    /// Runs `f` once at every cursor. While `f` runs, `cursor` and
+    /// `selection` hold the position and selection of one of the cursors.
+    /// All changes are undone as a single step.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use edtui::actions::{Execute, InsertChar};
+    /// use edtui::{EditorState, Index2, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("a\nb"));
+    /// state.add_cursor(Index2::new(1, 0));
+    /// state.for_each_cursor(|state| InsertChar('-').execute(state));
+    /// assert_eq!(state.lines, Lines::from("-a\n-b"));
+    /// ```
+    pub fn for_each_cursor(&mut self, mut f: impl FnMut(&mut EditorState)) {
+        if self.cursors.is_empty() {
+            f(self);
+            return;
+        }
+
+        self.transaction(|state| {
+            let primary = SecondaryCursor::new(state.cursor, state.selection.take());
+            let mut cursors = vec![primary];
+            cursors.append(&mut state.cursors);
+
+            for i in 0..cursors.len() {
+                let active = cursors.remove(i);
+                state.cursor = active.cursor;
+                state.selection = active.selection;
+                state.cursors = cursors;
+
+                f(state);
+
+                cursors = std::mem::take(&mut state.cursors);
+                let active = SecondaryCursor::new(state.cursor, state.selection.take());
+                cursors.insert(i, active);
+            }
+
+            let primary = cursors.remove(0);
+            state.cursor = primary.cursor;
+            state.selection = primary.selection;
+            state.cursors = cursors;
+        });
+        self.merge_cursors();
+    }
+
+    /// Moves cursors that are out of bounds back into the buffer and
+    /// removes cursors that share a position with another cursor.
+    fn merge_cursors(&mut self) {
+        let last_row = self.lines.len().saturating_sub(1);
+        let mut seen = vec![self.cursor];
+        self.cursors.retain_mut(|cursor| {
+            cursor.cursor.row = cursor.cursor.row.min(last_row);
+            if seen.contains(&cursor.cursor) {
+                return false;
+            }
+            seen.push(cursor.cursor);
+            true
+        });
+    }
+}
+
+/// Shifts the secondary cursors after a change of the text.
+pub(crate) fn shift_cursors(cursors: &mut [SecondaryCursor], shift: &RowShift) {
+    for cursor in cursors {
+        cursor.cursor = shift.apply(cursor.cursor, false);
+        if let Some(selection) = &mut cursor.selection {
+            selection.start = shift.apply(selection.start, false);
+            selection.end = shift.apply(selection.end, false);
+        }
+    }
+}
+
+/// Maps positions in the text before a change of a block of rows to
+/// positions after the change.
+pub(crate) struct RowShift {
+    row: usize,
+    old_rows: usize,
+    new_rows: usize,
+    old: Vec<char>,
+    new: Vec<char>,
+    /// The number of unchanged chars at the start of the block.
+    prefix: usize,
+    /// The number of unchanged chars at the end of the block.
+    suffix: usize,
+}
+
+impl RowShift {
+    pub(crate) fn new(row: usize, old: &[Vec<char>], new: &[Vec<char>]) -> Self {
+        let (old_rows, new_rows) = (old.len(), new.len());
+        let (old, new) = (old.join(&'\n'), new.join(&'\n'));
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        Self {
+            row,
+            old_rows,
+            new_rows,
+            old,
+            new,
+            prefix,
+            suffix,
+        }
+    }
+
+    /// Maps `pos` to its position after the change. Positions inside the
+    /// changed region move to its start, or to its end with `right_gravity`.
+    /// With `right_gravity`, text inserted at `pos` is placed before it.
+    pub(crate) fn apply(&self, pos: Index2, right_gravity: bool) -> Index2 {
+        if pos.row < self.row {
+            return pos;
+        }
+        if pos.row >= self.row + self.old_rows {
+            let row = pos.row + self.new_rows - self.old_rows;
+            return Index2::new(row, pos.col);
+        }
+
+        let offset = self.offset(pos);
+        let offset = if offset < self.prefix || (offset == self.prefix && !right_gravity) {
+            offset
+        } else if offset >= self.old.len() - self.suffix {
+            offset + self.new.len() - self.old.len()
+        } else if right_gravity {
+            self.new.len() - self.suffix
+        } else {
+            self.prefix
+        };
+        self.position(offset)
+    }
+
+    /// Converts a position within the old rows into a char offset.
+    fn offset(&self, pos: Index2) -> usize {
+        let mut offset = 0;
+        for (row, line) in (self.row..).zip(self.old.split(|&ch| ch == '\n')) {
+            if row == pos.row {
+                return offset + pos.col.min(line.len());
+            }
+            offset += line.len() + 1;
+        }
+        self.old.len()
+    }
+
+    /// Converts a char offset into a position within the new rows.
+    fn position(&self, mut offset: usize) -> Index2 {
+        for (row, line) in (self.row..).zip(self.new.split(|&ch| ch == '\n')) {
+            if offset <= line.len() {
+                return Index2::new(row, offset);
+            }
+            offset -= line.len() + 1;
+        }
+        Index2::new(self.row, 0)
+    }
+}
+
+/// Returns the start of the next occurrence of `word` after `from` that is
+/// delimited by non-word characters. The search wraps around the end of
+/// the buffer.
+pub(crate) fn find_next_word(state: &EditorState, word: &[char], from: Index2) -> Option<Index2> {
+    let lines = &state.lines;
+    let num_rows = lines.len();
+    for i in 0..=num_rows {
+        let row = (from.row + i) % num_rows.max(1);
+        let Some(line) = lines.get(RowIndex::new(row)) else {
+            continue;
+        };
+        let mut matches = (0..line.len()).filter(|&col| {
+            line[col..].starts_with(word)
+                && (col == 0 || !is_word_char(line[col - 1]))
+                && line
+                    .get(col + word.len())
+                    .is_none_or(|&ch| !is_word_char(ch))
+        });
+        let found = match i {
+            0 => matches.find(|&col| col > from.col),
+            _ if i == num_rows => matches.find(|&col| col <= from.col),
+            _ => matches.next(),
+        };
+        if let Some(col) = found {
+            return Some(Index2::new(row, col));
+        }
+    }
+    None
+}
+
+/// Returns the word under `index` and the column where it starts.
+pub(crate) fn word_at(state: &EditorState, index: Index2) -> Option<(Vec<char>, usize)> {
+    let line = state.lines.get(RowIndex::new(index.row))?;
+    if !line.get(index.col).is_some_and(|&ch| is_word_char(ch)) {
+        return None;
+    }
+    let start = line[..index.col]
+        .iter()
+        .rposition(|&ch| !is_word_char(ch))
+        .map_or(0, |i| i + 1);
+    let end = line[index.col..]
+        .iter()
+        .position(|&ch| !is_word_char(ch))
+        .map_or(line.len(), |i| index.col + i);
+    Some((line[start..end].to_vec(), start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Execute, InsertChar, LineBreak, RemoveChar};
+    use crate::Lines;
+
+    #[test]
+    fn test_for_each_cursor_same_row() {
+        let mut state = EditorState::new(Lines::from("ab ab ab"));
+        state.add_cursor(Index2::new(0, 3));
+        state.add_cursor(Index2::new(0, 6));
+
+        state.for_each_cursor(|state| InsertChar('x').execute(state));
+        assert_eq!(state.lines, Lines::from("xab xab xab"));
+        assert_eq!(
+            state.cursors(),
+            vec![Index2::new(0, 1), Index2::new(0, 5), Index2::new(0, 9)]
+        );
+
+        state.for_each_cursor(|state| RemoveChar(1).execute(state));
+        assert_eq!(state.lines, Lines::from("xb xb xb"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("xab xab xab"));
+    }
+
+    #[test]
+    fn test_for_each_cursor_line_break() {
+        let mut state = EditorState::new(Lines::from("ab\ncd"));
+        state.cursor = Index2::new(0, 1);
+        state.add_cursor(Index2::new(1, 1));
+
+        state.for_each_cursor(|state| LineBreak(1).execute(state));
+        assert_eq!(state.lines, Lines::from("a\nb\nc\nd"));
+        assert_eq!(state.cursors(), vec![Index2::new(1, 0), Index2::new(3, 0)]);
+    }
+
+    #[test]
+    fn test_merge_cursors() {
+        let mut state = EditorState::new(Lines::from("abc"));
+        state.add_cursor(Index2::new(0, 1));
+        state.for_each_cursor(|state| state.cursor = Index2::new(0, 0));
+        assert_eq!(state.cursors(), vec![Index2::new(0, 0)]);
+    }
+
+    #[test]
+    fn test_find_next_word() {
+        let state = EditorState::new(Lines::from("ab abc ab\nab"));
+        let word = ['a', 'b'];
+        let next = find_next_word(&state, &word, Index2::new(0, 0));
+        assert_eq!(next, Some(Index2::new(0, 7)));
+        let next = find_next_word(&state, &word, Index2::new(0, 7));
+        assert_eq!(next, Some(Index2::new(1, 0)));
+        let next = find_next_word(&state, &word, Index2::new(1, 0));
+        assert_eq!(next, Some(Index2::new(0, 0)));
+    }
+}
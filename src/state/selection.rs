@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
 
-use crate::{Index2, Lines};
+use jagged::index::RowIndex;
+
+use crate::{helper::grapheme_end, Index2, Lines};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Selection {
@@ -74,16 +76,29 @@ impl Selection {
         (self.start, self.end) = (self.end, self.start);
     }
 
+    /// Returns the last selected char, extended to the end of its
+    /// grapheme cluster.
+    fn grapheme_end(&self, lines: &Lines) -> Index2 {
+        let end = self.end();
+        match lines.get(RowIndex::new(end.row)) {
+            Some(line) if end.col < line.len() => {
+                Index2::new(end.row, grapheme_end(line, end.col) - 1)
+            }
+            _ => end,
+        }
+    }
+
     /// Copies a selection from `Lines`.
     #[must_use]
     pub fn copy_from(&self, lines: &Lines) -> Lines {
-        lines.copy_range(self.start()..=self.end())
+        lines.copy_range(self.start()..=self.grapheme_end(lines))
     }
 
     /// Extracts a selection from `Lines`.
     #[must_use]
     pub fn extract_from(&self, lines: &mut Lines) -> Lines {
-        lines.extract(self.start()..=self.end())
+        let end = self.grapheme_end(lines);
+        lines.extract(self.start()..=end)
     }
 
     /// Returns the start and end column of the selection in the given row.
@@ -0,0 +1,417 @@
+use jagged::index::RowIndex;
+
+use super::multi_cursor::{RowShift, SecondaryCursor};
+use super::selection::Selection;
+use crate::actions::{Execute, SwitchMode};
+use crate::completion::CompletionItem;
+use crate::helper::{insert_char, is_word_char, line_break};
+use crate::snippet::Snippet;
+use crate::{EditorMode, EditorState, Index2};
+
+/// The registered snippets and the snippet that is currently edited.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnippetState {
+    pub(crate) snippets: Vec<Snippet>,
+    /// The tabstops of the most recently expanded snippet. Ends when the
+    /// final tabstop is reached or insert mode is left.
+    pub(crate) session: Option<SnippetSession>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SnippetSession {
+    /// The tabstops in the order they are visited, i.e. `$1`, `$2`, ...
+    /// and `$0` last.
+    tabstops: Vec<Tabstop>,
+    /// The index of the current tabstop.
+    current: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Tabstop {
+    ranges: Vec<TabstopRange>,
+    choices: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct TabstopRange {
+    start: Index2,
+    /// The exclusive end of the range.
+    end: Index2,
+    /// The position of the range in the snippet, used to order empty
+    /// ranges at the same position.
+    rank: usize,
+}
+
+impl TabstopRange {
+    /// Returns whether the start and the end of the range move behind text
+    /// that is typed at them while the `active` ranges are edited.
+    fn gravity(&self, active: &[TabstopRange]) -> (bool, bool) {
+        let touching = active
+            .iter()
+            .find(|a| self.start <= a.end && a.start <= self.end);
+        let Some(a) = touching else {
+            return (false, false);
+        };
+        if (self.start, self.end) == (a.start, a.end) {
+            if self.start == self.end {
+                let right = self.rank > a.rank;
+                return (right, right);
+            }
+            return (false, true);
+        }
+        if self.start <= a.start && a.end <= self.end {
+            // The range encloses the active range and grows with it.
+            (false, true)
+        } else if self.end <= a.start {
+            (false, false)
+        } else {
+            (true, true)
+        }
+    }
+}
+
+impl SnippetState {
+    /// Shifts the tabstops after a change of the text. The current tabstop
+    /// grows with text typed at its end, adjacent tabstops stay in front
+    /// of or behind the typed text.
+    pub(crate) fn shift(&mut self, shift: &RowShift) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        let current = session.current;
+        let active = session.tabstops[current].ranges.clone();
+        for (i, tabstop) in session.tabstops.iter_mut().enumerate() {
+            for range in &mut tabstop.ranges {
+                let (start, end) = if i == current {
+                    (false, true)
+                } else {
+                    range.gravity(&active)
+                };
+                range.start = shift.apply(range.start, start);
+                range.end = shift.apply(range.end, end);
+            }
+        }
+    }
+
+    /// Returns completion items for the snippets whose trigger starts with
+    /// `prefix`.
+    pub(crate) fn completion_items(
+        &self,
+        file_type: Option<&str>,
+        prefix: &str,
+    ) -> Vec<CompletionItem> {
+        self.snippets
+            .iter()
+            .filter(|s| !prefix.is_empty() && s.trigger.starts_with(prefix))
+            .filter(|s| s.matches_file_type(file_type))
+            .map(|s| {
+                let detail = s.description.clone().unwrap_or_else(|| "snippet".into());
+                CompletionItem::new(s.trigger.clone())
+                    .detail(detail)
+                    .snippet(s.body.clone())
+            })
+            .collect()
+    }
+}
+
+impl EditorState {
+    /// Sets the file type of the text, e.g. `rs`. Snippets that are
+    /// restricted to other file types are not expanded.
+    pub fn set_file_type(&mut self, file_type: Option<&str>) {
+        self.file_type = file_type.map(str::to_string);
+    }
+
+    /// Registers a snippet. Typing the trigger of the snippet and pressing
+    /// `Tab` in insert mode expands the snippet. Snippets registered later
+    /// take precedence over earlier snippets with the same trigger.
+    pub fn add_snippet(&mut self, snippet: Snippet) {
+        self.snippets.snippets.push(snippet);
+    }
+
+    /// Inserts a snippet at the cursor, switches to insert mode and selects
+    /// the first tabstop. Lines of the snippet are indented like the line
+    /// of the cursor.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use edtui::snippet::Snippet;
+    /// use edtui::{EditorState, Index2, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("let x = ;"));
+    /// state.cursor = Index2::new(0, 8);
+    /// state.insert_snippet(&Snippet::new("vec", "vec![${1:0}; $2]").unwrap());
+    /// assert_eq!(state.lines, Lines::from("let x = vec![0; ];"));
+    /// assert_eq!(state.cursor, Index2::new(0, 14));
+    /// ```
+    pub fn insert_snippet(&mut self, snippet: &Snippet) {
+        if self.mode != EditorMode::Insert {
+            SwitchMode(EditorMode::Insert).execute(self);
+        }
+        self.clear_cursors();
+        self.selection = None;
+        self.completion.close();
+
+        let expansion = snippet.expand();
+        let row = self.cursor.row;
+        let indent: Vec<char> = self
+            .lines
+            .get(RowIndex::new(row))
+            .map(|line| {
+                line.iter()
+                    .take_while(|ch| ch.is_whitespace())
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // The position of every char of the snippet after it is inserted.
+        let mut positions = Vec::with_capacity(expansion.text.len() + 1);
+        self.modify_rows(row..row + 1, |state| {
+            for &ch in &expansion.text {
+                positions.push(state.cursor);
+                if ch == '\n' {
+                    line_break(&mut state.lines, &mut state.cursor);
+                    for &ch in &indent {
+                        insert_char(&mut state.lines, &mut state.cursor, ch, false);
+                    }
+                } else {
+                    insert_char(&mut state.lines, &mut state.cursor, ch, false);
+                }
+            }
+            positions.push(state.cursor);
+        });
+
+        let mut ranges: Vec<_> = expansion
+            .tabstops
+            .iter()
+            .flat_map(|(&index, ranges)| ranges.iter().map(move |range| (index, range.clone())))
+            .collect();
+        ranges.sort_by_key(|(index, range)| (range.start, range.end, *index));
+
+        let mut tabstops: Vec<(usize, Tabstop)> = Vec::new();
+        for (rank, (index, range)) in ranges.into_iter().enumerate() {
+            let range = TabstopRange {
+                start: positions[range.start],
+                end: positions[range.end],
+                rank,
+            };
+            match tabstops.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, tabstop)) => tabstop.ranges.push(range),
+                None => {
+                    let choices = expansion.choices.get(&index).cloned().unwrap_or_default();
+                    let tabstop = Tabstop {
+                        ranges: vec![range],
+                        choices,
+                    };
+                    tabstops.push((index, tabstop));
+                }
+            }
+        }
+        // Visit the tabstops in the order of their number and `$0` last.
+        // The end of the snippet is the final tabstop if there is no `$0`.
+        tabstops.sort_by_key(|(index, _)| (*index == 0, *index));
+        if tabstops.last().is_none_or(|(index, _)| *index != 0) {
+            let end = positions[expansion.text.len()];
+            let range = TabstopRange {
+                start: end,
+                end,
+                rank: usize::MAX,
+            };
+            let tabstop = Tabstop {
+                ranges: vec![range],
+                choices: Vec::new(),
+            };
+            tabstops.push((0, tabstop));
+        }
+
+        self.snippets.session = Some(SnippetSession {
+            tabstops: tabstops.into_iter().map(|(_, tabstop)| tabstop).collect(),
+            current: 0,
+        });
+        self.select_tabstop(0);
+    }
+
+    /// Expands the snippet whose trigger is in front of the cursor. Returns
+    /// whether a snippet was expanded.
+    pub(crate) fn expand_snippet(&mut self) -> bool {
+        let Index2 { row, col } = self.cursor;
+        let Some(line) = self.lines.get(RowIndex::new(row)) else {
+            return false;
+        };
+        let before = &line[..col.min(line.len())];
+        let file_type = self.file_type.as_deref();
+        let snippet = self.snippets.snippets.iter().rev().find(|snippet| {
+            let trigger: Vec<char> = snippet.trigger.chars().collect();
+            let start = before.len().saturating_sub(trigger.len());
+            !trigger.is_empty()
+                && before.ends_with(&trigger)
+                && (start == 0 || !is_word_char(before[start - 1]))
+                && snippet.matches_file_type(file_type)
+        });
+        let Some(snippet) = snippet.cloned() else {
+            return false;
+        };
+
+        let len = snippet.trigger.chars().count();
+        self.modify_rows(row..row + 1, |state| {
+            for _ in 0..len {
+                state.cursor.col -= 1;
+                state.lines.remove(state.cursor);
+            }
+        });
+        self.insert_snippet(&snippet);
+        true
+    }
+
+    /// Selects the next tabstop of the current snippet. Returns whether
+    /// there was a snippet to jump in.
+    pub(crate) fn next_tabstop(&mut self) -> bool {
+        let Some(session) = &self.snippets.session else {
+            return false;
+        };
+        let next = (session.current + 1).min(session.tabstops.len() - 1);
+        self.select_tabstop(next);
+        true
+    }
+
+    /// Selects the previous tabstop of the current snippet. Returns whether
+    /// there was a snippet to jump in.
+    pub(crate) fn previous_tabstop(&mut self) -> bool {
+        let Some(session) = &self.snippets.session else {
+            return false;
+        };
+        self.select_tabstop(session.current.saturating_sub(1));
+        true
+    }
+
+    /// Moves the cursors to the ranges of a tabstop and selects them. The
+    /// choices of the tabstop are offered in the completion popup. The
+    /// snippet ends at the final tabstop.
+    fn select_tabstop(&mut self, index: usize) {
+        let Some(session) = &mut self.snippets.session else {
+            return;
+        };
+        session.current = index;
+        let tabstop = session.tabstops[index].clone();
+        if index + 1 == session.tabstops.len() {
+            self.snippets.session = None;
+        }
+
+        self.clear_cursors();
+        for (i, range) in tabstop.ranges.iter().enumerate() {
+            let selection = self.placeholder_selection(range.start, range.end);
+            if i == 0 {
+                self.cursor = range.end;
+                self.selection = selection;
+            } else if range.end != self.cursor {
+                self.cursors
+                    .push(SecondaryCursor::new(range.end, selection));
+            }
+        }
+
+        if tabstop.choices.is_empty() {
+            self.completion.close();
+        } else {
+            let items = tabstop
+                .choices
+                .into_iter()
+                .map(CompletionItem::new)
+                .collect();
+            self.completion.show_choices(items);
+        }
+    }
+
+    /// Returns the selection of the text between `start` and the exclusive
+    /// `end`, or `None` if the text is empty.
+    fn placeholder_selection(&self, start: Index2, end: Index2) -> Option<Selection> {
+        let end = match end {
+            _ if start >= end => return None,
+            Index2 { row, col: 0 } => {
+                let len = self.lines.len_col(row - 1).unwrap_or_default();
+                Index2::new(row - 1, len)
+            }
+            Index2 { row, col } => Index2::new(row, col - 1),
+        };
+        Some(Selection::new(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{DeleteChar, InsertChar};
+    use crate::Lines;
+
+    fn insert_mode(text: &str, cursor: Index2) -> EditorState {
+        let mut state = EditorState::new(Lines::from(text));
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        state.cursor = cursor;
+        state
+    }
+
+    #[test]
+    fn test_expand_snippet_and_jump() {
+        let mut state = insert_mode("  fn", Index2::new(0, 4));
+        state.add_snippet(Snippet::new("fn", "fn ${1:name}($2) {\n\t$0\n}").unwrap());
+
+        assert!(state.expand_snippet());
+        assert_eq!(state.lines, Lines::from("  fn name() {\n  \t\n  }"));
+        assert_eq!(state.cursor, Index2::new(0, 9));
+        let selection = state.selection.clone().unwrap();
+        assert_eq!(
+            (selection.start(), selection.end()),
+            (Index2::new(0, 5), Index2::new(0, 8))
+        );
+
+        state.for_each_cursor(|state| InsertChar('f').execute(state));
+        assert_eq!(state.lines, Lines::from("  fn f() {\n  \t\n  }"));
+
+        assert!(state.next_tabstop());
+        assert_eq!(state.cursor, Index2::new(0, 7));
+        assert!(state.previous_tabstop());
+        assert_eq!(state.cursor, Index2::new(0, 6));
+
+        state.next_tabstop();
+        state.next_tabstop();
+        assert_eq!(state.cursor, Index2::new(1, 3));
+        assert!(!state.next_tabstop());
+    }
+
+    #[test]
+    fn test_mirrored_tabstops() {
+        let mut state = insert_mode("", Index2::new(0, 0));
+        state.insert_snippet(&Snippet::new("", "<${1:div}>$0</$1>").unwrap());
+        assert_eq!(state.cursors(), vec![Index2::new(0, 4), Index2::new(0, 10)]);
+
+        state.for_each_cursor(|state| InsertChar('p').execute(state));
+        assert_eq!(state.lines, Lines::from("<p></p>"));
+        state.for_each_cursor(|state| DeleteChar(1).execute(state));
+        state.for_each_cursor(|state| InsertChar('a').execute(state));
+        assert_eq!(state.lines, Lines::from("<a></a>"));
+
+        state.next_tabstop();
+        assert_eq!(state.cursors(), vec![Index2::new(0, 3)]);
+    }
+
+    #[test]
+    fn test_adjacent_tabstops() {
+        let mut state = insert_mode("", Index2::new(0, 0));
+        state.insert_snippet(&Snippet::new("", "${1:a}$2").unwrap());
+        state.for_each_cursor(|state| InsertChar('x').execute(state));
+        state.for_each_cursor(|state| InsertChar('y').execute(state));
+        state.next_tabstop();
+        assert_eq!(state.cursor, Index2::new(0, 2));
+    }
+
+    #[test]
+    fn test_snippet_file_types() {
+        let mut state = insert_mode("fn", Index2::new(0, 2));
+        state.add_snippet(Snippet::new("fn", "function").unwrap().file_type("js"));
+        assert!(!state.expand_snippet());
+
+        state.set_file_type(Some("js"));
+        assert!(state.expand_snippet());
+        assert_eq!(state.lines, Lines::from("function"));
+    }
+}
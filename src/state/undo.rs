@@ -1,80 +1,1030 @@
-//! This module contains a rather brute-force implementation of undo and redo functionality
-//! for a simple text editor. It stores the entire editor state at each action.
+//! Undo and redo based on a tree of recorded edits.
 //!
-//! While this approach works for basic undo/redo needs, it may not be efficient for more
-//! complex usage. In the long run, this should be replaced with an action-based mechanism.
+//! Every change of the buffer is recorded as an [`Edit`] that replaces a
+//! contiguous block of rows with another block. An edit only holds the rows
+//! it touches, so recording it costs O(changed rows) instead of O(buffer).
+//! Undoing an edit applies its inverse, i.e. swaps the two blocks back.
+//!
+//! Edits are grouped into steps. A new step is started with
+//! [`EditorState::capture`], and each call to `undo` or `redo` reverts or
+//! reapplies one step as a whole, including the cursor and selection.
+//! While an insert session or a transaction is open, `capture` does not
+//! start a new step, so that all of its edits are undone together.
+//!
+//! Steps are stored in a tree. Each node is a text state and holds the step
+//! that leads from its parent to it. Making a change after an undo starts a
+//! new branch instead of discarding the undone changes. States are numbered
+//! in the order they were created, which allows to travel through the
+//! history chronologically across branches, similar to vim's `g-` and `g+`.
+//!
+//! The history is bounded by its memory footprint rather than by the number
+//! of steps. If the limit is exceeded, the oldest steps are discarded.
+//!
+//! The history can be saved and restored across sessions, see
+//! [`EditorState::save_undo_history`].
+mod persist;
+
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
+
+use jagged::index::RowIndex;
+
 use crate::{EditorState, Index2, Lines};
 
+use super::diagnostic::Diagnostic;
+use super::fold::FoldState;
+use super::inlay_hint::{shift_inlay_hints, InlayHint};
+use super::multi_cursor::{shift_cursors, RowShift, SecondaryCursor};
+use super::selection::Selection;
+use super::snippet::SnippetState;
+
+pub use self::persist::UndoHistoryError;
+
+/// The default memory limit of the undo history in bytes.
+pub(crate) const DEFAULT_UNDO_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The id of the initial text state.
+const ROOT: usize = 0;
+
 #[derive(Debug, Clone)]
 pub(crate) struct Stack {
-    inner: Vec<UndoState>,
-    max_size: usize,
+    /// The text states by id. Ids increase in creation order.
+    nodes: BTreeMap<usize, Node>,
+    /// The oldest text state that can be restored.
+    root: usize,
+    /// The current text state.
+    current: usize,
+    /// The id of the next text state.
+    next_id: usize,
+    /// The approximate number of bytes occupied by all steps.
+    size: usize,
+    /// The maximum number of bytes the history may occupy.
+    limit: usize,
+    /// Whether the current state accepts new edits.
+    open: bool,
+    /// The cursor and selection before the step that is started next.
+    pending: Option<(Index2, Option<Selection>)>,
+    /// Whether the editor is in an insert session.
+    insert_session: bool,
+    /// The number of nested transactions.
+    transaction_depth: usize,
+    /// Whether moving the cursor in an insert session starts a new step.
+    break_on_cursor_move: bool,
+    /// The cursor position after the last recorded edit.
+    last_cursor: Option<Index2>,
+}
+
+/// A text state in the undo tree.
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The child that is restored by a redo.
+    redo_child: Option<usize>,
+    /// The step that leads from the parent to this state.
+    step: Step,
+    /// The time of the last change of this state.
+    time: SystemTime,
+}
+
+impl Node {
+    fn new(parent: Option<usize>, step: Step) -> Self {
+        Self {
+            parent,
+            children: Vec::new(),
+            redo_child: None,
+            step,
+            time: SystemTime::now(),
+        }
+    }
 }
 
 impl Stack {
     pub(crate) fn new() -> Self {
+        let root = Node::new(None, Step::new(Index2::default(), None));
         Self {
-            inner: Vec::new(),
-            max_size: 100,
+            nodes: BTreeMap::from([(ROOT, root)]),
+            root: ROOT,
+            current: ROOT,
+            next_id: ROOT + 1,
+            size: 0,
+            limit: DEFAULT_UNDO_MEMORY_LIMIT,
+            open: false,
+            pending: None,
+            insert_session: false,
+            transaction_depth: 0,
+            break_on_cursor_move: false,
+            last_cursor: None,
+        }
+    }
+
+    /// Whether edits are currently grouped into the open step.
+    fn is_grouping(&self) -> bool {
+        self.insert_session || self.transaction_depth > 0
+    }
+
+    /// Starts a new undo step. The step is added to the tree with its
+    /// first edit, so that steps without edits leave no trace.
+    fn start_step(&mut self, cursor: Index2, selection: Option<Selection>) {
+        self.pending = Some((cursor, selection));
+        self.open = false;
+    }
+
+    /// Records an edit into the current step.
+    ///
+    /// `before` is the cursor position before the edit and `after` the
+    /// position after the edit.
+    fn record(
+        &mut self,
+        edit: Edit,
+        before: Index2,
+        after: Index2,
+        selection: Option<Selection>,
+    ) {
+        let cursor_moved = self.last_cursor.is_some_and(|cursor| cursor != before);
+        if self.break_on_cursor_move
+            && self.insert_session
+            && self.transaction_depth == 0
+            && cursor_moved
+        {
+            self.start_step(before, selection.clone());
+        }
+        if !self.open {
+            let (cursor, selection) = self.pending.take().unwrap_or((before, selection));
+            self.push_node(Step::new(cursor, selection));
+        }
+        if let Some(node) = self.nodes.get_mut(&self.current) {
+            self.size = self.size.saturating_sub(node.step.size());
+            node.step.push(edit);
+            node.step.cursor_after = after;
+            node.time = SystemTime::now();
+            self.size += node.step.size();
         }
+        self.last_cursor = Some(after);
+
+        self.enforce_limit();
+    }
+
+    /// Adds a new state as child of the current state and makes it current.
+    fn push_node(&mut self, step: Step) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.size += step.size();
+        self.nodes.insert(id, Node::new(Some(self.current), step));
+        if let Some(parent) = self.nodes.get_mut(&self.current) {
+            parent.children.push(id);
+            parent.redo_child = Some(id);
+        }
+        self.current = id;
+        self.open = true;
+    }
+
+    /// Closes the current step. The next edit starts a new step.
+    fn close(&mut self) {
+        self.open = false;
+        self.pending = None;
+        self.last_cursor = None;
+    }
+
+    /// Returns the path of states from the root to `id`.
+    fn path_to(&self, id: usize) -> Vec<usize> {
+        let mut path = vec![id];
+        let mut node = id;
+        while let Some(parent) = self.nodes.get(&node).and_then(|node| node.parent) {
+            path.push(parent);
+            node = parent;
+        }
+        path.reverse();
+        path
     }
 
-    pub(crate) fn pop(&mut self) -> Option<UndoState> {
-        self.inner.pop()
+    /// Whether `ancestor` lies on the path from the root to `id`.
+    fn is_ancestor(&self, ancestor: usize, id: usize) -> bool {
+        let mut node = Some(id);
+        while let Some(id) = node {
+            if id == ancestor {
+                return true;
+            }
+            node = self.nodes.get(&id).and_then(|node| node.parent);
+        }
+        false
     }
 
-    pub(crate) fn push(&mut self, value: UndoState) {
-        self.inner.push(value);
-        if self.len() > self.max_size {
-            self.remove(0);
+    /// Sets the memory limit in bytes and discards steps exceeding it.
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.enforce_limit();
+    }
+
+    /// Discards the oldest steps until the history fits into the memory
+    /// limit. The step leading to the current state is always kept.
+    fn enforce_limit(&mut self) {
+        while self.size > self.limit {
+            let Some(id) = self.find_discardable() else {
+                break;
+            };
+            self.discard(id);
         }
     }
 
-    fn len(&mut self) -> usize {
-        self.inner.len()
+    /// Finds the oldest state that can be discarded. That is either a leaf
+    /// of a branch that does not contain the current state, or the only
+    /// child of the root if it precedes the current state.
+    fn find_discardable(&self) -> Option<usize> {
+        let root_children = self.nodes.get(&self.root).map_or(0, |n| n.children.len());
+        self.nodes
+            .iter()
+            .filter(|(&id, _)| id != self.root && id != self.current)
+            .find(|(&id, node)| {
+                let is_leaf = node.children.is_empty() && !self.is_ancestor(id, self.current);
+                let is_first = node.parent == Some(self.root)
+                    && root_children == 1
+                    && self.is_ancestor(id, self.current);
+                is_leaf || is_first
+            })
+            .map(|(&id, _)| id)
+    }
+
+    /// Removes a state found by [`Self::find_discardable`].
+    fn discard(&mut self, id: usize) {
+        let Some(mut node) = self.nodes.remove(&id) else {
+            return;
+        };
+        self.size = self.size.saturating_sub(node.step.size());
+
+        if node.parent == Some(self.root) && self.is_ancestor(id, self.current) {
+            // The state becomes the new root, i.e. it can no longer be undone.
+            self.nodes.remove(&self.root);
+            node.parent = None;
+            node.step.edits.clear();
+            self.size += node.step.size();
+            self.root = id;
+            self.nodes.insert(id, node);
+            return;
+        }
+
+        if let Some(parent) = node.parent.and_then(|id| self.nodes.get_mut(&id)) {
+            parent.children.retain(|&child| child != id);
+            if parent.redo_child == Some(id) {
+                parent.redo_child = parent.children.last().copied();
+            }
+        }
     }
 
-    fn remove(&mut self, index: usize) {
-        self.inner.remove(index);
+    /// Returns the approximate number of bytes occupied by the history.
+    pub(crate) fn size(&self) -> usize {
+        self.size
     }
 }
 
+/// A branch of the undo history, see [`EditorState::undo_branches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoBranch {
+    /// The id of the newest text state of the branch.
+    pub id: usize,
+    /// The time of the last change on the branch.
+    pub time: SystemTime,
+    /// The number of changes from the oldest restorable state to the
+    /// newest state of the branch.
+    pub changes: usize,
+    /// Whether the current text state lies on the branch.
+    pub is_current: bool,
+}
+
+/// A group of edits that is undone and redone as a single unit.
 #[derive(Debug, Clone)]
-pub(crate) struct UndoState {
-    lines: Lines,
-    cursor: Index2,
+struct Step {
+    edits: Vec<Edit>,
+    cursor_before: Index2,
+    cursor_after: Index2,
+    selection_before: Option<Selection>,
+    selection_after: Option<Selection>,
+}
+
+impl Step {
+    fn new(cursor: Index2, selection: Option<Selection>) -> Self {
+        Self {
+            edits: Vec::new(),
+            cursor_before: cursor,
+            cursor_after: cursor,
+            selection_before: selection.clone(),
+            selection_after: selection,
+        }
+    }
+
+    /// Pushes an edit to the step. Merges it into the previous edit if
+    /// it modifies exactly the rows that the previous edit inserted, which
+    /// keeps consecutive edits on the same line cheap.
+    fn push(&mut self, edit: Edit) {
+        if let Some(last) = self.edits.last_mut() {
+            if last.row == edit.row && last.inserted.len() == edit.removed.len() {
+                last.inserted = edit.inserted;
+                return;
+            }
+        }
+        self.edits.push(edit);
+    }
+
+    fn size(&self) -> usize {
+        size_of::<Self>() + self.edits.iter().map(Edit::size).sum::<usize>()
+    }
+}
+
+/// A recorded change of the buffer. The rows `removed` starting at `row`
+/// were replaced by the rows `inserted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Edit {
+    pub(crate) row: usize,
+    pub(crate) removed: Vec<Vec<char>>,
+    pub(crate) inserted: Vec<Vec<char>>,
+}
+
+impl Edit {
+    /// Applies the edit to `lines`.
+    fn apply(&self, lines: &mut Lines) {
+        replace_rows(lines, self.row, self.removed.len(), &self.inserted);
+    }
+
+    /// Applies the inverse of the edit to `lines`.
+    fn revert(&self, lines: &mut Lines) {
+        replace_rows(lines, self.row, self.inserted.len(), &self.removed);
+    }
+
+    /// Removes the unchanged rows at the start and the end of the edit.
+    fn trim(mut self) -> Self {
+        let leading = self
+            .removed
+            .iter()
+            .zip(&self.inserted)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.removed.drain(..leading);
+        self.inserted.drain(..leading);
+        self.row += leading;
+
+        let trailing = self
+            .removed
+            .iter()
+            .rev()
+            .zip(self.inserted.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.removed.truncate(self.removed.len() - trailing);
+        self.inserted.truncate(self.inserted.len() - trailing);
+
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.inserted.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        let rows = self.removed.iter().chain(&self.inserted);
+        size_of::<Self>()
+            + rows
+                .map(|row| size_of::<Vec<char>>() + row.len() * size_of::<char>())
+                .sum::<usize>()
+    }
+}
+
+/// Replaces `len` rows starting at `row` with `rows`.
+fn replace_rows(lines: &mut Lines, row: usize, len: usize, rows: &[Vec<char>]) {
+    let row = row.min(lines.len());
+    let end = (row + len).min(lines.len());
+    let _ = lines.extract_rows(row..end);
+    for (i, data) in rows.iter().enumerate() {
+        lines.insert(RowIndex::new(row + i), data.clone());
+    }
+}
+
+/// Ends a transaction when dropped, also when the transaction panics.
+struct TransactionGuard<'a>(&'a mut EditorState);
+
+impl Drop for TransactionGuard<'_> {
+    fn drop(&mut self) {
+        let undo = &mut self.0.undo;
+        undo.transaction_depth = undo.transaction_depth.saturating_sub(1);
+        if undo.transaction_depth == 0 && !undo.insert_session {
+            undo.close();
+        }
+    }
 }
 
 impl EditorState {
+    /// Starts a new undo step. All edits until the next call are undone
+    /// and redone together. Does nothing in an insert session or in a
+    /// transaction.
     pub(crate) fn capture(&mut self) {
-        let editor_state = UndoState {
-            lines: self.lines.clone(),
-            cursor: self.cursor,
-        };
-        self.undo.push(editor_state);
+        if !self.undo.is_grouping() {
+            self.undo.start_step(self.cursor, self.selection.clone());
+        }
     }
 
-    pub fn undo(&mut self) {
-        if let Some(prev) = self.undo.pop() {
-            let current = UndoState {
-                lines: self.lines.clone(),
-                cursor: self.cursor,
-            };
-            self.lines = prev.lines;
-            self.cursor = prev.cursor;
-            self.redo.push(current);
+    /// Starts an insert session. Edits are grouped into the current step
+    /// until the session ends.
+    pub(crate) fn begin_insert_session(&mut self) {
+        self.undo.insert_session = true;
+        self.undo.last_cursor = None;
+    }
+
+    /// Ends the insert session.
+    pub(crate) fn end_insert_session(&mut self) {
+        self.undo.insert_session = false;
+    }
+
+    /// Runs `f` as a single transaction. All edits made by `f` are
+    /// undone and redone as one step.
+    ///
+    /// Note that only edits made through actions are recorded, direct
+    /// changes to `lines` are not.
+    ///
+    /// The step is closed when the outermost transaction ends, also if `f`
+    /// panics, unless an insert session is open. The edits of a transaction
+    /// in an insert session are part of the session's step.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use edtui::actions::{Execute, InsertChar, LineBreak};
+    /// use edtui::{EditorState, Lines};
+    ///
+    /// let mut state = EditorState::new(Lines::from("world"));
+    /// state.transaction(|state| {
+    ///     for ch in "hello".chars() {
+    ///         InsertChar(ch).execute(state);
+    ///     }
+    ///     LineBreak(1).execute(state);
+    /// });
+    /// assert_eq!(state.lines, Lines::from("hello\nworld"));
+    ///
+    /// state.undo();
+    /// assert_eq!(state.lines, Lines::from("world"));
+    /// ```
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut EditorState) -> R) -> R {
+        self.capture();
+        self.undo.transaction_depth += 1;
+        let guard = TransactionGuard(self);
+        f(guard.0)
+    }
+
+    /// Runs `f` and records the change of the buffer in the undo history.
+    /// If no undo step was started yet, the cursor and selection before `f`
+    /// are restored when undoing.
+    ///
+    /// `rows` must cover every existing row that `f` modifies or removes,
+    /// and the position where `f` inserts new rows.
+    pub(crate) fn modify_rows<R>(
+        &mut self,
+        rows: Range<usize>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let len = self.lines.len();
+        let start = rows.start.min(len);
+        let end = rows.end.clamp(start, len);
+        let removed = copy_rows(&self.lines, start..end);
+        let (cursor, selection) = (self.cursor, self.selection.clone());
+
+        let result = f(self);
+
+        let new_len = self.lines.len();
+        debug_assert!(end + new_len >= len, "modified rows outside of {start}..{end}");
+        let new_end = (end + new_len).saturating_sub(len).max(start);
+        let inserted = copy_rows(&self.lines, start..new_end);
+        shift_positions(
+            &mut self.cursors,
+            &mut self.snippets,
+            &mut self.diagnostics,
+            &mut self.inlay_hints,
+            &mut self.folds,
+            start,
+            &removed,
+            &inserted,
+        );
+        self.view
+            .highlight_cache
+            .edit(start, removed.len(), inserted.len());
+
+        let edit = Edit {
+            row: start,
+            removed,
+            inserted,
+        }
+        .trim();
+        if !edit.is_empty() {
+            self.undo.record(edit, cursor, self.cursor, selection);
         }
+
+        result
     }
 
+    /// Sets the maximum amount of memory in bytes that the undo history may
+    /// occupy. If the history grows larger, the oldest changes are discarded.
+    /// The most recent change is always kept.
+    pub fn set_undo_memory_limit(&mut self, bytes: usize) {
+        self.undo.set_limit(bytes);
+    }
+
+    /// Sets whether moving the cursor in insert mode, e.g. with the arrow
+    /// keys, starts a new undo step. By default, all edits made between
+    /// entering and leaving insert mode are undone together.
+    pub fn set_undo_break_on_cursor_move(&mut self, value: bool) {
+        self.undo.break_on_cursor_move = value;
+    }
+
+    /// Undoes the last change.
+    pub fn undo(&mut self) {
+        self.undo.close();
+        self.revert_current();
+    }
+
+    /// Redoes the last undone change. If there are several branches,
+    /// the most recently visited one is restored.
     pub fn redo(&mut self) {
-        if let Some(prev) = self.redo.pop() {
-            let current = UndoState {
-                lines: self.lines.clone(),
-                cursor: self.cursor,
-            };
-            self.lines = prev.lines;
-            self.cursor = prev.cursor;
-            self.undo.push(current);
+        self.undo.close();
+        let Some(node) = self.undo.nodes.get(&self.undo.current) else {
+            return;
+        };
+        if let Some(child) = node.redo_child.or(node.children.last().copied()) {
+            self.apply_child(child);
         }
     }
+
+    /// Goes to the previous text state in chronological order, regardless
+    /// of the branch. Equivalent to vim's `g-`.
+    pub fn undo_older(&mut self) {
+        let id = self.undo.nodes.range(..self.undo.current).next_back();
+        if let Some((&id, _)) = id {
+            self.goto_undo_state(id);
+        }
+    }
+
+    /// Goes to the next text state in chronological order, regardless
+    /// of the branch. Equivalent to vim's `g+`.
+    pub fn undo_newer(&mut self) {
+        let id = self.undo.nodes.range(self.undo.current + 1..).next();
+        if let Some((&id, _)) = id {
+            self.goto_undo_state(id);
+        }
+    }
+
+    /// Goes to the text state as it was `duration` before the current
+    /// state. Equivalent to vim's `:earlier {N}s`.
+    pub fn earlier(&mut self, duration: Duration) {
+        let Some(time) = self.undo_state_time().and_then(|t| t.checked_sub(duration)) else {
+            return;
+        };
+        let id = self
+            .undo
+            .nodes
+            .range(..self.undo.current)
+            .rev()
+            .find(|(_, node)| node.time <= time)
+            .map_or(self.undo.root, |(&id, _)| id);
+        self.goto_undo_state(id);
+    }
+
+    /// Goes to the text state as it was `duration` after the current
+    /// state. Equivalent to vim's `:later {N}s`.
+    pub fn later(&mut self, duration: Duration) {
+        let Some(time) = self.undo_state_time().and_then(|t| t.checked_add(duration)) else {
+            return;
+        };
+        let id = self
+            .undo
+            .nodes
+            .range(self.undo.current + 1..)
+            .take_while(|(_, node)| node.time <= time)
+            .last()
+            .map(|(&id, _)| id);
+        if let Some(id) = id {
+            self.goto_undo_state(id);
+        }
+    }
+
+    /// Returns the branches of the undo history in chronological order.
+    /// Each branch is identified by its newest text state, which can be
+    /// restored with [`Self::goto_undo_state`].
+    #[must_use]
+    pub fn undo_branches(&self) -> Vec<UndoBranch> {
+        self.undo
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.children.is_empty())
+            .map(|(&id, node)| UndoBranch {
+                id,
+                time: node.time,
+                changes: self.undo.path_to(id).len() - 1,
+                is_current: self.undo.is_ancestor(self.undo.current, id),
+            })
+            .collect()
+    }
+
+    /// Returns the id of the current text state in the undo history.
+    #[must_use]
+    pub fn undo_state(&self) -> usize {
+        self.undo.current
+    }
+
+    /// Restores the text state with the given id. The ids are returned by
+    /// [`Self::undo_branches`] and [`Self::undo_state`]. Does nothing if
+    /// the state does not exist.
+    pub fn goto_undo_state(&mut self, id: usize) {
+        if !self.undo.nodes.contains_key(&id) {
+            return;
+        }
+        self.undo.close();
+
+        let target = self.undo.path_to(id);
+        while !target.contains(&self.undo.current) {
+            self.revert_current();
+        }
+        let start = target.iter().position(|&id| id == self.undo.current);
+        for &child in target.iter().skip(start.map_or(0, |i| i + 1)) {
+            self.apply_child(child);
+        }
+    }
+
+    fn undo_state_time(&self) -> Option<SystemTime> {
+        self.undo.nodes.get(&self.undo.current).map(|node| node.time)
+    }
+
+    /// Reverts the step that leads to the current state and makes the
+    /// parent state current.
+    fn revert_current(&mut self) {
+        let current = self.undo.current;
+        let Some(node) = self.undo.nodes.get_mut(&current) else {
+            return;
+        };
+        let Some(parent) = node.parent else {
+            return;
+        };
+
+        let step = &mut node.step;
+        step.cursor_after = self.cursor;
+        step.selection_after.clone_from(&self.selection);
+        for edit in step.edits.iter().rev() {
+            edit.revert(&mut self.lines);
+            shift_positions(
+                &mut self.cursors,
+                &mut self.snippets,
+                &mut self.diagnostics,
+                &mut self.inlay_hints,
+                &mut self.folds,
+                edit.row,
+                &edit.inserted,
+                &edit.removed,
+            );
+            self.view
+                .highlight_cache
+                .edit(edit.row, edit.inserted.len(), edit.removed.len());
+        }
+        self.cursor = step.cursor_before;
+        self.selection.clone_from(&step.selection_before);
+
+        if let Some(parent) = self.undo.nodes.get_mut(&parent) {
+            parent.redo_child = Some(current);
+        }
+        self.undo.current = parent;
+    }
+
+    /// Applies the step that leads to the child state and makes the child
+    /// state current.
+    fn apply_child(&mut self, child: usize) {
+        let Some(node) = self.undo.nodes.get(&child) else {
+            return;
+        };
+        let step = &node.step;
+        for edit in &step.edits {
+            edit.apply(&mut self.lines);
+            shift_positions(
+                &mut self.cursors,
+                &mut self.snippets,
+                &mut self.diagnostics,
+                &mut self.inlay_hints,
+                &mut self.folds,
+                edit.row,
+                &edit.removed,
+                &edit.inserted,
+            );
+            self.view
+                .highlight_cache
+                .edit(edit.row, edit.removed.len(), edit.inserted.len());
+        }
+        self.cursor = step.cursor_after;
+        self.selection.clone_from(&step.selection_after);
+
+        if let Some(parent) = self.undo.nodes.get_mut(&self.undo.current) {
+            parent.redo_child = Some(child);
+        }
+        self.undo.current = child;
+    }
+}
+
+/// Shifts the secondary cursors, the snippet tabstops, the diagnostics, the
+/// inlay hints and the folds after the rows `old` starting at `row` were
+/// replaced by the rows `new`.
+#[allow(clippy::too_many_arguments)]
+fn shift_positions(
+    cursors: &mut [SecondaryCursor],
+    snippets: &mut SnippetState,
+    diagnostics: &mut [Diagnostic],
+    inlay_hints: &mut [InlayHint],
+    folds: &mut FoldState,
+    row: usize,
+    old: &[Vec<char>],
+    new: &[Vec<char>],
+) {
+    folds.shift(row, old.len(), new.len());
+    if cursors.is_empty()
+        && snippets.session.is_none()
+        && diagnostics.is_empty()
+        && inlay_hints.is_empty()
+    {
+        return;
+    }
+    let shift = RowShift::new(row, old, new);
+    shift_cursors(cursors, &shift);
+    snippets.shift(&shift);
+    for diagnostic in diagnostics {
+        diagnostic.shift(&shift);
+    }
+    shift_inlay_hints(inlay_hints, &shift);
+}
+
+fn copy_rows(lines: &Lines, rows: Range<usize>) -> Vec<Vec<char>> {
+    rows.filter_map(|row| lines.get(RowIndex::new(row)).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{
+        DeleteChar, DeleteLine, Execute, InsertChar, LineBreak, MoveForward, RemoveChar,
+        SwitchMode,
+    };
+    use crate::EditorMode;
+
+    fn test_state() -> EditorState {
+        EditorState::new(Lines::from("Hello World!\n\n123."))
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut state = test_state();
+        state.cursor = Index2::new(2, 1);
+
+        DeleteLine(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("Hello World!\n"));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+        assert_eq!(state.cursor, Index2::new(2, 1));
+
+        state.redo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n"));
+        assert_eq!(state.cursor, Index2::new(1, 0));
+    }
+
+    #[test]
+    fn test_undo_insert_session() {
+        let mut state = test_state();
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('a').execute(&mut state);
+        LineBreak(1).execute(&mut state);
+        InsertChar('b').execute(&mut state);
+        SwitchMode(EditorMode::Normal).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("a\nbHello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+        assert_eq!(state.cursor, Index2::new(0, 0));
+
+        state.redo();
+        assert_eq!(state.lines, Lines::from("a\nbHello World!\n\n123."));
+        assert_eq!(state.cursor, Index2::new(1, 1));
+    }
+
+    #[test]
+    fn test_undo_insert_session_with_backspace() {
+        let mut state = test_state();
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('a').execute(&mut state);
+        InsertChar('b').execute(&mut state);
+        DeleteChar(1).execute(&mut state);
+        InsertChar('c').execute(&mut state);
+        SwitchMode(EditorMode::Normal).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_undo_break_on_cursor_move() {
+        let mut state = test_state();
+        state.set_undo_break_on_cursor_move(true);
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('a').execute(&mut state);
+        MoveForward(1).execute(&mut state);
+        InsertChar('b').execute(&mut state);
+        SwitchMode(EditorMode::Normal).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("aHbello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("aHello World!\n\n123."));
+        assert_eq!(state.cursor, Index2::new(0, 2));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut state = test_state();
+
+        state.transaction(|state| {
+            RemoveChar(1).execute(state);
+            RemoveChar(1).execute(state);
+            DeleteLine(1).execute(state);
+        });
+        assert_eq!(state.lines, Lines::from("\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+
+        state.redo();
+        assert_eq!(state.lines, Lines::from("\n123."));
+    }
+
+    #[test]
+    fn test_transaction_is_closed_on_panic() {
+        let mut state = test_state();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            state.transaction(|state| {
+                RemoveChar(1).execute(state);
+                panic!("transaction failed");
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(state.undo.transaction_depth, 0);
+
+        // Later edits are not merged into the transaction's step.
+        RemoveChar(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::from("llo World!\n\n123."));
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_transaction_closes_step() {
+        let mut state = test_state();
+
+        state.transaction(|state| RemoveChar(1).execute(state));
+        state.begin_insert_session();
+        InsertChar('a').execute(&mut state);
+        state.end_insert_session();
+        assert_eq!(state.lines, Lines::from("aello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_edit_after_undo_starts_new_step() {
+        let mut state = test_state();
+
+        RemoveChar(1).execute(&mut state);
+        RemoveChar(1).execute(&mut state);
+        state.undo();
+        InsertChar('a').execute(&mut state);
+        assert_eq!(state.lines, Lines::from("aello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("ello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_undo_empty_buffer() {
+        let mut state = EditorState::new(Lines::from("a"));
+
+        DeleteLine(1).execute(&mut state);
+        assert_eq!(state.lines, Lines::default());
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("a"));
+    }
+
+    #[test]
+    fn test_consecutive_edits_are_merged() {
+        let mut state = test_state();
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        InsertChar('a').execute(&mut state);
+        let size = state.undo.size();
+        InsertChar('b').execute(&mut state);
+        InsertChar('c').execute(&mut state);
+
+        let row_growth = 2 * size_of::<char>();
+        assert_eq!(state.undo.size(), size + row_growth);
+    }
+
+    #[test]
+    fn test_memory_limit_discards_oldest_steps() {
+        let mut state = test_state();
+        state.set_undo_memory_limit(0);
+
+        state.capture();
+        InsertChar('a').execute(&mut state);
+        state.capture();
+        InsertChar('b').execute(&mut state);
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("aHello World!\n\n123."));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("aHello World!\n\n123."));
+    }
+
+    /// Creates two branches: "ab..." (undone) and "c...".
+    fn branched_state() -> EditorState {
+        let mut state = test_state();
+        state.capture();
+        InsertChar('a').execute(&mut state);
+        state.capture();
+        InsertChar('b').execute(&mut state);
+        state.undo();
+        state.capture();
+        InsertChar('c').execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn test_undo_tree_keeps_branches() {
+        let mut state = branched_state();
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+
+        state.undo();
+        state.redo();
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+
+        let branches = state.undo_branches();
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].changes, 2);
+        assert!(!branches[0].is_current);
+        assert!(branches[1].is_current);
+
+        state.goto_undo_state(branches[0].id);
+        assert_eq!(state.lines, Lines::from("abHello World!\n\n123."));
+        assert!(state.undo_branches()[0].is_current);
+    }
+
+    #[test]
+    fn test_undo_older_newer() {
+        let mut state = branched_state();
+
+        state.undo_older();
+        assert_eq!(state.lines, Lines::from("abHello World!\n\n123."));
+        state.undo_older();
+        assert_eq!(state.lines, Lines::from("aHello World!\n\n123."));
+        state.undo_older();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+        state.undo_older();
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+
+        state.undo_newer();
+        state.undo_newer();
+        state.undo_newer();
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+        state.undo_newer();
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+    }
+
+    #[test]
+    fn test_earlier_later() {
+        let mut state = branched_state();
+        let start = SystemTime::UNIX_EPOCH;
+        for (id, node) in &mut state.undo.nodes {
+            node.time = start + Duration::from_secs(*id as u64 * 10);
+        }
+
+        state.earlier(Duration::from_secs(15));
+        assert_eq!(state.lines, Lines::from("aHello World!\n\n123."));
+
+        state.later(Duration::from_secs(10));
+        assert_eq!(state.lines, Lines::from("abHello World!\n\n123."));
+
+        state.earlier(Duration::from_secs(100));
+        assert_eq!(state.lines, Lines::from("Hello World!\n\n123."));
+
+        state.later(Duration::from_secs(100));
+        assert_eq!(state.lines, Lines::from("acHello World!\n\n123."));
+    }
 }
@@ -0,0 +1,414 @@
+//! Saving and restoring the undo history.
+//!
+//! The history is written into a compact binary format. Integers are
+//! encoded as LEB128 varints and rows as UTF-8 strings. The file starts
+//! with a header that holds a hash of the buffer content at the time the
+//! history was saved. A history is only restored if the hash matches the
+//! current content, because its edits are meaningless for any other text.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::{EditorState, Index2, Lines};
+
+use super::{Edit, Node, Stack, Step};
+use crate::state::selection::Selection;
+
+/// Identifies an undo history file.
+const MAGIC: &[u8; 4] = b"EDTU";
+
+/// The version of the file format.
+const VERSION: u8 = 1;
+
+/// An error that occurs when restoring the undo history with
+/// [`EditorState::load_undo_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoHistoryError {
+    /// The data is not an undo history or it is corrupted.
+    InvalidFormat,
+    /// The data was written by an incompatible version of edtui.
+    UnsupportedVersion(u8),
+    /// The history belongs to a different buffer content.
+    ContentMismatch,
+}
+
+impl fmt::Display for UndoHistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFormat => write!(f, "invalid undo history format"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported undo history version {version}")
+            }
+            Self::ContentMismatch => write!(f, "undo history does not match the buffer content"),
+        }
+    }
+}
+
+impl std::error::Error for UndoHistoryError {}
+
+impl EditorState {
+    /// Serializes the undo history, including all branches, into a compact
+    /// binary format. The data contains a hash of the current buffer, so
+    /// that it can be saved next to the document and restored with
+    /// [`Self::load_undo_history`] when the document is reopened.
+    #[must_use]
+    pub fn save_undo_history(&self) -> Vec<u8> {
+        let stack = &self.undo;
+        let mut writer = Writer::default();
+        writer.bytes(MAGIC);
+        writer.bytes(&[VERSION]);
+        writer.bytes(&content_hash(&self.lines).to_le_bytes());
+        writer.usize(stack.root);
+        writer.usize(stack.current);
+        writer.usize(stack.next_id);
+        writer.usize(stack.nodes.len());
+        for (&id, node) in &stack.nodes {
+            writer.usize(id);
+            writer.option(node.parent);
+            writer.option(node.redo_child);
+            writer.time(node.time);
+            writer.step(&node.step);
+        }
+        writer.0
+    }
+
+    /// Restores an undo history that was saved with
+    /// [`Self::save_undo_history`], replacing the current history.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a valid undo history or if it
+    /// was saved for a different buffer content. The current history is
+    /// left unchanged in this case.
+    pub fn load_undo_history(&mut self, data: &[u8]) -> Result<(), UndoHistoryError> {
+        let mut reader = Reader(data);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(UndoHistoryError::InvalidFormat);
+        }
+        let version = reader.bytes(1)?[0];
+        if version != VERSION {
+            return Err(UndoHistoryError::UnsupportedVersion(version));
+        }
+        let hash = reader.bytes(8)?;
+        if hash != content_hash(&self.lines).to_le_bytes() {
+            return Err(UndoHistoryError::ContentMismatch);
+        }
+
+        let root = reader.usize()?;
+        let current = reader.usize()?;
+        let next_id = reader.usize()?;
+        let mut nodes = BTreeMap::new();
+        for _ in 0..reader.usize()? {
+            let id = reader.usize()?;
+            let node = Node {
+                parent: reader.option()?,
+                children: Vec::new(),
+                redo_child: reader.option()?,
+                time: reader.time()?,
+                step: reader.step()?,
+            };
+            nodes.insert(id, node);
+        }
+        if !reader.0.is_empty() {
+            return Err(UndoHistoryError::InvalidFormat);
+        }
+
+        let ids: Vec<usize> = nodes.keys().copied().collect();
+        for id in ids {
+            let parent = nodes[&id].parent;
+            if id >= next_id || parent.is_some_and(|parent| parent >= id) {
+                return Err(UndoHistoryError::InvalidFormat);
+            }
+            match parent {
+                Some(parent) => match nodes.get_mut(&parent) {
+                    Some(parent) => parent.children.push(id),
+                    None => return Err(UndoHistoryError::InvalidFormat),
+                },
+                None if id != root => return Err(UndoHistoryError::InvalidFormat),
+                None => {}
+            }
+        }
+        if !nodes.contains_key(&root) || !nodes.contains_key(&current) {
+            return Err(UndoHistoryError::InvalidFormat);
+        }
+        // The content hash does not cover the tree, a redo must not lead
+        // to a state that is not a child.
+        let redo_is_child = |node: &Node| {
+            node.redo_child
+                .is_none_or(|child| node.children.contains(&child))
+        };
+        if !nodes.values().all(redo_is_child) {
+            return Err(UndoHistoryError::InvalidFormat);
+        }
+
+        self.undo = Stack {
+            size: nodes.values().map(|node| node.step.size()).sum(),
+            nodes,
+            root,
+            current,
+            next_id,
+            limit: self.undo.limit,
+            break_on_cursor_move: self.undo.break_on_cursor_move,
+            ..Stack::new()
+        };
+        self.undo.enforce_limit();
+        Ok(())
+    }
+}
+
+/// Computes a 64-bit FNV-1a hash of the buffer content. Unlike the hashers
+/// of the standard library, the result is stable across Rust versions.
+fn content_hash(lines: &Lines) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    let mut write = |byte: u8| {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(PRIME);
+    };
+    let mut buf = [0; 4];
+    for row in lines.iter_row() {
+        for ch in row {
+            ch.encode_utf8(&mut buf).bytes().for_each(&mut write);
+        }
+        // 0xFF never occurs in UTF-8 and terminates a row.
+        write(0xFF);
+    }
+    hash
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u64(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    /// Writes `None` as 0 and `Some(value)` as `value + 1`.
+    fn option(&mut self, value: Option<usize>) {
+        self.usize(value.map_or(0, |value| value + 1));
+    }
+
+    fn time(&mut self, time: SystemTime) {
+        let since_epoch = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+        self.u64(since_epoch.as_secs());
+        self.u64(u64::from(since_epoch.subsec_nanos()));
+    }
+
+    fn index(&mut self, index: Index2) {
+        self.usize(index.row);
+        self.usize(index.col);
+    }
+
+    fn selection(&mut self, selection: Option<&Selection>) {
+        match selection {
+            Some(selection) => {
+                self.bytes(&[1]);
+                self.index(selection.start);
+                self.index(selection.end);
+            }
+            None => self.bytes(&[0]),
+        }
+    }
+
+    fn rows(&mut self, rows: &[Vec<char>]) {
+        self.usize(rows.len());
+        for row in rows {
+            let row: String = row.iter().collect();
+            self.usize(row.len());
+            self.bytes(row.as_bytes());
+        }
+    }
+
+    fn step(&mut self, step: &Step) {
+        self.index(step.cursor_before);
+        self.index(step.cursor_after);
+        self.selection(step.selection_before.as_ref());
+        self.selection(step.selection_after.as_ref());
+        self.usize(step.edits.len());
+        for edit in &step.edits {
+            self.usize(edit.row);
+            self.rows(&edit.removed);
+            self.rows(&edit.inserted);
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], UndoHistoryError> {
+        if self.0.len() < len {
+            return Err(UndoHistoryError::InvalidFormat);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, UndoHistoryError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(UndoHistoryError::InvalidFormat)
+    }
+
+    fn usize(&mut self) -> Result<usize, UndoHistoryError> {
+        usize::try_from(self.u64()?).map_err(|_| UndoHistoryError::InvalidFormat)
+    }
+
+    fn option(&mut self) -> Result<Option<usize>, UndoHistoryError> {
+        Ok(self.usize()?.checked_sub(1))
+    }
+
+    fn time(&mut self) -> Result<SystemTime, UndoHistoryError> {
+        let secs = self.u64()?;
+        let nanos = u32::try_from(self.u64()?).map_err(|_| UndoHistoryError::InvalidFormat)?;
+        SystemTime::UNIX_EPOCH
+            .checked_add(Duration::new(secs, nanos))
+            .ok_or(UndoHistoryError::InvalidFormat)
+    }
+
+    fn index(&mut self) -> Result<Index2, UndoHistoryError> {
+        Ok(Index2::new(self.usize()?, self.usize()?))
+    }
+
+    fn selection(&mut self) -> Result<Option<Selection>, UndoHistoryError> {
+        match self.bytes(1)?[0] {
+            0 => Ok(None),
+            1 => Ok(Some(Selection::new(self.index()?, self.index()?))),
+            _ => Err(UndoHistoryError::InvalidFormat),
+        }
+    }
+
+    fn rows(&mut self) -> Result<Vec<Vec<char>>, UndoHistoryError> {
+        let len = self.usize()?;
+        let mut rows = Vec::with_capacity(len.min(self.0.len()));
+        for _ in 0..len {
+            let row_len = self.usize()?;
+            let row = std::str::from_utf8(self.bytes(row_len)?)
+                .map_err(|_| UndoHistoryError::InvalidFormat)?;
+            rows.push(row.chars().collect());
+        }
+        Ok(rows)
+    }
+
+    fn step(&mut self) -> Result<Step, UndoHistoryError> {
+        let cursor_before = self.index()?;
+        let cursor_after = self.index()?;
+        let selection_before = self.selection()?;
+        let selection_after = self.selection()?;
+        let len = self.usize()?;
+        let mut edits = Vec::with_capacity(len.min(self.0.len()));
+        for _ in 0..len {
+            edits.push(Edit {
+                row: self.usize()?,
+                removed: self.rows()?,
+                inserted: self.rows()?,
+            });
+        }
+        Ok(Step {
+            edits,
+            cursor_before,
+            cursor_after,
+            selection_before,
+            selection_after,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Execute, InsertChar};
+
+    fn edited_state() -> EditorState {
+        let mut state = EditorState::new(Lines::from("Hello\nWorld"));
+        state.capture();
+        InsertChar('a').execute(&mut state);
+        state.capture();
+        InsertChar('ö').execute(&mut state);
+        state.undo();
+        state.capture();
+        InsertChar('c').execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn test_save_and_load_undo_history() {
+        let state = edited_state();
+        let data = state.save_undo_history();
+
+        let mut restored = EditorState::new(state.lines.clone());
+        assert_eq!(restored.load_undo_history(&data), Ok(()));
+        assert_eq!(restored.undo_branches(), state.undo_branches());
+
+        restored.undo();
+        assert_eq!(restored.lines, Lines::from("aHello\nWorld"));
+        restored.undo_newer();
+        assert_eq!(restored.lines, Lines::from("aöHello\nWorld"));
+        restored.undo_older();
+        restored.undo_older();
+        assert_eq!(restored.lines, Lines::from("Hello\nWorld"));
+    }
+
+    #[test]
+    fn test_load_undo_history_content_mismatch() {
+        let data = edited_state().save_undo_history();
+
+        let mut state = EditorState::new(Lines::from("acHello\nWorld!"));
+        let err = state.load_undo_history(&data);
+        assert_eq!(err, Err(UndoHistoryError::ContentMismatch));
+
+        state.undo();
+        assert_eq!(state.lines, Lines::from("acHello\nWorld!"));
+    }
+
+    #[test]
+    fn test_load_undo_history_invalid() {
+        let data = edited_state().save_undo_history();
+        let mut state = edited_state();
+
+        let err = state.load_undo_history(&data[..data.len() - 1]);
+        assert_eq!(err, Err(UndoHistoryError::InvalidFormat));
+
+        let err = state.load_undo_history(b"none");
+        assert_eq!(err, Err(UndoHistoryError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_load_undo_history_redo_child_not_a_child() {
+        let mut state = edited_state();
+        // The root redoes into a grandchild instead of a child.
+        let (root, grandchild) = (state.undo.root, *state.undo.nodes.keys().last().unwrap());
+        state.undo.nodes.get_mut(&root).unwrap().redo_child = Some(grandchild);
+        let data = state.save_undo_history();
+
+        let mut restored = EditorState::new(state.lines.clone());
+        let err = restored.load_undo_history(&data);
+        assert_eq!(err, Err(UndoHistoryError::InvalidFormat));
+    }
+}
@@ -1,9 +1,23 @@
+use super::fold::FoldState;
+use super::inlay_hint::{display_line, visible_inlay_hints, InlayHint};
 use crate::{
-    helper::{char_width, chars_width},
-    view::line_wrapper::LineWrapper,
+    helper::{grapheme_start, grapheme_widths},
+    view::{
+        bracket::MatchPairs,
+        cursor::CursorShapes,
+        gutter::LineNumbers,
+        highlighter::HighlightCache,
+        line_wrapper::{LineWrapper, WrapOptions},
+        list_chars::ListChars,
+        minimap::MinimapArea,
+    },
     Lines,
 };
-use ratatui::layout::Rect;
+use jagged::index::RowIndex;
+use ratatui::{
+    buffer::Cell,
+    layout::{Position, Rect},
+};
 
 /// Represents the (x, y) offset of the editor's viewport.
 /// It represents the top-left local editor coordinate.
@@ -22,6 +36,48 @@ pub(crate) struct ViewState {
     pub(crate) wrap: bool,
     /// The number of spaces used to display a tab.
     pub(crate) tab_width: usize,
+    /// How lines are wrapped.
+    pub(crate) wrap_options: WrapOptions,
+    /// The line numbers displayed in the gutter.
+    pub(crate) line_numbers: LineNumbers,
+    /// Whether the messages of diagnostics are displayed behind the lines.
+    pub(crate) diagnostic_virtual_text: bool,
+    /// Whether the cells of diagnostics are kept to be undercurled.
+    pub(crate) diagnostic_undercurl: bool,
+    /// The underlined cells of diagnostics of the last render and their
+    /// content, if undercurls are enabled.
+    pub(crate) undercurls: Vec<(Position, Cell)>,
+    /// Whether the background of the cursor line is highlighted.
+    pub(crate) cursor_line: bool,
+    /// The highlighted screen columns, starting at one.
+    pub(crate) color_columns: Vec<usize>,
+    /// The glyphs that make whitespace visible.
+    pub(crate) list_chars: ListChars,
+    /// The minimum number of lines kept visible above and below the cursor.
+    pub(crate) scroll_off: usize,
+    /// The minimum number of columns kept visible left and right of the
+    /// cursor if lines are not wrapped.
+    pub(crate) side_scroll_off: usize,
+    /// The pairs that are highlighted at the cursor.
+    pub(crate) match_pairs: MatchPairs,
+    /// Whether brackets are coloured by their nesting depth.
+    pub(crate) rainbow_brackets: bool,
+    /// Whether the cursor is displayed by the terminal.
+    pub(crate) terminal_cursor: bool,
+    /// The cursor shapes of the editor modes.
+    pub(crate) cursor_shapes: CursorShapes,
+    /// The screen position of the cursor, if it was rendered.
+    pub(crate) cursor_position: Option<Position>,
+    /// Whether a scrollbar is displayed right of the text.
+    pub(crate) scrollbar: bool,
+    /// The area of the scrollbar on the screen. Empty if there is none.
+    pub(crate) scrollbar_area: Rect,
+    /// The minimap that was rendered last.
+    pub(crate) minimap: MinimapArea,
+    /// Whether the mouse is dragging the scrollbar or the minimap.
+    pub(crate) scroll_drag: Option<ScrollDrag>,
+    /// The state of the syntax highlighter between renders.
+    pub(crate) highlight_cache: HighlightCache,
 }
 
 impl Default for ViewState {
@@ -32,10 +88,40 @@ impl Default for ViewState {
             screen_area: Rect::default(),
             wrap: true,
             tab_width: 2,
+            wrap_options: WrapOptions::default(),
+            line_numbers: LineNumbers::default(),
+            diagnostic_virtual_text: false,
+            diagnostic_undercurl: false,
+            undercurls: Vec::new(),
+            cursor_line: false,
+            color_columns: Vec::new(),
+            list_chars: ListChars::default(),
+            scroll_off: 0,
+            side_scroll_off: 0,
+            match_pairs: MatchPairs {
+                brackets: true,
+                ..MatchPairs::default()
+            },
+            rainbow_brackets: false,
+            terminal_cursor: false,
+            cursor_shapes: CursorShapes::default(),
+            cursor_position: None,
+            scrollbar: false,
+            scrollbar_area: Rect::default(),
+            minimap: MinimapArea::default(),
+            scroll_drag: None,
+            highlight_cache: HighlightCache::default(),
         }
     }
 }
 
+/// The widget that scrolls the editor while the mouse drags it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ScrollDrag {
+    Scrollbar,
+    Minimap,
+}
+
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
 pub(crate) struct Offset {
     /// The x-offset.
@@ -68,7 +154,8 @@ impl ViewState {
         self.screen_area = area.into();
     }
 
-    /// Updates the viewports horizontal offset.
+    /// Updates the viewports horizontal offset, keeping `side_scroll_off`
+    /// columns visible left and right of the cursor.
     pub(crate) fn update_viewport_horizontal(
         &mut self,
         width: usize,
@@ -80,17 +167,23 @@ impl ViewState {
             return self.viewport.x;
         };
 
+        // The margin is at most half of the width. The right margin does not
+        // reach beyond the end of the line.
+        let margin = self.side_scroll_off.min(width.saturating_sub(1) / 2);
+        let target_col = (cursor_col + margin).min(line.len().max(cursor_col));
+
         // scroll left
-        if cursor_col < self.viewport.x {
-            self.viewport.x = cursor_col;
+        if cursor_col.saturating_sub(margin) < self.viewport.x {
+            self.viewport.x = grapheme_start(line, cursor_col.saturating_sub(margin));
             return self.viewport.x;
         }
 
         // Iterate forward from the viewport.x position and calculate width
         let mut max_cursor_pos = self.viewport.x;
+        let widths = grapheme_widths(line, self.tab_width);
         let mut current_width = 0;
-        for &ch in line.iter().skip(self.viewport.x) {
-            current_width += char_width(ch, self.tab_width);
+        for &char_width in widths.iter().skip(self.viewport.x) {
+            current_width += char_width;
             if current_width >= width {
                 break;
             }
@@ -98,16 +191,13 @@ impl ViewState {
         }
 
         // scroll right
-        if cursor_col > max_cursor_pos {
+        if target_col > max_cursor_pos {
             let mut backward_width = 0;
-            let mut new_viewport_x = cursor_col;
+            let mut new_viewport_x = target_col;
 
             // Iterate backward from max_cursor_pos to find the first fitting character
-            for i in (0..=cursor_col).rev() {
-                let char_width = match line.get(i) {
-                    Some(&ch) => char_width(ch, self.tab_width),
-                    None => 1,
-                };
+            for i in (0..=target_col).rev() {
+                let char_width = widths.get(i).copied().unwrap_or(1);
                 backward_width += char_width;
                 if backward_width >= width {
                     break;
@@ -121,38 +211,73 @@ impl ViewState {
         self.viewport.x
     }
 
-    /// Updates the view ports vertical offset.
-    pub(crate) fn update_viewport_vertical(&mut self, height: usize, cursor_row: usize) -> usize {
-        let max_cursor_pos = height.saturating_sub(1) + self.viewport.y;
+    /// Updates the view ports vertical offset, keeping `scroll_off` rows
+    /// visible above and below the cursor. A closed fold counts as a
+    /// single row.
+    pub(crate) fn update_viewport_vertical(
+        &mut self,
+        height: usize,
+        cursor_row: usize,
+        num_lines: usize,
+        folds: &FoldState,
+    ) -> usize {
+        let cursor_row = folds.visible_row(cursor_row);
+        let mut viewport_y = folds.visible_row(self.viewport.y);
+        let max_cursor_pos = height.saturating_sub(1) + viewport_y;
+
+        // The margin is at most half of the height. The bottom margin does
+        // not reach beyond the last line.
+        let margin = self.scroll_off.min(height.saturating_sub(1) / 2);
+        let last_row = folds.visible_row(num_lines.saturating_sub(1));
+        let top_row = cursor_row.saturating_sub(margin);
+        let bottom_row = (cursor_row + margin).min(last_row.max(cursor_row));
 
         // scroll up
-        if cursor_row < self.viewport.y {
-            self.viewport.y = cursor_row;
+        if top_row < viewport_y {
+            viewport_y = top_row;
         }
 
         // scroll down
-        if cursor_row >= max_cursor_pos {
-            self.viewport.y += cursor_row.saturating_sub(max_cursor_pos);
+        if bottom_row >= max_cursor_pos {
+            viewport_y += bottom_row.saturating_sub(max_cursor_pos);
         }
 
+        self.viewport.y = folds.row_at_visible(viewport_y);
         self.viewport.y
     }
 
-    /// Updates the view ports vertical offset.
+    /// Updates the view ports vertical offset if lines are wrapped, keeping
+    /// `scroll_off` lines visible above and below the cursor line. A closed
+    /// fold counts as a single row.
     pub(crate) fn update_viewport_vertical_wrap(
         &mut self,
         width: usize,
         height: usize,
         cursor_row: usize,
         lines: &Lines,
+        inlay_hints: &[InlayHint],
+        folds: &FoldState,
     ) -> usize {
+        let cursor_row = folds.fold_start(cursor_row);
+        self.viewport.y = folds.fold_start(self.viewport.y);
+
+        // The margin is at most half of the height and is counted in lines.
+        let margin = self.scroll_off.min(height.saturating_sub(1) / 2);
+        let visible_row = folds.visible_row(cursor_row);
+        let last_row = folds.visible_row(lines.len().saturating_sub(1));
+        let top_row = folds.row_at_visible(visible_row.saturating_sub(margin));
+        let bottom_row =
+            folds.row_at_visible((visible_row + margin).min(last_row.max(visible_row)));
+
         // scroll up
-        if cursor_row < self.viewport.y {
-            self.viewport.y = cursor_row;
+        if top_row < self.viewport.y {
+            self.viewport.y = top_row;
         }
 
-        // scroll down
-        self.scroll_down(lines, width, height, cursor_row);
+        // scroll down. If the lines below the cursor do not fit, the cursor
+        // line stays visible at the top.
+        self.scroll_down(lines, inlay_hints, folds, width, height, bottom_row);
+        self.viewport.y = self.viewport.y.min(cursor_row);
 
         self.viewport.y
     }
@@ -177,6 +302,8 @@ impl ViewState {
     fn scroll_down(
         &mut self,
         lines: &Lines,
+        inlay_hints: &[InlayHint],
+        folds: &FoldState,
         max_width: usize,
         max_height: usize,
         cursor_row: usize,
@@ -188,20 +315,31 @@ impl ViewState {
 
         let mut remaining_height = max_height;
 
-        let skip = lines.len().saturating_sub(cursor_row + 1);
-        for (i, line) in lines.iter_row().rev().skip(skip).enumerate() {
-            let line_width = chars_width(line, self.tab_width);
-            let current_row_height = LineWrapper::determine_split(line_width, max_width).len();
+        // Iterate backward over the displayed rows, starting at the cursor.
+        let mut row = cursor_row.min(lines.len().saturating_sub(1));
+        let mut first_visible_row = row;
+        while let Some(line) = lines.get(RowIndex::new(row)) {
+            let current_row_height = if folds.closed_fold(row).is_some() {
+                1
+            } else {
+                let hints = visible_inlay_hints(inlay_hints, row, 0);
+                let line = display_line(line, hints);
+                LineWrapper::layout(&line, max_width, self.tab_width, &self.wrap_options).len()
+            };
 
             // If we run out of height or exceed it, scroll the viewport.
             if remaining_height < current_row_height {
-                let first_visible_row = cursor_row.saturating_sub(i.saturating_sub(1));
                 self.viewport.y = first_visible_row;
                 break;
             }
 
             // Subtract the number of wrapped rows from the remaining height.
             remaining_height = remaining_height.saturating_sub(current_row_height);
+            first_visible_row = row;
+            if row == 0 {
+                break;
+            }
+            row = folds.fold_start(row - 1);
         }
     }
 }
@@ -225,7 +363,8 @@ mod tests {
                 let cursor = $given_cursor;
 
                 // when
-                let offset = view.update_viewport_vertical(height, cursor);
+                let offset =
+                    view.update_viewport_vertical(height, cursor, 10, &FoldState::default());
 
                 // then
                 assert_eq!(offset, $expected_offset);
@@ -310,4 +449,96 @@ mod tests {
             expected: 1
         }
     );
+
+    update_view_vertical_test!(
+        // 0    |
+        // 1    | ----
+        // 2 <- | --<-
+        // 3    | ----
+        scroll_down_with_scroll_off: {
+            view: ViewState{
+                viewport: Offset::new(0, 0),
+                scroll_off: 1,
+                ..Default::default()
+            },
+            height:  3,
+            cursor: 2,
+            expected: 1
+        }
+    );
+
+    update_view_vertical_test!(
+        // 8    | ----
+        // 9 <- | --<-
+        scroll_off_stops_at_last_line: {
+            view: ViewState{
+                viewport: Offset::new(0, 0),
+                scroll_off: 2,
+                ..Default::default()
+            },
+            height:  5,
+            cursor: 9,
+            expected: 5
+        }
+    );
+
+    update_view_vertical_test!(
+        scroll_up_with_scroll_off: {
+            view: ViewState{
+                viewport: Offset::new(0, 3),
+                scroll_off: 2,
+                ..Default::default()
+            },
+            height:  5,
+            cursor: 4,
+            expected: 2
+        }
+    );
+
+    #[test]
+    fn scroll_horizontal_with_side_scroll_off() {
+        let line: Vec<char> = "abcdefghij".chars().collect();
+        let mut view = ViewState {
+            side_scroll_off: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(view.update_viewport_horizontal(5, 4, Some(&line)), 1);
+        assert_eq!(view.update_viewport_horizontal(5, 9, Some(&line)), 6);
+        assert_eq!(view.update_viewport_horizontal(5, 6, Some(&line)), 5);
+    }
+
+    #[test]
+    fn scroll_wrapped_with_scroll_off() {
+        let lines = Lines::from("0\n1\n2\n3\n4\n5\n6\n7\n8\n9");
+        let folds = FoldState::default();
+        let mut view = ViewState {
+            scroll_off: 1,
+            num_rows: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            view.update_viewport_vertical_wrap(10, 4, 3, &lines, &[], &folds),
+            1
+        );
+        assert_eq!(
+            view.update_viewport_vertical_wrap(10, 4, 1, &lines, &[], &folds),
+            0
+        );
+    }
+
+    #[test]
+    fn scroll_down_over_closed_fold() {
+        // 0    | 0
+        // 1-4  | 1-4 (closed fold)
+        // 5 <- | 5 <-
+        let mut state = crate::EditorState::new(Lines::from("0\n1\n2\n3\n4\n5"));
+        state.add_fold(1..=4);
+        let folds = state.folds.clone();
+        let mut view = ViewState::default();
+
+        assert_eq!(view.update_viewport_vertical(3, 5, 6, &folds), 0);
+        assert_eq!(view.update_viewport_vertical(2, 5, 6, &folds), 1);
+    }
 }
@@ -1,26 +1,50 @@
+pub(crate) mod bracket;
+mod completion;
+pub(crate) mod cursor;
+mod diagnostic;
+pub(crate) mod gutter;
 mod internal;
 pub(crate) mod line_wrapper;
+pub(crate) mod list_chars;
+pub(crate) mod minimap;
 mod render_line;
+pub(crate) mod highlighter;
+pub(crate) mod scrollbar;
 pub mod status_line;
 #[cfg(feature = "syntax-highlighting")]
 pub(crate) mod syntax_higlighting;
 pub mod theme;
+#[cfg(feature = "tree-sitter")]
+pub(crate) mod tree_sitter_highlighting;
 
+use completion::CompletionPopup;
 use render_line::RenderLine;
+use scrollbar::Scrollbar;
 #[cfg(feature = "syntax-highlighting")]
 use syntax_higlighting::SyntaxHighlighter;
 
 use crate::{
-    helper::{max_col, rect_indent_y},
-    state::{selection::Selection, EditorState},
+    helper::{chars_width, max_col, rect_indent_y},
+    state::{
+        inlay_hint::{display_col, display_line, visible_inlay_hints},
+        selection::Selection,
+        EditorState,
+    },
     EditorMode, Index2,
 };
 
-use internal::into_spans_with_selections;
-#[cfg(feature = "syntax-highlighting")]
-use internal::line_into_highlighted_spans_with_selections;
+pub use cursor::{CursorShape, CursorShapes};
+pub use gutter::LineNumbers;
+use highlighter::LineHighlighter;
+use internal::{
+    insert_inlay_hints, into_spans_with_selections, line_into_highlighted_spans_with_selections,
+    InternalSpan,
+};
 use jagged::index::RowIndex;
 use line_wrapper::LineWrapper;
+pub use line_wrapper::WrapMode;
+pub use list_chars::ListChars;
+pub use minimap::{EditorMinimap, MinimapMarker};
 use ratatui::{prelude::*, widgets::Widget};
 pub use status_line::EditorStatusLine;
 use theme::EditorTheme;
@@ -50,8 +74,7 @@ pub struct EditorView<'a, 'b> {
     pub(crate) theme: EditorTheme<'b>,
 
     /// An optional syntax highlighter.
-    #[cfg(feature = "syntax-highlighting")]
-    pub(crate) syntax_highlighter: Option<SyntaxHighlighter>,
+    pub(crate) highlighter: Option<Box<dyn LineHighlighter>>,
 }
 
 impl<'a, 'b> EditorView<'a, 'b> {
@@ -61,8 +84,7 @@ impl<'a, 'b> EditorView<'a, 'b> {
         Self {
             state,
             theme: EditorTheme::default(),
-            #[cfg(feature = "syntax-highlighting")]
-            syntax_highlighter: None,
+            highlighter: None,
         }
     }
 
@@ -92,7 +114,23 @@ impl<'a, 'b> EditorView<'a, 'b> {
     /// ```
     #[must_use]
     pub fn syntax_highlighter(mut self, syntax_highlighter: Option<SyntaxHighlighter>) -> Self {
-        self.syntax_highlighter = syntax_highlighter;
+        self.state.syntax_comment_tokens = syntax_highlighter
+            .as_ref()
+            .map(SyntaxHighlighter::comment_tokens);
+        self.highlighter = syntax_highlighter
+            .map(|highlighter| Box::new(highlighter) as Box<dyn LineHighlighter>);
+        self
+    }
+
+    /// Set a custom highlighter for the [`EditorView`], such as a
+    /// `SyntaxHighlighter`, a `TreeSitterHighlighter` or a highlighter of
+    /// another backend. Comments are toggled with the comment tokens of
+    /// the highlighter, see [`LineHighlighter::comment_tokens`].
+    /// See [`LineHighlighter`] for more information.
+    #[must_use]
+    pub fn highlighter(mut self, highlighter: impl LineHighlighter + 'static) -> Self {
+        self.state.syntax_comment_tokens = highlighter.comment_tokens();
+        self.highlighter = Some(Box::new(highlighter));
         self
     }
 
@@ -106,6 +144,161 @@ impl<'a, 'b> EditorView<'a, 'b> {
         self
     }
 
+    /// Sets where overflowing lines are broken if lines are wrapped.
+    /// Defaults to [`WrapMode::Char`].
+    #[must_use]
+    pub fn wrap_mode(self, mode: WrapMode) -> Self {
+        self.state.view.wrap_options.mode = mode;
+        self
+    }
+
+    /// Sets whether the continuation rows of wrapped lines are indented
+    /// like the line (breakindent).
+    #[must_use]
+    pub fn break_indent(self, break_indent: bool) -> Self {
+        self.state.view.wrap_options.break_indent = break_indent;
+        self
+    }
+
+    /// Sets a marker that is displayed in front of the continuation rows
+    /// of wrapped lines (showbreak), e.g. `"↪ "`. The marker is rendered in
+    /// the [`EditorTheme::show_break_style`].
+    #[must_use]
+    pub fn show_break<S: Into<String>>(self, show_break: S) -> Self {
+        self.state.view.wrap_options.show_break = show_break.into();
+        self
+    }
+
+    /// Sets the line numbers that are displayed in a gutter left of the
+    /// text. Defaults to [`LineNumbers::None`].
+    #[must_use]
+    pub fn line_numbers(self, line_numbers: LineNumbers) -> Self {
+        self.state.view.line_numbers = line_numbers;
+        self
+    }
+
+    /// Sets whether the message of the most severe diagnostic of a line is
+    /// displayed behind the end of the line.
+    #[must_use]
+    pub fn diagnostic_virtual_text(self, virtual_text: bool) -> Self {
+        self.state.view.diagnostic_virtual_text = virtual_text;
+        self
+    }
+
+    /// Sets whether the host undercurls the text of diagnostics. Ratatui
+    /// can only underline, so the underlined cells are kept and the host
+    /// redraws them with an undercurl after every render, see
+    /// [`EditorState::draw_undercurls`].
+    #[must_use]
+    pub fn diagnostic_undercurl(self, undercurl: bool) -> Self {
+        self.state.view.diagnostic_undercurl = undercurl;
+        self
+    }
+
+    /// Sets whether the background of the cursor line is highlighted in
+    /// the [`EditorTheme::cursor_line_style`] (cursorline).
+    #[must_use]
+    pub fn cursor_line(self, cursor_line: bool) -> Self {
+        self.state.view.cursor_line = cursor_line;
+        self
+    }
+
+    /// Sets the screen columns whose background is highlighted in the
+    /// [`EditorTheme::color_column_style`] (colorcolumn), e.g. `[80, 100]`.
+    /// Columns start at one.
+    #[must_use]
+    pub fn color_columns<I: IntoIterator<Item = usize>>(self, columns: I) -> Self {
+        self.state.view.color_columns = columns.into_iter().filter(|&c| c > 0).collect();
+        self
+    }
+
+    /// Sets the glyphs that make tabs, trailing spaces, non-breaking spaces
+    /// and the end of lines visible (listchars). See [`ListChars`].
+    #[must_use]
+    pub fn list_chars(self, list_chars: ListChars) -> Self {
+        self.state.view.list_chars = list_chars;
+        self
+    }
+
+    /// Sets the minimum number of lines that are kept visible above and
+    /// below the cursor (scrolloff). The margin is at most half of the
+    /// height of the editor.
+    #[must_use]
+    pub fn scroll_off(self, scroll_off: usize) -> Self {
+        self.state.view.scroll_off = scroll_off;
+        self
+    }
+
+    /// Sets the minimum number of columns that are kept visible left and
+    /// right of the cursor if lines are not wrapped (sidescrolloff).
+    #[must_use]
+    pub fn side_scroll_off(self, side_scroll_off: usize) -> Self {
+        self.state.view.side_scroll_off = side_scroll_off;
+        self
+    }
+
+    /// Sets whether a vertical scrollbar is displayed right of the text.
+    /// The scrollbar marks the rows of search matches, diagnostics and the
+    /// selection. If mouse support is enabled, dragging the scrollbar
+    /// scrolls the editor.
+    #[must_use]
+    pub fn scrollbar(self, scrollbar: bool) -> Self {
+        self.state.view.scrollbar = scrollbar;
+        self
+    }
+
+    /// Sets whether the bracket under or left of the cursor and its
+    /// matching bracket are highlighted in the
+    /// [`EditorTheme::matching_bracket_style`]. Enabled by default.
+    #[must_use]
+    pub fn match_brackets(self, match_brackets: bool) -> Self {
+        self.state.view.match_pairs.brackets = match_brackets;
+        self
+    }
+
+    /// Sets whether the quote under or left of the cursor and its matching
+    /// quote on the same line are highlighted.
+    #[must_use]
+    pub fn match_quotes(self, match_quotes: bool) -> Self {
+        self.state.view.match_pairs.quotes = match_quotes;
+        self
+    }
+
+    /// Sets whether the html tag under the cursor and its matching opening
+    /// or closing tag are highlighted.
+    #[must_use]
+    pub fn match_tags(self, match_tags: bool) -> Self {
+        self.state.view.match_pairs.tags = match_tags;
+        self
+    }
+
+    /// Sets whether brackets are coloured by their nesting depth, cycling
+    /// through the [`EditorTheme::rainbow_bracket_styles`].
+    #[must_use]
+    pub fn rainbow_brackets(self, rainbow_brackets: bool) -> Self {
+        self.state.view.rainbow_brackets = rainbow_brackets;
+        self
+    }
+
+    /// Sets whether the cursor is displayed by the terminal instead of by
+    /// restyling a cell. The host then places the terminal cursor at
+    /// [`EditorState::cursor_screen_position`], e.g. with
+    /// `Frame::set_cursor_position`, and sets the shape returned by
+    /// [`EditorState::cursor_shape`]. Secondary cursors are still drawn as
+    /// cells.
+    #[must_use]
+    pub fn terminal_cursor(self, terminal_cursor: bool) -> Self {
+        self.state.view.terminal_cursor = terminal_cursor;
+        self
+    }
+
+    /// Sets the cursor shapes of the editor modes. See [`CursorShapes`].
+    #[must_use]
+    pub fn cursor_shapes(self, cursor_shapes: CursorShapes) -> Self {
+        self.state.view.cursor_shapes = cursor_shapes;
+        self
+    }
+
     pub(super) fn get_wrap(&self) -> bool {
         self.state.view.wrap
     }
@@ -154,6 +347,27 @@ impl Widget for EditorView<'_, '_> {
             Constraint::Length(u16::from(self.theme.status_line.is_some())),
         ])
         .areas(area);
+
+        // Split off the gutter left of the text. It consists of a sign
+        // column, shown if there are diagnostics, and the line numbers.
+        let line_numbers = self.state.view.line_numbers;
+        let sign_width = if self.state.diagnostics.is_empty() {
+            0
+        } else {
+            2
+        };
+        let number_width = line_numbers.width(self.state.lines.len());
+        let gutter_width = sign_width + number_width;
+        let [gutter, main] =
+            Layout::horizontal([Constraint::Length(gutter_width as u16), Constraint::Min(0)])
+                .areas(main);
+
+        // Split off the scrollbar right of the text.
+        let scrollbar_width = u16::from(self.state.view.scrollbar);
+        let [main, scrollbar_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(scrollbar_width)])
+                .areas(main);
+        self.state.view.scrollbar_area = scrollbar_area;
         let width = main.width as usize;
         let height = main.height as usize;
         let wrap_lines = self.get_wrap();
@@ -165,8 +379,13 @@ impl Widget for EditorView<'_, '_> {
         let max_col = max_col(&self.state.lines, &self.state.cursor, self.state.mode);
         let cursor = Index2::new(self.state.cursor.row, self.state.cursor.col.min(max_col));
 
-        // Store the coordinats of the current editor.
-        self.state.view.set_screen_area(area);
+        // Store the coordinats of the current editor. The gutter is excluded
+        // so that mouse positions map onto the text.
+        self.state.view.set_screen_area(Rect {
+            x: main.x,
+            width: main.width,
+            ..area
+        });
 
         // Update the view offset. Requuires the screen size and the position
         // of the cursor. Updates the view offset only if the cursor is out
@@ -175,13 +394,25 @@ impl Widget for EditorView<'_, '_> {
         let (offset_x, offset_y) = if wrap_lines {
             (
                 0,
-                view_state.update_viewport_vertical_wrap(width, height, cursor.row, lines),
+                view_state.update_viewport_vertical_wrap(
+                    width,
+                    height,
+                    cursor.row,
+                    lines,
+                    &self.state.inlay_hints,
+                    &self.state.folds,
+                ),
             )
         } else {
             let line = lines.get(RowIndex::new(cursor.row));
             (
                 view_state.update_viewport_horizontal(width, cursor.col, line),
-                view_state.update_viewport_vertical(height, cursor.row),
+                view_state.update_viewport_vertical(
+                    height,
+                    cursor.row,
+                    lines.len(),
+                    &self.state.folds,
+                ),
             )
         };
 
@@ -190,45 +421,228 @@ impl Widget for EditorView<'_, '_> {
         if self.state.mode == EditorMode::Search {
             search_selection = (&self.state.search).into();
         };
-        let selections = vec![&self.state.selection, &search_selection];
+        let mut selections = vec![&self.state.selection, &search_selection];
+        selections.extend(self.state.cursors.iter().map(|c| &c.selection));
+
+        // The displayed positions of the secondary cursors.
+        let secondary_cursors: Vec<Index2> = self
+            .state
+            .cursors
+            .iter()
+            .map(|c| {
+                let col = crate::helper::max_col(&self.state.lines, &c.cursor, self.state.mode);
+                Index2::new(c.cursor.row, c.cursor.col.min(col))
+            })
+            .collect();
+
+        // The styles of the brackets, coloured by their depth, and of the
+        // pair at the cursor. Selected brackets are not coloured.
+        let mut pair_styles: Vec<(Index2, Style)> = Vec::new();
+        let rainbow_styles = &self.theme.rainbow_bracket_styles;
+        if self.state.view.rainbow_brackets && !rainbow_styles.is_empty() {
+            let folds = &self.state.folds;
+            let last_row = folds.row_at_visible(folds.visible_row(offset_y) + height);
+            for (index, depth) in bracket::bracket_depths(lines, offset_y..last_row + 1) {
+                let selected = selections
+                    .iter()
+                    .any(|s| s.as_ref().is_some_and(|s| s.contains(&index)));
+                if !selected {
+                    pair_styles.push((index, rainbow_styles[depth % rainbow_styles.len()]));
+                }
+            }
+        }
+        for index in bracket::matching_pair(lines, cursor, self.state.view.match_pairs) {
+            pair_styles.push((index, self.theme.matching_bracket_style));
+        }
 
         let mut cursor_position: Option<Position> = None;
+        let mut completion_position: Option<Position> = None;
+        let mut secondary_positions: Vec<Position> = Vec::new();
+        let mut undercurl_cells: Vec<Position> = Vec::new();
         let mut content_area = main;
         let mut num_rendered_rows = 0;
+        let mut folded_until = None;
+
+        // The text is rendered without a background, such that the cursor
+        // line and the colour columns below it remain visible. Selections
+        // and syntax highlighting are drawn on top of them.
+        let text_style = Style {
+            bg: None,
+            ..self.theme.base
+        };
+
+        // The highlighter keeps its state between renders.
+        let mut highlight_cache = std::mem::take(&mut self.state.view.highlight_cache);
+        highlight_cache.sync(self.state.lines.len());
 
-        let mut row_index = offset_y;
-        for line in lines.iter_row().skip(row_index) {
+        for (row_index, line) in lines.iter_row().enumerate().skip(offset_y) {
             if content_area.height == 0 {
                 break;
             }
 
+            // Skip the rows that are hidden in a closed fold.
+            if folded_until.is_some_and(|end| row_index <= end) {
+                continue;
+            }
+
             let col_skips = offset_x;
             num_rendered_rows += 1;
 
+            // Render the sign and the line number on the first row of the line.
+            let diagnostics = &self.state.diagnostics;
+            let severe_diagnostic = diagnostic::most_severe(diagnostics, row_index);
+            if let Some(diagnostic) = severe_diagnostic {
+                let style = self.theme.diagnostic_style(diagnostic.severity);
+                let sign = diagnostic.severity.sign().to_string();
+                buf.set_stringn(gutter.x, content_area.y, sign, gutter.width.into(), style);
+            }
+            if line_numbers != LineNumbers::None {
+                let label = line_numbers.label(row_index, cursor.row);
+                let style = if row_index == cursor.row {
+                    self.theme.current_line_number_style
+                } else {
+                    self.theme.line_number_style
+                };
+                let label = format!("{label:>width$} ", width = number_width - 1);
+                let x = gutter.x + sign_width as u16;
+                let max_width = gutter.right().saturating_sub(x);
+                buf.set_stringn(x, content_area.y, label, max_width.into(), style);
+            }
+
+            // Render a closed fold as a single summary row.
+            if let Some(fold) = self.state.folds.closed_fold(row_index) {
+                let row_area = Rect {
+                    height: 1,
+                    ..content_area
+                };
+                buf.set_style(row_area, self.theme.fold_style);
+                let summary = self.state.fold_summary(fold);
+                let style = self.theme.fold_style;
+                buf.set_stringn(row_area.x, row_area.y, summary, width, style);
+                if (fold.start..=fold.end).contains(&cursor.row) {
+                    cursor_position = Some(row_area.as_position());
+                }
+                num_rendered_rows += fold.end - row_index;
+                folded_until = Some(fold.end);
+                content_area = rect_indent_y(content_area, 1);
+                continue;
+            }
+
+            let highlighted = self.highlighter.as_ref().and_then(|highlighter| {
+                highlighter.highlight_line(lines, row_index, &self.theme, &mut highlight_cache)
+            });
             let spans = generate_spans(
                 line,
                 &selections,
                 row_index,
                 col_skips,
-                &self.theme.base,
+                &text_style,
                 &self.theme.selection_style,
-                #[cfg(feature = "syntax-highlighting")]
-                self.syntax_highlighter.as_ref(),
+                highlighted,
             );
+            let hints = visible_inlay_hints(&self.state.inlay_hints, row_index, col_skips);
+            let spans = insert_inlay_hints(spans, hints, col_skips, &self.theme.inlay_hint_style);
 
             let render_line = if wrap_lines {
-                RenderLine::Wrapped(LineWrapper::wrap_spans(spans, width, tab_width))
+                let options = &self.state.view.wrap_options;
+                let display_line = display_line(line, hints);
+                let rows = LineWrapper::layout(&display_line, width, tab_width, options);
+                let prefix = match rows.get(1) {
+                    Some(row) if row.indent > 0 => {
+                        let show_break = options.show_break.clone();
+                        let show_break_chars: Vec<char> = show_break.chars().collect();
+                        let show_break_width = chars_width(&show_break_chars, tab_width);
+                        vec![
+                            Span::styled(" ".repeat(row.indent - show_break_width), text_style),
+                            Span::styled(show_break, self.theme.show_break_style),
+                        ]
+                    }
+                    _ => Vec::new(),
+                };
+                RenderLine::Wrapped(LineWrapper::split_spans(spans, &rows), prefix)
             } else {
                 RenderLine::Single(spans)
             };
 
-            // Determine the cursor position.
-            if row_index == cursor.row {
-                cursor_position = Some(render_line.data_coordinate_to_screen_coordinate(
-                    cursor.col.saturating_sub(offset_x),
+            // Maps a column of the line to the screen, skipping inlay hints.
+            let screen_position = |col: usize| {
+                render_line.data_coordinate_to_screen_coordinate(
+                    display_col(hints, col, col_skips),
                     content_area,
                     tab_width,
-                ));
+                )
+            };
+
+            // Determine the cursor position.
+            if row_index == cursor.row {
+                cursor_position = Some(screen_position(cursor.col));
+                if self.state.completion.is_open() {
+                    let anchor = self.state.completion_anchor();
+                    completion_position = Some(screen_position(anchor.col));
+                }
+            }
+            for secondary in secondary_cursors.iter().filter(|c| c.row == row_index) {
+                if secondary.col >= offset_x {
+                    secondary_positions.push(screen_position(secondary.col));
+                }
+            }
+
+            // Determine the decorations of the diagnostics.
+            let underlines = diagnostic::underlined_cells(
+                line,
+                row_index,
+                diagnostics,
+                col_skips,
+                content_area,
+                tab_width,
+                screen_position,
+            );
+            let virtual_text = severe_diagnostic
+                .filter(|_| self.state.view.diagnostic_virtual_text)
+                .map(|diagnostic| {
+                    let position = diagnostic::virtual_text_position(line, screen_position);
+                    (position, diagnostic)
+                });
+
+            // Determine the glyphs of the visible whitespace.
+            let line_area = Rect {
+                height: (render_line.num_lines() as u16).min(content_area.height),
+                ..content_area
+            };
+            let glyphs: Vec<(Position, char)> = self
+                .state
+                .view
+                .list_chars
+                .glyphs(line)
+                .into_iter()
+                .filter(|&(col, _)| col >= col_skips)
+                .map(|(col, glyph)| (screen_position(col), glyph))
+                .filter(|&(position, _)| line_area.contains(position))
+                .collect();
+            let pair_cells: Vec<(Position, Style)> = pair_styles
+                .iter()
+                .filter(|(index, _)| index.row == row_index && index.col >= col_skips)
+                .map(|&(index, style)| (screen_position(index.col), style))
+                .filter(|&(position, _)| line_area.contains(position))
+                .collect();
+
+            // Highlight the background of the cursor line and the colour
+            // columns.
+            if self.state.view.cursor_line && row_index == cursor.row {
+                buf.set_style(line_area, self.theme.cursor_line_style);
+            }
+            for &column in &self.state.view.color_columns {
+                let Some(x) = (column - 1).checked_sub(offset_x) else {
+                    continue;
+                };
+                if x < line_area.width as usize {
+                    let column_area = Rect {
+                        x: line_area.x + x as u16,
+                        width: 1,
+                        ..line_area
+                    };
+                    buf.set_style(column_area, self.theme.color_column_style);
+                }
             }
 
             // Render the current line.
@@ -238,21 +652,90 @@ impl Widget for EditorView<'_, '_> {
                 rect_indent_y(content_area, num_lines)
             };
 
-            row_index += 1;
+            // Draw the whitespace glyphs over the rendered whitespace.
+            for (position, glyph) in glyphs {
+                if let Some(cell) = buf.cell_mut(position) {
+                    cell.set_char(glyph).set_style(self.theme.whitespace_style);
+                }
+            }
+
+            // Highlight the brackets and the pair at the cursor.
+            for (position, style) in pair_cells {
+                if let Some(cell) = buf.cell_mut(position) {
+                    cell.set_style(style);
+                }
+            }
+
+            // Underline the diagnostics and render the virtual text.
+            for (cell, severity) in underlines {
+                buf.set_style(cell, self.theme.diagnostic_underline_style(severity));
+                if self.state.view.diagnostic_undercurl && !cell.is_empty() {
+                    undercurl_cells.push(cell.as_position());
+                }
+            }
+            if let Some((position, diagnostic)) = virtual_text {
+                if main.contains(position) {
+                    let message = diagnostic.message.lines().next().unwrap_or_default();
+                    let style = self.theme.diagnostic_style(diagnostic.severity);
+                    let max_width = main.right() - position.x;
+                    buf.set_stringn(position.x, position.y, message, max_width.into(), style);
+                }
+            }
         }
 
-        // Render the cursor on top.
-        if let Some(cell) = buf.cell_mut(cursor_position.unwrap_or(Position::new(
+        self.state.view.highlight_cache = highlight_cache;
+
+        // Render the secondary cursors and the cursor on top.
+        for position in secondary_positions {
+            if main.contains(position) {
+                if let Some(cell) = buf.cell_mut(position) {
+                    cell.set_style(self.theme.cursor_style);
+                }
+            }
+        }
+        let cursor_position = cursor_position.unwrap_or(Position::new(
             main.left(),
             main.top() + self.state.cursor.row as u16,
-        ))) {
-            cell.set_style(self.theme.cursor_style);
+        ));
+        self.state.view.cursor_position = main.contains(cursor_position).then_some(cursor_position);
+        if !self.state.view.terminal_cursor {
+            if let Some(cell) = buf.cell_mut(cursor_position) {
+                cell.set_style(self.theme.cursor_style);
+            }
+        }
+
+        // Render the completion popup next to the cursor.
+        if let Some(anchor) = completion_position {
+            CompletionPopup {
+                items: &self.state.completion.items,
+                selected: self.state.completion.selected,
+                style: self.theme.completion_style,
+                selected_style: self.theme.completion_selected_style,
+            }
+            .render(anchor, main, buf);
         }
 
+        // Keep the underlined cells, as rendered, to be undercurled.
+        self.state.view.undercurls = undercurl_cells
+            .into_iter()
+            .filter_map(|position| Some((position, buf.cell(position)?.clone())))
+            .filter(|(_, cell)| cell.modifier.contains(Modifier::UNDERLINED))
+            .collect();
+
         // Save the total number of lines that are currently displayed on the viewport.
         // Required to handle scrolling.
         self.state.view.update_num_rows(num_rendered_rows);
 
+        // Render the scrollbar.
+        if self.state.view.scrollbar {
+            Scrollbar {
+                state: self.state,
+                theme: &self.theme,
+                num_rows: num_rendered_rows,
+            }
+            .render(scrollbar_area, buf);
+        }
+
         // Render the status line.
         if let Some(s) = self.theme.status_line {
             s.mode(self.state.mode.name())
@@ -261,6 +744,11 @@ impl Widget for EditorView<'_, '_> {
                 } else {
                     None
                 })
+                .command(if self.state.mode == EditorMode::Command {
+                    Some(self.state.command())
+                } else {
+                    None
+                })
                 .render(status, buf);
         }
     }
@@ -273,14 +761,17 @@ fn generate_spans<'a>(
     col_skips: usize,
     base_style: &Style,
     highlight_style: &Style,
-    #[cfg(feature = "syntax-highlighting")] syntax_highlighter: Option<&SyntaxHighlighter>,
+    highlighted: Option<Vec<Span<'static>>>,
 ) -> Vec<Span<'a>> {
-    #[cfg(feature = "syntax-highlighting")]
-    if let Some(syntax) = syntax_highlighter {
+    // Highlighted spans that do not hold the chars of the line are ignored.
+    let highlighted = highlighted.filter(|spans| {
+        let len: usize = spans.iter().map(|span| span.content.chars().count()).sum();
+        len == line.len()
+    });
+    if let Some(spans) = highlighted {
         return line_into_highlighted_spans_with_selections(
-            line,
+            spans.into_iter().map(InternalSpan::from).collect(),
             selections,
-            syntax,
             row_index,
             col_skips,
             highlight_style,
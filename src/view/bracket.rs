@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+use jagged::index::RowIndex;
+
+use crate::{helper::find_matching_bracket, Index2, Lines};
+
+/// Which pairs are highlighted if the cursor is on one of their ends.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct MatchPairs {
+    /// Brackets, i.e. `()`, `[]` and `{}`.
+    pub(crate) brackets: bool,
+    /// Quotes on the same line, i.e. `""`, `''` and ``` `` ```.
+    pub(crate) quotes: bool,
+    /// Opening and closing html tags.
+    pub(crate) tags: bool,
+}
+
+/// Returns the positions of the pair under the cursor, or of the pair next
+/// to the left of the cursor. Tags are returned with all their chars.
+pub(super) fn matching_pair(lines: &Lines, cursor: Index2, pairs: MatchPairs) -> Vec<Index2> {
+    let candidates = [Some(cursor.col), cursor.col.checked_sub(1)];
+    for col in candidates.into_iter().flatten() {
+        let index = Index2::new(cursor.row, col);
+        if pairs.brackets {
+            if let Some(other) = find_matching_bracket(lines, index) {
+                return vec![index, other];
+            }
+        }
+        if pairs.quotes {
+            if let Some(other) = lines
+                .get(RowIndex::new(cursor.row))
+                .and_then(|line| find_matching_quote(line, col))
+            {
+                return vec![index, Index2::new(cursor.row, other)];
+            }
+        }
+    }
+    if pairs.tags {
+        if let Some((tag, other)) = find_matching_tag(lines, cursor) {
+            let mut positions: Vec<Index2> = [tag, other]
+                .into_iter()
+                .flat_map(|tag| tag.cols.map(move |col| Index2::new(tag.row, col)))
+                .collect();
+            positions.sort_by_key(|index| (index.row, index.col));
+            return positions;
+        }
+    }
+    Vec::new()
+}
+
+/// Returns the column of the quote that pairs with the quote at `col`.
+/// Quotes escaped with a backslash are skipped.
+fn find_matching_quote(line: &[char], col: usize) -> Option<usize> {
+    let quote = *line.get(col)?;
+    if !matches!(quote, '"' | '\'' | '`') {
+        return None;
+    }
+    let quotes: Vec<usize> = (0..line.len())
+        .filter(|&i| line[i] == quote && (i == 0 || line[i - 1] != '\\'))
+        .collect();
+    let index = quotes.iter().position(|&i| i == col)?;
+    let other = if index % 2 == 0 { index + 1 } else { index - 1 };
+    quotes.get(other).copied()
+}
+
+/// An opening or closing html tag on a single row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tag {
+    row: usize,
+    cols: Range<usize>,
+    name: String,
+    closing: bool,
+}
+
+/// Returns the tags of a line. Self-closing tags are skipped.
+fn tags(line: &[char], row: usize) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut col = 0;
+    while let Some(start) = (col..line.len()).find(|&i| line[i] == '<') {
+        let Some(end) = (start + 1..line.len()).find(|&i| matches!(line[i], '<' | '>')) else {
+            break;
+        };
+        col = end;
+        if line[end] == '<' {
+            continue;
+        }
+        let closing = line.get(start + 1) == Some(&'/');
+        let name: String = line[start + 1 + usize::from(closing)..end]
+            .iter()
+            .take_while(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | ':' | '.'))
+            .collect();
+        if !name.is_empty() && line[end - 1] != '/' {
+            tags.push(Tag {
+                row,
+                cols: start..end + 1,
+                name,
+                closing,
+            });
+        }
+    }
+    tags
+}
+
+/// Returns the tag under the cursor and the tag that pairs with it.
+fn find_matching_tag(lines: &Lines, cursor: Index2) -> Option<(Tag, Tag)> {
+    let all: Vec<Tag> = lines
+        .iter_row()
+        .enumerate()
+        .flat_map(|(row, line)| tags(line, row))
+        .collect();
+    let index = all
+        .iter()
+        .position(|tag| tag.row == cursor.row && tag.cols.contains(&cursor.col))?;
+    let tag = &all[index];
+    let mut depth = 0usize;
+    let candidates: Box<dyn Iterator<Item = &Tag>> = if tag.closing {
+        Box::new(all[..index].iter().rev())
+    } else {
+        Box::new(all[index + 1..].iter())
+    };
+    for other in candidates.filter(|other| other.name == tag.name) {
+        if other.closing == tag.closing {
+            depth += 1;
+        } else if depth == 0 {
+            return Some((tag.clone(), other.clone()));
+        } else {
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// Returns the nesting depth of the brackets in the rows `rows`, counting
+/// from the start of the text.
+pub(super) fn bracket_depths(lines: &Lines, rows: Range<usize>) -> Vec<(Index2, usize)> {
+    let mut depths = Vec::new();
+    let mut depth = 0usize;
+    for (row, line) in lines.iter_row().enumerate().take(rows.end) {
+        for (col, ch) in line.iter().enumerate() {
+            let bracket_depth = match ch {
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    depth - 1
+                }
+                ')' | ']' | '}' => {
+                    depth = depth.saturating_sub(1);
+                    depth
+                }
+                _ => continue,
+            };
+            if row >= rows.start {
+                depths.push((Index2::new(row, col), bracket_depth));
+            }
+        }
+    }
+    depths
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    use super::*;
+    use crate::{EditorState, EditorTheme, EditorView};
+
+    fn pairs(brackets: bool, quotes: bool, tags: bool) -> MatchPairs {
+        MatchPairs {
+            brackets,
+            quotes,
+            tags,
+        }
+    }
+
+    #[test]
+    fn test_matching_pair() {
+        let lines = Lines::from("f(a, \"b\\\"\")\n<p><b>x</b>\n</p>");
+        let all = pairs(true, true, true);
+
+        let pair = matching_pair(&lines, Index2::new(0, 1), all);
+        assert_eq!(pair, vec![Index2::new(0, 1), Index2::new(0, 10)]);
+        // The bracket left of the cursor.
+        let pair = matching_pair(&lines, Index2::new(0, 2), all);
+        assert_eq!(pair, vec![Index2::new(0, 1), Index2::new(0, 10)]);
+        let pair = matching_pair(&lines, Index2::new(0, 5), all);
+        assert_eq!(pair, vec![Index2::new(0, 5), Index2::new(0, 9)]);
+        assert!(matching_pair(&lines, Index2::new(0, 5), pairs(true, false, false)).is_empty());
+
+        let pair = matching_pair(&lines, Index2::new(2, 1), all);
+        let expected: Vec<_> = (0..3)
+            .map(|col| Index2::new(1, col))
+            .chain((0..4).map(|col| Index2::new(2, col)))
+            .collect();
+        assert_eq!(pair, expected);
+    }
+
+    #[test]
+    fn test_bracket_depths() {
+        let lines = Lines::from("(a [b]\n{c)})");
+        let depths: Vec<_> = bracket_depths(&lines, 1..2)
+            .into_iter()
+            .map(|(index, depth)| (index.col, depth))
+            .collect();
+        assert_eq!(depths, vec![(0, 1), (2, 1), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn test_render_matching_and_rainbow_brackets() {
+        let mut state = EditorState::new(Lines::from("(a (b))"));
+        let theme = EditorTheme::default();
+        let matching_bg = theme.matching_bracket_style.bg;
+        let depth_one_fg = theme.rainbow_bracket_styles[1].fg;
+        let area = Rect::new(0, 0, 8, 2);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .rainbow_brackets(true)
+            .render(area, &mut buf);
+
+        assert_eq!(Some(buf[(6, 0)].bg), matching_bg);
+        assert_ne!(Some(buf[(3, 0)].bg), matching_bg);
+        assert_eq!(Some(buf[(3, 0)].fg), depth_one_fg);
+        assert_eq!(Some(buf[(5, 0)].fg), depth_one_fg);
+    }
+}
@@ -0,0 +1,130 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Clear, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::completion::CompletionItem;
+
+/// The maximum number of items that are displayed at once.
+const MAX_HEIGHT: usize = 10;
+
+/// The popup that displays the completion items next to the cursor.
+pub(super) struct CompletionPopup<'a> {
+    pub(super) items: &'a [CompletionItem],
+    pub(super) selected: Option<usize>,
+    pub(super) style: Style,
+    pub(super) selected_style: Style,
+}
+
+impl CompletionPopup<'_> {
+    /// Renders the popup below `anchor`, or above if there is not enough
+    /// space below. The popup is kept within `area`.
+    pub(super) fn render(self, anchor: Position, area: Rect, buf: &mut Buffer) {
+        let label_width = self.items.iter().map(|i| i.label.width()).max();
+        let detail_width = self
+            .items
+            .iter()
+            .filter_map(|i| i.detail.as_ref())
+            .map(|d| d.width())
+            .max();
+        let label_width = label_width.unwrap_or_default();
+        let content_width = match detail_width {
+            Some(detail_width) => label_width + 2 + detail_width,
+            None => label_width,
+        };
+
+        let width = (content_width as u16 + 2).min(area.width);
+        let height = self.items.len().min(MAX_HEIGHT) as u16;
+        let below = anchor.y.saturating_add(1);
+        let y = if below + height <= area.bottom() || anchor.y < area.top() + height {
+            below
+        } else {
+            anchor.y - height
+        };
+        let x = anchor
+            .x
+            .min(area.right().saturating_sub(width))
+            .max(area.left());
+        let popup = Rect::new(x, y, width, height).intersection(area);
+        if popup.is_empty() {
+            return;
+        }
+
+        let offset = self.selected.map_or(0, |selected| {
+            (selected + 1).saturating_sub(popup.height as usize)
+        });
+        Clear.render(popup, buf);
+        for (i, item) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(popup.height as usize)
+        {
+            let style = if Some(i) == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let detail = item.detail.as_deref().unwrap_or_default();
+            let text = format!(" {:<label_width$}  {detail}", item.label);
+            let row = Rect::new(popup.x, popup.y + (i - offset) as u16, popup.width, 1);
+            buf.set_style(row, style);
+            Line::from(text).render(row, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn popup(items: &[CompletionItem], selected: Option<usize>) -> CompletionPopup<'_> {
+        CompletionPopup {
+            items,
+            selected,
+            style: Style::default(),
+            selected_style: Style::default(),
+        }
+    }
+
+    #[test]
+    fn test_render_below_anchor() {
+        let items = [
+            CompletionItem::new("foo").detail("fn"),
+            CompletionItem::new("foobar"),
+        ];
+        let area = Rect::new(0, 0, 14, 4);
+        let mut buf = Buffer::empty(area);
+
+        popup(&items, Some(0)).render(Position::new(1, 0), area, &mut buf);
+        assert_eq!(
+            buf,
+            Buffer::with_lines([
+                "              ",
+                "  foo     fn  ",
+                "  foobar      ",
+                "              ",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_render_above_anchor_and_scroll() {
+        let items: Vec<_> = (0..12)
+            .map(|i| CompletionItem::new(format!("{i}")))
+            .collect();
+        let area = Rect::new(0, 0, 5, 12);
+        let mut buf = Buffer::empty(area);
+
+        popup(&items, Some(11)).render(Position::new(0, 11), area, &mut buf);
+        assert_eq!(buf[(1, 0)].symbol(), " ");
+        assert_eq!(buf[(1, 1)].symbol(), "2");
+        assert_eq!(buf[(1, 10)].symbol(), "1");
+        assert_eq!(buf[(2, 10)].symbol(), "1");
+    }
+}
@@ -0,0 +1,119 @@
+use ratatui::crossterm::cursor::SetCursorStyle;
+
+use crate::EditorMode;
+
+/// The shape of the terminal cursor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    /// A block that covers the cell.
+    #[default]
+    Block,
+    /// A vertical bar left of the cell.
+    Bar,
+    /// A line below the cell.
+    Underline,
+}
+
+impl From<CursorShape> for SetCursorStyle {
+    /// Converts the shape into the crossterm command that sets a steady
+    /// cursor of the shape.
+    fn from(shape: CursorShape) -> Self {
+        match shape {
+            CursorShape::Block => Self::SteadyBlock,
+            CursorShape::Bar => Self::SteadyBar,
+            CursorShape::Underline => Self::SteadyUnderScore,
+        }
+    }
+}
+
+/// The cursor shapes of the editor modes. Defaults to a block in normal
+/// and visual mode and a bar in the other modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CursorShapes {
+    /// The shape in normal mode.
+    pub normal: CursorShape,
+    /// The shape in insert mode.
+    pub insert: CursorShape,
+    /// The shape in visual mode.
+    pub visual: CursorShape,
+    /// The shape in search mode.
+    pub search: CursorShape,
+    /// The shape in command mode.
+    pub command: CursorShape,
+}
+
+impl Default for CursorShapes {
+    fn default() -> Self {
+        Self {
+            normal: CursorShape::Block,
+            insert: CursorShape::Bar,
+            visual: CursorShape::Block,
+            search: CursorShape::Bar,
+            command: CursorShape::Bar,
+        }
+    }
+}
+
+impl CursorShapes {
+    /// Sets the shape in `mode`.
+    #[must_use]
+    pub fn with(mut self, mode: EditorMode, shape: CursorShape) -> Self {
+        *self.get_mut(mode) = shape;
+        self
+    }
+
+    /// Returns the shape in `mode`.
+    #[must_use]
+    pub fn get(&self, mode: EditorMode) -> CursorShape {
+        match mode {
+            EditorMode::Normal => self.normal,
+            EditorMode::Insert => self.insert,
+            EditorMode::Visual => self.visual,
+            EditorMode::Search => self.search,
+            EditorMode::Command => self.command,
+        }
+    }
+
+    fn get_mut(&mut self, mode: EditorMode) -> &mut CursorShape {
+        match mode {
+            EditorMode::Normal => &mut self.normal,
+            EditorMode::Insert => &mut self.insert,
+            EditorMode::Visual => &mut self.visual,
+            EditorMode::Search => &mut self.search,
+            EditorMode::Command => &mut self.command,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, layout::Position, layout::Rect, widgets::Widget};
+
+    use super::*;
+    use crate::{
+        actions::Execute, actions::SwitchMode, EditorState, EditorTheme, EditorView, Index2, Lines,
+    };
+
+    #[test]
+    fn test_terminal_cursor() {
+        let mut state = EditorState::new(Lines::from("abc\ndef"));
+        state.cursor = Index2::new(1, 2);
+        let area = Rect::new(0, 0, 10, 3);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .terminal_cursor(true)
+            .render(area, &mut buf);
+
+        assert_eq!(state.cursor_screen_position(), Some(Position::new(2, 1)));
+        assert_eq!(buf[(2, 1)].bg, EditorTheme::default().base.bg.unwrap());
+        assert_eq!(state.cursor_shape(), CursorShape::Block);
+
+        SwitchMode(EditorMode::Insert).execute(&mut state);
+        assert_eq!(state.cursor_shape(), CursorShape::Bar);
+
+        EditorView::new(&mut state)
+            .cursor_shapes(CursorShapes::default().with(EditorMode::Insert, CursorShape::Underline))
+            .render(area, &mut buf);
+        assert_eq!(state.cursor_shape(), CursorShape::Underline);
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::{self, Write};
+
+use ratatui::{
+    crossterm::{
+        cursor::{MoveTo, RestorePosition, SavePosition},
+        queue,
+        style::{
+            Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+            SetUnderlineColor,
+        },
+    },
+    layout::{Position, Rect},
+    style::{Color, Modifier},
+};
+
+use crate::{helper::grapheme_widths, Diagnostic, EditorState, Severity};
+
+impl EditorState {
+    /// Redraws the underlined text of diagnostics of the last render with
+    /// an undercurl, if the editor is rendered with
+    /// [`EditorView::diagnostic_undercurl`](crate::EditorView::diagnostic_undercurl).
+    ///
+    /// Ratatui cannot ask the terminal for an undercurl, call this after
+    /// every `Terminal::draw` with the writer of the backend, e.g.
+    /// `state.draw_undercurls(terminal.backend_mut())`. Terminals without
+    /// undercurl support keep the underline. The terminal cursor is not
+    /// moved.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the terminal fails.
+    pub fn draw_undercurls<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.view.undercurls.is_empty() {
+            return Ok(());
+        }
+        queue!(writer, SavePosition)?;
+        for (position, cell) in &self.view.undercurls {
+            queue!(
+                writer,
+                MoveTo(position.x, position.y),
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(cell.fg.into()),
+                SetBackgroundColor(cell.bg.into()),
+                SetUnderlineColor(cell.underline_color.into()),
+            )?;
+            for (modifier, attribute) in [
+                (Modifier::BOLD, Attribute::Bold),
+                (Modifier::DIM, Attribute::Dim),
+                (Modifier::ITALIC, Attribute::Italic),
+                (Modifier::REVERSED, Attribute::Reverse),
+                (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+            ] {
+                if cell.modifier.contains(modifier) {
+                    queue!(writer, SetAttribute(attribute))?;
+                }
+            }
+            queue!(
+                writer,
+                SetAttribute(Attribute::Undercurled),
+                Print(cell.symbol()),
+            )?;
+        }
+        queue!(
+            writer,
+            SetForegroundColor(Color::Reset.into()),
+            SetBackgroundColor(Color::Reset.into()),
+            SetUnderlineColor(Color::Reset.into()),
+            SetAttribute(Attribute::Reset),
+            RestorePosition,
+        )?;
+        writer.flush()
+    }
+}
+
+/// Returns the screen cells that are underlined by the diagnostics of the
+/// line `row`. `screen_position` maps a column of the line to the screen.
+pub(super) fn underlined_cells(
+    line: &[char],
+    row: usize,
+    diagnostics: &[Diagnostic],
+    col_skips: usize,
+    area: Rect,
+    tab_width: usize,
+    screen_position: impl Fn(usize) -> Position,
+) -> Vec<(Rect, Severity)> {
+    let widths = grapheme_widths(line, tab_width);
+    let mut cells = Vec::new();
+    for diagnostic in diagnostics {
+        let Some(columns) = diagnostic.columns(row, line.len()) else {
+            continue;
+        };
+        for col in columns.filter(|&col| col >= col_skips) {
+            let position = screen_position(col);
+            let width = widths.get(col).copied().unwrap_or(1);
+            let cell = Rect::new(position.x, position.y, width as u16, 1);
+            cells.push((cell.intersection(area), diagnostic.severity));
+        }
+    }
+    cells
+}
+
+/// Returns the most severe diagnostic that starts on the line `row`.
+pub(super) fn most_severe(diagnostics: &[Diagnostic], row: usize) -> Option<&Diagnostic> {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.start.row == row)
+        .max_by_key(|diagnostic| diagnostic.severity)
+}
+
+/// Returns the screen position of the virtual text behind the line,
+/// leaving one cell of space after its end.
+pub(super) fn virtual_text_position(
+    line: &[char],
+    screen_position: impl Fn(usize) -> Position,
+) -> Position {
+    let position = screen_position(line.len());
+    Position::new(position.x.saturating_add(1), position.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, widgets::Widget};
+
+    use crate::{EditorState, EditorView, Index2, LineNumbers, Lines};
+
+    use super::*;
+
+    #[test]
+    fn test_render_diagnostics() {
+        let mut state = EditorState::new(Lines::from("let x\nfoo"));
+        state.set_diagnostics(vec![Diagnostic::new(
+            Index2::new(0, 4),
+            Index2::new(0, 5),
+            Severity::Warning,
+            "unused\nsecond line",
+        )]);
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .line_numbers(LineNumbers::Absolute)
+            .diagnostic_virtual_text(true)
+            .render(area, &mut buf);
+
+        let row = |y| (0..20).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "W   1 let x unused  ");
+        assert_eq!(row(1), "    2 foo           ");
+
+        let underlined = |x: u16| buf[(x, 0)].modifier.contains(Modifier::UNDERLINED);
+        assert!(underlined(10));
+        assert!(!underlined(9));
+        assert!(!underlined(11));
+    }
+
+    #[test]
+    fn test_draw_undercurls() {
+        let mut state = EditorState::new(Lines::from("let x"));
+        state.set_diagnostics(vec![Diagnostic::new(
+            Index2::new(0, 4),
+            Index2::new(0, 5),
+            Severity::Error,
+            "unused",
+        )]);
+        let area = Rect::new(0, 0, 10, 2);
+        let mut output = Vec::new();
+
+        EditorView::new(&mut state).render(area, &mut Buffer::empty(area));
+        state.draw_undercurls(&mut output).unwrap();
+        assert!(output.is_empty());
+
+        EditorView::new(&mut state)
+            .diagnostic_undercurl(true)
+            .render(area, &mut Buffer::empty(area));
+        assert_eq!(state.view.undercurls.len(), 1);
+        assert_eq!(state.view.undercurls[0].0, Position::new(6, 0));
+        state.draw_undercurls(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[1;7H"));
+        assert!(output.contains("\x1b[4:3mx"));
+    }
+}
@@ -0,0 +1,57 @@
+/// The line numbers that are displayed in the gutter left of the text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineNumbers {
+    /// No line numbers.
+    #[default]
+    None,
+    /// The number of every line.
+    Absolute,
+    /// The distance of every line to the cursor line.
+    Relative,
+    /// The distance of every line to the cursor line, and the number of
+    /// the cursor line.
+    Hybrid,
+}
+
+impl LineNumbers {
+    /// Returns the width of the line numbers including a space behind them.
+    /// Fits the number of the last line, but at least three digits.
+    pub(crate) fn width(self, num_lines: usize) -> usize {
+        if self == Self::None {
+            return 0;
+        }
+        let digits = num_lines.max(1).ilog10() as usize + 1;
+        digits.max(3) + 1
+    }
+
+    /// Returns the line number displayed for `row`.
+    pub(crate) fn label(self, row: usize, cursor_row: usize) -> String {
+        let distance = row.abs_diff(cursor_row);
+        match self {
+            Self::None => String::new(),
+            Self::Absolute => (row + 1).to_string(),
+            Self::Relative => distance.to_string(),
+            Self::Hybrid if distance == 0 => (row + 1).to_string(),
+            Self::Hybrid => distance.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_numbers() {
+        assert_eq!(LineNumbers::None.width(10), 0);
+        assert_eq!(LineNumbers::Absolute.width(0), 4);
+        assert_eq!(LineNumbers::Absolute.width(999), 4);
+        assert_eq!(LineNumbers::Relative.width(12345), 6);
+
+        assert_eq!(LineNumbers::Absolute.label(4, 2), "5");
+        assert_eq!(LineNumbers::Relative.label(4, 2), "2");
+        assert_eq!(LineNumbers::Relative.label(2, 2), "0");
+        assert_eq!(LineNumbers::Hybrid.label(0, 2), "2");
+        assert_eq!(LineNumbers::Hybrid.label(2, 2), "3");
+    }
+}
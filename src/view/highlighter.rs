@@ -0,0 +1,206 @@
+use std::any::Any;
+
+use ratatui::text::Span;
+
+use super::theme::EditorTheme;
+use crate::{CommentTokens, Lines};
+
+/// A syntax highlighter that the [`EditorView`](crate::EditorView) renders
+/// the text with, see [`EditorView::highlighter`].
+///
+/// [`EditorView::highlighter`]: crate::EditorView::highlighter
+pub trait LineHighlighter {
+    /// Returns the highlighted spans of the line `row`, or `None` if the
+    /// line cannot be highlighted. The spans must hold the chars of the
+    /// line, without a line break.
+    ///
+    /// `theme` is the theme of the view. Highlighters that name their
+    /// highlights take their styles from it, see
+    /// [`EditorTheme::highlight_style`].
+    ///
+    /// `cache` is kept by the [`EditorState`](crate::EditorState) between
+    /// renders. It holds the rows that were edited since the last render
+    /// and any state the highlighter wants to keep.
+    fn highlight_line(
+        &self,
+        lines: &Lines,
+        row: usize,
+        theme: &EditorTheme,
+        cache: &mut HighlightCache,
+    ) -> Option<Vec<Span<'static>>>;
+
+    /// Returns the comment tokens of the highlighted language, used to
+    /// toggle comments unless they are set with
+    /// [`EditorState::set_comment_tokens`](crate::EditorState::set_comment_tokens).
+    fn comment_tokens(&self) -> Option<CommentTokens> {
+        None
+    }
+}
+
+/// The state of a [`LineHighlighter`] that is kept between renders.
+#[derive(Default)]
+pub struct HighlightCache {
+    /// The rows that were edited since the edits were taken last.
+    edits: Option<EditedRows>,
+    /// The number of lines after the recorded edits, to notice lines that
+    /// were replaced without recording an edit.
+    num_lines: Option<usize>,
+    /// The state of the highlighter.
+    state: Option<Box<dyn Any>>,
+}
+
+impl HighlightCache {
+    /// Returns the state of the highlighter. A new state is created with
+    /// `init` if there is none, or if it was created by another highlighter
+    /// type.
+    pub fn state<T: Any>(&mut self, init: impl FnOnce() -> T) -> &mut T {
+        if !self.state.as_ref().is_some_and(|state| state.is::<T>()) {
+            self.state = Some(Box::new(init()));
+        }
+        self.state
+            .as_mut()
+            .and_then(|state| state.downcast_mut::<T>())
+            .unwrap_or_else(|| unreachable!("the state is of type T"))
+    }
+
+    /// Returns the first row that was edited since the last call, if any.
+    pub fn take_edited_row(&mut self) -> Option<usize> {
+        self.take_edits().map(|edits| edits.start)
+    }
+
+    /// Returns the rows that were edited since the last call, if any.
+    pub fn take_edits(&mut self) -> Option<EditedRows> {
+        self.edits.take()
+    }
+
+    /// Records an edit that replaced `removed` rows at the row `row` with
+    /// `inserted` rows. Nothing is recorded until a highlighter created its
+    /// state.
+    pub(crate) fn edit(&mut self, row: usize, removed: usize, inserted: usize) {
+        if self.state.is_some() {
+            let edits = match self.edits {
+                Some(edits) => edits.merge(row, removed, inserted),
+                None => EditedRows {
+                    start: row,
+                    old_end: row + removed,
+                    new_end: row + inserted,
+                },
+            };
+            self.edits = Some(edits);
+        }
+        self.num_lines = self
+            .num_lines
+            .map(|num_lines| (num_lines + inserted).saturating_sub(removed));
+    }
+
+    /// Records an edit of all rows.
+    pub(crate) fn invalidate(&mut self) {
+        let num_lines = self.num_lines.unwrap_or_default();
+        self.edit(0, num_lines, num_lines);
+    }
+
+    /// Records an edit of all rows if the number of lines differs from the
+    /// recorded edits, e.g. after the lines were replaced directly.
+    pub(crate) fn sync(&mut self, num_lines: usize) {
+        if let Some(expected) = self.num_lines.filter(|&expected| expected != num_lines) {
+            self.edit(0, expected, num_lines);
+        }
+        self.num_lines = Some(num_lines);
+    }
+}
+
+/// The rows that were edited since the edits were taken last. The rows
+/// `start..old_end` of the text before the edits were replaced with the
+/// rows `start..new_end`, the rows around them are unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditedRows {
+    /// The first edited row.
+    pub start: usize,
+    /// The row behind the edited rows, before the edits.
+    pub old_end: usize,
+    /// The row behind the edited rows, after the edits.
+    pub new_end: usize,
+}
+
+impl EditedRows {
+    /// Extends the edited rows by an edit that replaced `removed` rows at
+    /// the row `row` of the edited text with `inserted` rows.
+    fn merge(self, row: usize, removed: usize, inserted: usize) -> Self {
+        let start = self.start.min(row);
+        let end = self.new_end.max(row + removed);
+        Self {
+            start,
+            old_end: (end + self.old_end).saturating_sub(self.new_end),
+            new_end: (end + inserted).saturating_sub(removed),
+        }
+    }
+}
+
+impl Clone for HighlightCache {
+    /// The state of the highlighter cannot be cloned, the clone starts
+    /// without one.
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for HighlightCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HighlightCache")
+            .field("edits", &self.edits)
+            .field("num_lines", &self.num_lines)
+            .field("has_state", &self.state.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_cache() {
+        let mut cache = HighlightCache::default();
+        cache.edit(3, 1, 1);
+        assert_eq!(cache.take_edited_row(), None);
+
+        *cache.state(|| 1_u8) += 1;
+        assert_eq!(*cache.state(|| 0_u8), 2);
+        cache.edit(3, 1, 1);
+        cache.edit(1, 1, 1);
+        assert_eq!(cache.take_edited_row(), Some(1));
+        assert_eq!(cache.take_edited_row(), None);
+
+        // Edits are merged into the rows they replaced.
+        cache.edit(2, 1, 3);
+        cache.edit(6, 2, 0);
+        cache.edit(1, 0, 1);
+        assert_eq!(
+            cache.take_edits(),
+            Some(EditedRows {
+                start: 1,
+                old_end: 6,
+                new_end: 7,
+            })
+        );
+
+        // Lines that were replaced without an edit invalidate all rows.
+        cache.sync(5);
+        cache.edit(2, 1, 3);
+        cache.sync(7);
+        assert_eq!(cache.take_edited_row(), Some(2));
+        cache.sync(6);
+        assert_eq!(
+            cache.take_edits(),
+            Some(EditedRows {
+                start: 0,
+                old_end: 7,
+                new_end: 6,
+            })
+        );
+
+        // A highlighter of another type replaces the state.
+        assert_eq!(cache.state(|| "new"), &"new");
+        assert_eq!(*cache.state(|| 0_u8), 0);
+    }
+}
@@ -1,11 +1,10 @@
 //! A collection of internal datatypes for rendering.
 //!
 //! TODO: Refactor.
-#[cfg(feature = "syntax-highlighting")]
-use crate::SyntaxHighlighter;
 use crate::{
-    helper::{char_width, span_width, split_str_at},
+    helper::{grapheme_end, grapheme_widths, graphemes, span_width, split_str_at},
     state::selection::Selection,
+    InlayHint,
 };
 use jagged::Index2;
 use ratatui::{style::Style, text::Span};
@@ -43,6 +42,10 @@ impl InternalSpan {
         let (start_col, end_col) = selection.get_selected_columns_in_row(row_index, spans_len)?;
         debug_assert!(end_col >= start_col, "{start_col} {end_col}");
 
+        // Grapheme clusters are selected as a whole.
+        let line: Vec<char> = spans.iter().flat_map(|span| span.content.chars()).collect();
+        let end_col = grapheme_end(&line, end_col).saturating_sub(1).max(end_col);
+
         Some(Self::split_spans(spans, start_col, end_col, style))
     }
 
@@ -187,9 +190,15 @@ pub(crate) fn into_spans_with_selections<'a>(
     let mut current_span = String::new();
     let mut previous_is_selected = false;
 
+    // Grapheme clusters are selected as a whole, by their first char.
+    let mut cluster_starts = vec![0; line.len()];
+    for range in graphemes(line) {
+        cluster_starts[range.clone()].fill(range.start);
+    }
+
     // Iterate over the line's characters, starting from the offset
     for (i, &ch) in line.iter().skip(col_skips).enumerate() {
-        let position = Index2::new(row_index, col_skips + i);
+        let position = Index2::new(row_index, cluster_starts[col_skips + i]);
 
         // Check if the current position is selected by any selection
         let current_is_selected = selections
@@ -213,18 +222,13 @@ pub(crate) fn into_spans_with_selections<'a>(
     spans
 }
 
-#[cfg(feature = "syntax-highlighting")]
 pub(crate) fn line_into_highlighted_spans_with_selections<'a>(
-    line: &[char],
+    mut internal_spans: Vec<InternalSpan>,
     selections: &[&Option<Selection>],
-    syntax_highligher: &SyntaxHighlighter,
     row_index: usize,
     col_skips: usize,
     highlight_style: &Style,
 ) -> Vec<Span<'a>> {
-    let line: String = line.iter().collect();
-    let mut internal_spans = syntax_highligher.highlight_line(&line);
-
     let selections = selections
         .iter()
         .filter_map(|selection| selection.as_ref().filter(|s| s.contains_row(row_index)));
@@ -244,6 +248,43 @@ pub(crate) fn line_into_highlighted_spans_with_selections<'a>(
     internal_spans.into_iter().map(Span::from).collect()
 }
 
+/// Inserts the text of inlay hints into the spans of a line. The spans
+/// hold the chars of the line starting at `col_skips`, and `hints` are
+/// the hints of the line that are not scrolled out of view.
+pub(crate) fn insert_inlay_hints<'a>(
+    spans: Vec<Span<'a>>,
+    hints: &[InlayHint],
+    col_skips: usize,
+    hint_style: &Style,
+) -> Vec<Span<'a>> {
+    if hints.is_empty() {
+        return spans;
+    }
+
+    let mut new_spans = Vec::with_capacity(spans.len() + 2 * hints.len());
+    let mut hints = hints.iter().peekable();
+    let mut col = col_skips;
+    for span in spans {
+        let mut current_span = String::new();
+        for ch in span.content.chars() {
+            while let Some(hint) = hints.next_if(|hint| hint.position.col <= col) {
+                if !current_span.is_empty() {
+                    new_spans.push(Span::styled(current_span.clone(), span.style));
+                    current_span.clear();
+                }
+                new_spans.push(Span::styled(hint.text.clone(), *hint_style));
+            }
+            current_span.push(ch);
+            col += 1;
+        }
+        new_spans.push(Span::styled(current_span, span.style));
+    }
+
+    // Hints behind the end of the line.
+    new_spans.extend(hints.map(|hint| Span::styled(hint.text.clone(), *hint_style)));
+    new_spans
+}
+
 /// Finds the position of a character within wrapped spans based on a given
 /// index position.
 ///
@@ -292,22 +333,10 @@ pub(super) fn find_position_in_wrapped_spans(
 }
 
 /// Returns the position of a char in a string taking into
-/// account unicode width.
+/// account unicode width and grapheme clusters.
 pub(super) fn unicode_width_position_in_spans(spans: &[Span], n: usize, tab_width: usize) -> usize {
-    let mut total_width = 0;
-    let mut chars_counted = 0;
-
-    for span in spans {
-        for ch in span.content.chars() {
-            if chars_counted >= n {
-                return total_width;
-            }
-            total_width += char_width(ch, tab_width);
-            chars_counted += 1;
-        }
-    }
-
-    total_width
+    let chars: Vec<char> = spans.iter().flat_map(|span| span.content.chars()).collect();
+    grapheme_widths(&chars, tab_width).iter().take(n).sum()
 }
 
 pub(crate) fn find_position_in_spans(spans: &[Span], char_pos: usize, tab_width: usize) -> Index2 {
@@ -325,7 +354,7 @@ fn count_characters_in_spans(spans: &[Span]) -> usize {
     spans.iter().map(|span| span.content.chars().count()).sum()
 }
 
-fn spans_width(spans: &[Span], tab_width: usize) -> usize {
+pub(super) fn spans_width(spans: &[Span], tab_width: usize) -> usize {
     spans
         .iter()
         .fold(0, |sum, span| sum + span_width(span, tab_width))
@@ -342,7 +371,7 @@ mod tests {
         // given
         let base = Style::default();
         let hightlighted = Style::default().red();
-        let line = "Hello".chars().into_iter().collect::<Vec<char>>();
+        let line = "Hello".chars().collect::<Vec<char>>();
 
         let selection = Some(Selection::new(Index2::new(0, 0), Index2::new(0, 2)));
         let selections = vec![&selection];
@@ -355,6 +384,23 @@ mod tests {
         assert_eq!(spans[1], Span::styled("lo", base));
     }
 
+    #[test]
+    fn test_internal_line_into_spans_with_grapheme_clusters() {
+        let base = Style::default();
+        let hightlighted = Style::default().red();
+        let line = "ae\u{301}b".chars().collect::<Vec<char>>();
+
+        // The accent is selected together with its base char.
+        let selection = Some(Selection::new(Index2::new(0, 0), Index2::new(0, 1)));
+        let spans = into_spans_with_selections(&line, &[&selection], 0, 0, &base, &hightlighted);
+
+        assert_eq!(spans[0], Span::styled("ae\u{301}", hightlighted));
+        assert_eq!(spans[1], Span::styled("b", base));
+
+        let spans = vec![Span::from("ae\u{301}b")];
+        assert_eq!(unicode_width_position_in_spans(&spans, 3, 4), 2);
+    }
+
     #[test]
     fn test_internal_span_split_spans() {
         // given
@@ -366,7 +412,7 @@ mod tests {
         ];
 
         // when
-        let new_spans = InternalSpan::split_spans(&spans, 1, 1, &hightlighted);
+        let new_spans = InternalSpan::split_spans(&spans, 1, 1, hightlighted);
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("H", base));
@@ -375,7 +421,7 @@ mod tests {
         assert_eq!(new_spans[3], InternalSpan::new("lo!", base));
 
         // when
-        let new_spans = InternalSpan::split_spans(&spans, 1, 2, &hightlighted);
+        let new_spans = InternalSpan::split_spans(&spans, 1, 2, hightlighted);
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("H", base));
@@ -383,7 +429,7 @@ mod tests {
         assert_eq!(new_spans[2], InternalSpan::new("lo!", base));
 
         // when
-        let new_spans = InternalSpan::split_spans(&spans, 1, 3, &hightlighted);
+        let new_spans = InternalSpan::split_spans(&spans, 1, 3, hightlighted);
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("H", base));
@@ -392,7 +438,7 @@ mod tests {
         assert_eq!(new_spans[3], InternalSpan::new("o!", base));
 
         // when
-        let new_spans = InternalSpan::split_spans(&spans, 1, 10, &hightlighted);
+        let new_spans = InternalSpan::split_spans(&spans, 1, 10, hightlighted);
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("H", base));
@@ -408,7 +454,7 @@ mod tests {
         let spans = vec![InternalSpan::new("Hell🙂!", base)];
 
         // when
-        let new_spans = InternalSpan::split_spans(&spans, 2, 4, &hightlighted);
+        let new_spans = InternalSpan::split_spans(&spans, 2, 4, hightlighted);
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("He", base));
@@ -466,7 +512,7 @@ mod tests {
         // when
         let selection = Selection::new(Index2::new(0, 1), Index2::new(0, 3));
         let new_spans =
-            InternalSpan::apply_selection(&spans, 0, &selection, &hightlighted).unwrap();
+            InternalSpan::apply_selection(&spans, 0, &selection, hightlighted).unwrap();
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("H", base));
@@ -488,7 +534,7 @@ mod tests {
         // when
         let selection = Selection::new(Index2::new(0, 3), Index2::new(0, 5));
         let new_spans =
-            InternalSpan::apply_selection(&spans, 0, &selection, &hightlighted).unwrap();
+            InternalSpan::apply_selection(&spans, 0, &selection, hightlighted).unwrap();
 
         // then
         assert_eq!(new_spans[0], InternalSpan::new("Hel", base));
@@ -496,6 +542,35 @@ mod tests {
         assert_eq!(new_spans[2], InternalSpan::new("!", hightlighted));
     }
 
+    #[test]
+    fn test_insert_inlay_hints() {
+        // given
+        let base = Style::default();
+        let hint_style = Style::default().red();
+        let spans = vec![Span::styled("ab", base), Span::styled("cd", base)];
+        let hints = vec![
+            InlayHint::new(Index2::new(0, 2), "1"),
+            InlayHint::new(Index2::new(0, 3), "2"),
+            InlayHint::new(Index2::new(0, 5), "3"),
+        ];
+
+        // when
+        let new_spans = insert_inlay_hints(spans, &hints, 1, &hint_style);
+
+        // then
+        assert_eq!(
+            new_spans,
+            vec![
+                Span::styled("a", base),
+                Span::styled("1", hint_style),
+                Span::styled("b", base),
+                Span::styled("2", hint_style),
+                Span::styled("cd", base),
+                Span::styled("3", hint_style),
+            ]
+        );
+    }
+
     #[test]
     fn test_unicode_width_position_in_spans() {
         let spans = vec![Span::from("a😀b"), Span::from("c😀d")];
@@ -1,129 +1,153 @@
-use std::borrow::Cow;
-
 use ratatui::text::Span;
 
-use crate::helper::{char_width, span_width, split_str_at};
+use crate::helper::{chars_width, grapheme_widths, split_str_at};
+
+/// Determines where overflowing lines are broken when wrapping is enabled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WrapMode {
+    /// Breaks lines at the last character that fits into a row.
+    #[default]
+    Char,
+    /// Breaks lines after whitespace or punctuation. Words that do not fit
+    /// into a row are broken at the last character that fits.
+    Word,
+}
+
+/// Options of wrapped lines.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct WrapOptions {
+    pub(crate) mode: WrapMode,
+    /// Whether continuation rows are indented like the line.
+    pub(crate) break_indent: bool,
+    /// A marker that is displayed in front of continuation rows.
+    pub(crate) show_break: String,
+}
+
+/// The chars of a line that are displayed in one row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WrappedRow {
+    /// The index of the first char.
+    pub(crate) start: usize,
+    /// The index behind the last char.
+    pub(crate) end: usize,
+    /// The width of the indentation and the showbreak marker in front of
+    /// the row.
+    pub(crate) indent: usize,
+}
 
 #[derive(Default)]
 pub(crate) struct LineWrapper;
 
 impl LineWrapper {
-    /// Splits a given line width into multiple smaller widths, ensuring each width
-    /// is no larger than the specified maximum width.
-    pub(crate) fn determine_split(line_width: usize, max_width: usize) -> Vec<usize> {
-        if line_width == 0 {
-            return vec![0];
+    /// Breaks a line into rows that are no wider than `max_width`. Every
+    /// row holds at least one char, and an empty line has one empty row.
+    pub(crate) fn layout(
+        line: &[char],
+        max_width: usize,
+        tab_width: usize,
+        options: &WrapOptions,
+    ) -> Vec<WrappedRow> {
+        let show_break: Vec<char> = options.show_break.chars().collect();
+        let mut prefix = chars_width(&show_break, tab_width);
+        if options.break_indent {
+            let indent = line.iter().take_while(|ch| ch.is_whitespace()).count();
+            prefix += chars_width(&line[..indent], tab_width);
         }
-
-        let mut remaining_width = line_width;
-        let mut split_widths = Vec::new();
-
-        while remaining_width > 0 {
-            let current_chunk = std::cmp::min(remaining_width, max_width);
-            split_widths.push(current_chunk);
-            remaining_width = remaining_width.saturating_sub(max_width);
+        // Continuation rows are not indented if there is no space left.
+        if prefix >= max_width {
+            prefix = 0;
         }
 
-        split_widths
-    }
-
-    pub(crate) fn wrap_line(line: &[char], max_width: usize, tab_width: usize) -> Vec<Vec<char>> {
-        let mut lines = Vec::new();
-        let mut line_width = 0;
-        let mut current_line = Vec::new();
-
-        for &ch in line {
-            let char_width = char_width(ch, tab_width);
-
-            if line_width + char_width > max_width {
-                lines.push(current_line.clone());
-                current_line.clear();
-                line_width = 0;
+        // Rows are only broken in front of grapheme clusters, the other
+        // chars of a cluster are zero wide.
+        let widths = grapheme_widths(line, tab_width);
+        let mut rows = Vec::new();
+        let (mut start, mut indent) = (0, 0);
+        let (mut width, mut line_break) = (0, None);
+        let mut i = 0;
+        while i < line.len() {
+            let char_width = widths[i];
+            if char_width > 0 && width + char_width > max_width.saturating_sub(indent) && i > start
+            {
+                let end = match line_break {
+                    Some(end) if options.mode == WrapMode::Word => end,
+                    _ => i,
+                };
+                rows.push(WrappedRow { start, end, indent });
+                // Continue after the break, which may be before `i`.
+                (start, indent) = (end, prefix);
+                (width, line_break) = (0, None);
+                i = end;
+                continue;
             }
-
-            current_line.push(ch);
-            line_width += char_width;
-        }
-
-        if !current_line.is_empty() {
-            lines.push(current_line);
+            width += char_width;
+            if is_break_char(line[i]) {
+                line_break = Some(i + 1);
+            }
+            i += 1;
         }
-
-        lines
+        rows.push(WrappedRow {
+            start,
+            end: line.len(),
+            indent,
+        });
+        rows
     }
 
-    pub(crate) fn wrap_spans(
-        spans: Vec<Span<'_>>,
-        max_width: usize,
-        tab_width: usize,
-    ) -> Vec<Vec<Span<'_>>> {
-        let mut wrapped_lines = Vec::new();
-        let mut current_line = Vec::new();
-        let mut current_line_width = 0;
-
-        for span in spans {
-            // If adding this span exceeds the max width, handle wrapping
-            if current_line_width + span_width(&span, tab_width) > max_width {
-                let mut remaining_span = span.clone();
-                let mut split_at = max_width - current_line_width;
-
-                while span_width(&remaining_span, tab_width) > split_at {
-                    let (fitting_part, rest) =
-                        Self::split_span_at(remaining_span, split_at, tab_width);
-                    current_line.push(fitting_part.clone());
-                    wrapped_lines.push(current_line.clone());
-
-                    // Prepare for the next line
-                    current_line.clear();
-                    remaining_span = rest;
-                    split_at = max_width;
+    /// Splits the spans of a line into the rows of its layout. The spans
+    /// must hold the chars of the line.
+    pub(crate) fn split_spans<'a>(spans: Vec<Span<'a>>, rows: &[WrappedRow]) -> Vec<Vec<Span<'a>>> {
+        let mut spans = spans.into_iter();
+        let mut current = spans.next();
+        let mut wrapped_lines = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut remaining = row.end - row.start;
+            let mut line = Vec::new();
+            while remaining > 0 {
+                let Some(span) = current.take() else {
+                    break;
+                };
+                let len = span.content.chars().count();
+                if len <= remaining {
+                    remaining -= len;
+                    line.push(span);
+                    current = spans.next();
+                } else {
+                    let (a, b) = split_str_at(&span.content, remaining);
+                    line.push(Span::styled(a, span.style));
+                    current = Some(Span::styled(b, span.style));
+                    remaining = 0;
                 }
-
-                // Add remaining part to the current line
-                current_line_width = span_width(&remaining_span, tab_width);
-                current_line.push(remaining_span);
-            } else {
-                // No wrapping needed, just add the span
-                current_line_width += span_width(&span, tab_width);
-                current_line.push(span);
             }
+            wrapped_lines.push(line);
         }
-
-        // Add any remaining content as the last line
-        if !current_line.is_empty() {
-            wrapped_lines.push(current_line);
-        }
-
         wrapped_lines
     }
+}
 
-    fn split_str_at(s: Cow<'_, str>, split_at: usize, tab_width: usize) -> (String, String) {
-        let mut current_width = 0;
-        for (i, ch) in s.chars().enumerate() {
-            current_width += char_width(ch, tab_width);
-            if current_width > split_at {
-                let (a, b) = split_str_at(s, i);
-                return (a.to_string(), b.to_string());
-            }
-        }
-
-        (s.to_string(), String::new())
-    }
-
-    fn split_span_at(span: Span, split_at: usize, tab_width: usize) -> (Span, Span) {
-        let (a, b) = Self::split_str_at(span.content, split_at, tab_width);
-        (Span::styled(a, span.style), Span::styled(b, span.style))
-    }
+/// Whether a line may be broken after `ch` in [`WrapMode::Word`].
+fn is_break_char(ch: char) -> bool {
+    ch.is_whitespace()
+        || matches!(
+            ch,
+            '!' | '@' | '*' | '-' | '+' | ';' | ':' | ',' | '.' | '/' | '?'
+        )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn wrap_spans(spans: Vec<Span<'_>>, max_width: usize) -> Vec<Vec<Span<'_>>> {
+        let line: Vec<char> = spans.iter().flat_map(|span| span.content.chars()).collect();
+        let rows = LineWrapper::layout(&line, max_width, 0, &WrapOptions::default());
+        LineWrapper::split_spans(spans, &rows)
+    }
+
     #[test]
-    fn test_wrap_spans() {
+    fn test_split_spans() {
         let spans = vec![Span::raw("Hello"), Span::raw("World")];
-        let wrapped_spans = LineWrapper::wrap_spans(spans, 3, 0);
+        let wrapped_spans = wrap_spans(spans, 3);
 
         assert_eq!(wrapped_spans[0], vec![Span::raw("Hel")]);
         assert_eq!(wrapped_spans[1], vec![Span::raw("lo"), Span::raw("W")]);
@@ -132,32 +156,80 @@ mod tests {
     }
 
     #[test]
-    fn test_wrap_spans_with_emoji() {
+    fn test_split_spans_with_emoji() {
         let spans = vec![Span::raw("Hell🙂!")];
-        let wrapped_spans = LineWrapper::wrap_spans(spans, 4, 0);
+        let wrapped_spans = wrap_spans(spans, 4);
 
         assert_eq!(wrapped_spans[0], vec![Span::raw("Hell")]);
         assert_eq!(wrapped_spans[1], vec![Span::raw("🙂!")]);
     }
 
-    #[test]
-    fn test_split_span_at_with_emoji() {
-        let span = Span::raw("🙂!");
-        let (left, right) = LineWrapper::split_span_at(span, 2, 0);
-
-        assert_eq!(left, Span::raw("🙂"));
-        assert_eq!(right, Span::raw("!"));
+    fn layout(line: &str, max_width: usize, options: &WrapOptions) -> Vec<String> {
+        let line: Vec<char> = line.chars().collect();
+        LineWrapper::layout(&line, max_width, 2, options)
+            .iter()
+            .map(|row| {
+                format!(
+                    "{}|{}",
+                    row.indent,
+                    String::from_iter(&line[row.start..row.end])
+                )
+            })
+            .collect()
     }
 
-    fn test_line_wrapper_determine_split() {
-        let line_widths = LineWrapper::determine_split(5, 3);
-
-        assert_eq!(line_widths[0], 3);
-        assert_eq!(line_widths[1], 2);
+    #[test]
+    fn test_layout_word_wrap() {
+        let options = WrapOptions {
+            mode: WrapMode::Word,
+            ..WrapOptions::default()
+        };
+        assert_eq!(
+            layout("hello big world", 10, &options),
+            vec!["0|hello big ", "0|world"]
+        );
+        assert_eq!(
+            layout("a-very-long-word", 8, &options),
+            vec!["0|a-very-", "0|long-", "0|word"]
+        );
+        assert_eq!(
+            layout("abcdefghij", 4, &options),
+            vec!["0|abcd", "0|efgh", "0|ij"]
+        );
+        assert_eq!(layout("", 4, &options), vec!["0|"]);
+    }
 
-        let line_widths = LineWrapper::determine_split(6, 3);
+    #[test]
+    fn test_layout_break_indent_and_show_break() {
+        let options = WrapOptions {
+            mode: WrapMode::Word,
+            break_indent: true,
+            show_break: "> ".to_string(),
+        };
+        assert_eq!(
+            layout("  one two three", 10, &options),
+            vec!["0|  one two ", "4|three"]
+        );
+
+        // The indentation is dropped if it leaves no space for text.
+        assert_eq!(
+            layout("      abcdef", 6, &options),
+            vec!["0|      ", "0|abcdef"]
+        );
+    }
 
-        assert_eq!(line_widths[0], 3);
-        assert_eq!(line_widths[1], 3);
+    #[test]
+    fn test_layout_grapheme_clusters() {
+        // Accents are zero wide and stay with their base char, the family
+        // is two cells wide and never broken.
+        let options = WrapOptions::default();
+        assert_eq!(
+            layout("ae\u{301}e\u{301}", 2, &options),
+            vec!["0|ae\u{301}", "0|e\u{301}"]
+        );
+        assert_eq!(
+            layout("a\u{1f468}\u{200d}\u{1f469}b", 3, &options),
+            vec!["0|a\u{1f468}\u{200d}\u{1f469}", "0|b"]
+        );
     }
 }
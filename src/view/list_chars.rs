@@ -0,0 +1,151 @@
+use crate::helper::char_width;
+
+/// Glyphs that make whitespace visible, like vim's `listchars`. Every
+/// glyph is optional, whitespace without a glyph is rendered as usual.
+/// Glyphs are drawn in the [`EditorTheme::whitespace_style`] and must be
+/// one cell wide, wider glyphs are ignored.
+///
+/// [`EditorTheme::whitespace_style`]: crate::EditorTheme::whitespace_style
+///
+/// # Example
+///
+/// ```
+/// use edtui::{EditorState, EditorView, ListChars};
+///
+/// let list_chars = ListChars::default().tab('→').trail('·').eol('¬');
+/// EditorView::new(&mut EditorState::default()).list_chars(list_chars);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListChars {
+    /// The glyph in the first cell of a tab.
+    pub tab: Option<char>,
+    /// The glyph of spaces at the end of a line.
+    pub trail: Option<char>,
+    /// The glyph of non-breaking spaces.
+    pub nbsp: Option<char>,
+    /// The glyph behind the end of a line.
+    pub eol: Option<char>,
+}
+
+impl ListChars {
+    /// Sets the glyph in the first cell of a tab.
+    #[must_use]
+    pub fn tab(mut self, glyph: char) -> Self {
+        self.tab = Some(glyph);
+        self
+    }
+
+    /// Sets the glyph of spaces at the end of a line.
+    #[must_use]
+    pub fn trail(mut self, glyph: char) -> Self {
+        self.trail = Some(glyph);
+        self
+    }
+
+    /// Sets the glyph of non-breaking spaces.
+    #[must_use]
+    pub fn nbsp(mut self, glyph: char) -> Self {
+        self.nbsp = Some(glyph);
+        self
+    }
+
+    /// Sets the glyph behind the end of a line.
+    #[must_use]
+    pub fn eol(mut self, glyph: char) -> Self {
+        self.eol = Some(glyph);
+        self
+    }
+
+    /// Returns whether any glyph is set.
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the columns of `line` that are drawn as a glyph, together
+    /// with the glyph. The column `line.len()` marks the end of the line.
+    pub(crate) fn glyphs(&self, line: &[char]) -> Vec<(usize, char)> {
+        let narrow = |glyph: Option<char>| glyph.filter(|&ch| char_width(ch, 1) == 1);
+        let (tab, trail, nbsp) = (narrow(self.tab), narrow(self.trail), narrow(self.nbsp));
+        let trail_start = line.len() - line.iter().rev().take_while(|&&ch| ch == ' ').count();
+        let mut glyphs: Vec<(usize, char)> = line
+            .iter()
+            .enumerate()
+            .filter_map(|(col, &ch)| {
+                let glyph = match ch {
+                    '\t' => tab,
+                    ' ' if col >= trail_start => trail,
+                    '\u{a0}' => nbsp,
+                    _ => None,
+                };
+                glyph.map(|glyph| (col, glyph))
+            })
+            .collect();
+        if let Some(eol) = narrow(self.eol) {
+            glyphs.push((line.len(), eol));
+        }
+        glyphs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Style},
+        widgets::Widget,
+    };
+
+    use super::*;
+    use crate::{state::selection::Selection, EditorState, EditorTheme, EditorView, Index2, Lines};
+
+    #[test]
+    fn test_glyphs() {
+        let list_chars = ListChars::default().tab('>').trail('-').nbsp('+').eol('$');
+        let line: Vec<char> = "\ta b\u{a0}c  ".chars().collect();
+        assert_eq!(
+            list_chars.glyphs(&line),
+            vec![(0, '>'), (4, '+'), (6, '-'), (7, '-'), (8, '$')]
+        );
+
+        let wide = ListChars::default().tab('＞').eol('$');
+        assert_eq!(wide.glyphs(&line), vec![(8, '$')]);
+        assert!(ListChars::default().is_empty());
+    }
+
+    #[test]
+    fn test_render_with_cursor_line_and_color_columns() {
+        let mut state = EditorState::new(Lines::from("a\tb \nxyz"));
+        state.cursor = Index2::new(0, 3);
+        state.selection = Some(Selection::new(Index2::new(1, 0), Index2::new(1, 1)));
+        let theme = EditorTheme::default()
+            .hide_status_line()
+            .cursor_line_style(Style::default().bg(Color::Blue))
+            .color_column_style(Style::default().bg(Color::Red));
+        let area = Rect::new(0, 0, 8, 2);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .theme(theme)
+            .cursor_line(true)
+            .color_columns([2])
+            .list_chars(ListChars::default().tab('>').trail('.').eol('$'))
+            .render(area, &mut buf);
+
+        let row = |y| (0..8).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "a> b.$  ");
+        assert_eq!(row(1), "xyz$    ");
+
+        assert_eq!(buf[(0, 0)].bg, Color::Blue);
+        assert_eq!(buf[(5, 0)].bg, Color::Blue);
+        assert_eq!(buf[(1, 0)].bg, Color::Red);
+        assert_eq!(
+            buf[(1, 1)].bg,
+            EditorTheme::default().selection_style.bg.unwrap()
+        );
+        assert_eq!(buf[(2, 1)].bg, EditorTheme::default().base.bg.unwrap());
+        assert_eq!(
+            buf[(5, 0)].fg,
+            EditorTheme::default().whitespace_style.fg.unwrap()
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use ratatui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+
+use super::theme::{BLACK, DARK_GRAY, GRAY};
+use crate::{helper::char_width, EditorState, Lines};
+
+/// The characters that are used to draw an [`EditorMinimap`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MinimapMarker {
+    /// Braille patterns, every cell covers two columns and four lines.
+    #[default]
+    Braille,
+    /// Half blocks, every cell covers one column and two lines.
+    HalfBlock,
+}
+
+impl MinimapMarker {
+    /// Returns the number of columns and lines that are covered by a cell.
+    fn cell_size(self) -> (usize, usize) {
+        match self {
+            Self::Braille => (2, 4),
+            Self::HalfBlock => (1, 2),
+        }
+    }
+
+    /// Returns the char of a cell. `dots[y][x]` is set if the text covered
+    /// by the dot is not blank.
+    fn symbol(self, dots: [[bool; 2]; 4]) -> char {
+        match self {
+            Self::Braille => {
+                // The bits of the dots of the braille patterns, by row and column.
+                const BITS: [[u32; 2]; 4] =
+                    [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+                let mut bits = 0;
+                for (y, row) in dots.iter().enumerate() {
+                    for (x, &dot) in row.iter().enumerate() {
+                        if dot {
+                            bits |= BITS[y][x];
+                        }
+                    }
+                }
+                char::from_u32(0x2800 + bits).unwrap_or(' ')
+            }
+            Self::HalfBlock => match (dots[0][0], dots[1][0]) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            },
+        }
+    }
+}
+
+/// The rows of the text displayed by a rendered minimap. Used to scroll
+/// the editor with the mouse.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MinimapArea {
+    /// The area of the minimap on the screen.
+    pub(crate) area: Rect,
+    /// The first displayed row of the text.
+    pub(crate) first_row: usize,
+    /// The number of rows of the text covered by a cell.
+    pub(crate) rows_per_cell: usize,
+}
+
+impl MinimapArea {
+    /// Returns the row of the text at the screen row `y`.
+    #[cfg(feature = "mouse-support")]
+    pub(crate) fn row_at(&self, y: u16) -> usize {
+        let y = y.saturating_sub(self.area.y) as usize;
+        self.first_row + y * self.rows_per_cell
+    }
+}
+
+/// A down-scaled overview of the text. The rows of the text that are shown
+/// in the editor are highlighted. The minimap is rendered separately from
+/// the [`EditorView`](crate::EditorView), after it.
+///
+/// If mouse support is enabled, clicking or dragging the minimap scrolls
+/// the editor.
+///
+/// ## Example
+///
+/// ```rust
+/// use edtui::{EditorMinimap, EditorState, EditorView};
+/// use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+///
+/// let mut state = EditorState::default();
+/// let mut buf = Buffer::empty(Rect::new(0, 0, 50, 10));
+/// EditorView::new(&mut state).render(Rect::new(0, 0, 40, 10), &mut buf);
+/// EditorMinimap::new(&mut state).render(Rect::new(40, 0, 10, 10), &mut buf);
+/// ```
+pub struct EditorMinimap<'a> {
+    state: &'a mut EditorState,
+    marker: MinimapMarker,
+    style: Style,
+    viewport_style: Style,
+}
+
+impl<'a> EditorMinimap<'a> {
+    /// Creates a new minimap of the text of `state`.
+    #[must_use]
+    pub fn new(state: &'a mut EditorState) -> Self {
+        Self {
+            state,
+            marker: MinimapMarker::default(),
+            style: Style::default().bg(BLACK).fg(GRAY),
+            viewport_style: Style::default().bg(DARK_GRAY),
+        }
+    }
+
+    /// Sets the characters that are used to draw the minimap.
+    #[must_use]
+    pub fn marker(mut self, marker: MinimapMarker) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    /// Sets the style of the minimap.
+    #[must_use]
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the style of the rows that are shown in the editor.
+    #[must_use]
+    pub fn viewport_style(mut self, style: Style) -> Self {
+        self.viewport_style = style;
+        self
+    }
+}
+
+impl Widget for EditorMinimap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, self.style);
+        let (cols_per_cell, rows_per_cell) = self.marker.cell_size();
+        let lines = &self.state.lines;
+        let view = &self.state.view;
+
+        // Scroll the minimap in proportion to the editor, such that the
+        // first and the last row of the text can be shown.
+        let capacity = area.height as usize * rows_per_cell;
+        let num_lines = lines.len();
+        let first_row = if num_lines <= capacity {
+            0
+        } else {
+            let scrollable = num_lines.saturating_sub(view.num_rows).max(1);
+            (view.viewport.y * (num_lines - capacity) / scrollable).min(num_lines - capacity)
+        };
+
+        let ink = ink_mask(lines, view.tab_width);
+        let shown = view.viewport.y..view.viewport.y + view.num_rows;
+        for y in 0..area.height {
+            let row = first_row + y as usize * rows_per_cell;
+            if row >= num_lines {
+                break;
+            }
+            let cell_rows = row..row + rows_per_cell;
+            if cell_rows.start < shown.end && shown.start < cell_rows.end {
+                let row_area = Rect::new(area.x, area.y + y, area.width, 1);
+                buf.set_style(row_area, self.viewport_style);
+            }
+            for x in 0..area.width {
+                let col = x as usize * cols_per_cell;
+                let mut dots = [[false; 2]; 4];
+                for (dy, dots) in dots.iter_mut().enumerate().take(rows_per_cell) {
+                    for (dx, dot) in dots.iter_mut().enumerate().take(cols_per_cell) {
+                        *dot = ink
+                            .get(row + dy)
+                            .and_then(|line| line.get(col + dx))
+                            .is_some_and(|&ink| ink);
+                    }
+                }
+                if let Some(cell) = buf.cell_mut((area.x + x, area.y + y)) {
+                    cell.set_char(self.marker.symbol(dots));
+                }
+            }
+        }
+
+        self.state.view.minimap = MinimapArea {
+            area,
+            first_row,
+            rows_per_cell,
+        };
+    }
+}
+
+/// Returns for every display column of every line whether it is not blank.
+fn ink_mask(lines: &Lines, tab_width: usize) -> Vec<Vec<bool>> {
+    lines
+        .iter_row()
+        .map(|line| {
+            line.iter()
+                .flat_map(|&ch| {
+                    let width = char_width(ch, tab_width);
+                    std::iter::repeat_n(!ch.is_whitespace(), width)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_symbols() {
+        let dots = [[true, false], [false, true], [false, false], [true, true]];
+        assert_eq!(MinimapMarker::Braille.symbol(dots), '⣑');
+        assert_eq!(MinimapMarker::HalfBlock.symbol(dots), '▀');
+    }
+
+    #[test]
+    fn test_render_minimap() {
+        let mut state = EditorState::new(Lines::from("ab\n  c\n\n\td\ne"));
+        state.view.num_rows = 2;
+        let area = Rect::new(0, 0, 3, 2);
+        let mut buf = Buffer::empty(area);
+        EditorMinimap::new(&mut state)
+            .marker(MinimapMarker::HalfBlock)
+            .render(area, &mut buf);
+
+        let row = |y| (0..3).map(|x| buf[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(0), "▀▀▄");
+        assert_eq!(row(1), "  ▄");
+        assert_eq!(buf[(0, 0)].bg, DARK_GRAY);
+        assert_eq!(buf[(0, 1)].bg, BLACK);
+        assert_eq!(state.view.minimap.first_row, 0);
+    }
+}
@@ -7,20 +7,21 @@ use ratatui::{
 
 use crate::helper::{rect_indent_y, replace_tabs_in_span};
 
-use super::internal::{find_position_in_spans, find_position_in_wrapped_spans};
+use super::internal::{find_position_in_spans, find_position_in_wrapped_spans, spans_width};
 
 /// An internal data type that represents a line for rendering.
 /// A vector of spans represents a line. Wrapped lines consist
-/// of an array of lines.
+/// of an array of lines and a prefix that is rendered in front of
+/// every line but the first.
 pub(super) enum RenderLine<'a> {
-    Wrapped(Vec<Vec<Span<'a>>>),
+    Wrapped(Vec<Vec<Span<'a>>>, Vec<Span<'a>>),
     Single(Vec<Span<'a>>),
 }
 
 impl RenderLine<'_> {
     pub(super) fn num_lines(&self) -> usize {
         match self {
-            RenderLine::Wrapped(lines) => lines.len().max(1),
+            RenderLine::Wrapped(lines, _) => lines.len().max(1),
             RenderLine::Single(_) => 1,
         }
     }
@@ -33,8 +34,19 @@ impl RenderLine<'_> {
         tab_width: usize,
     ) -> Position {
         let index2 = match self {
-            RenderLine::Wrapped(lines) => {
-                find_position_in_wrapped_spans(lines, data_col, area.width as usize, tab_width)
+            RenderLine::Wrapped(lines, prefix) => {
+                let prefix_width = spans_width(prefix, tab_width);
+                // The last line is shortened by the prefix if it is not the first line.
+                let max_width = match lines.len() {
+                    0 | 1 => area.width as usize,
+                    _ => (area.width as usize).saturating_sub(prefix_width),
+                };
+                let mut index =
+                    find_position_in_wrapped_spans(lines, data_col, max_width, tab_width);
+                if index.row > 0 {
+                    index.col += prefix_width;
+                }
+                index
             }
 
             RenderLine::Single(line) => find_position_in_spans(line, data_col, tab_width),
@@ -48,12 +60,15 @@ impl RenderLine<'_> {
 
     pub(super) fn render(self, mut area: Rect, buf: &mut Buffer, tab_width: usize) {
         match self {
-            RenderLine::Wrapped(lines) => {
-                for line in lines {
+            RenderLine::Wrapped(lines, prefix) => {
+                for (i, mut line) in lines.into_iter().enumerate() {
                     if area.height == 0 {
                         break;
                     }
 
+                    if i > 0 && !prefix.is_empty() {
+                        line.splice(0..0, prefix.iter().cloned());
+                    }
                     render_line(area, buf, line, tab_width);
                     area = rect_indent_y(area, 1);
                 }
@@ -0,0 +1,118 @@
+use ratatui::{buffer::Buffer, layout::Rect, style::Style};
+
+use super::theme::EditorTheme;
+use crate::EditorState;
+
+/// The glyph of a marker on the scrollbar track.
+const MARKER: char = '━';
+
+/// The vertical scrollbar right of the text. The thumb covers the rows of
+/// the viewport, markers show the rows of search matches, diagnostics and
+/// the selection.
+pub(super) struct Scrollbar<'a> {
+    pub(super) state: &'a EditorState,
+    pub(super) theme: &'a EditorTheme<'a>,
+    /// The number of rows displayed on the viewport.
+    pub(super) num_rows: usize,
+}
+
+impl Scrollbar<'_> {
+    pub(super) fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = area.height as usize;
+        let num_lines = self.state.lines.len().max(1);
+        if height == 0 {
+            return;
+        }
+        let cell = |row: usize| (row * height / num_lines).min(height - 1);
+
+        // The thumb is at least one cell high.
+        let offset = self.state.view.viewport.y;
+        let thumb_start = cell(offset);
+        let thumb_end = ((offset + self.num_rows) * height)
+            .div_ceil(num_lines)
+            .clamp(thumb_start + 1, height);
+
+        // Markers of more important rows replace earlier ones.
+        let mut markers: Vec<Option<Style>> = vec![None; height];
+        let mut mark = |row: usize, style: Style| markers[cell(row)] = Some(style);
+        if let Some(selection) = &self.state.selection {
+            for row in selection.start().row..=selection.end().row {
+                mark(row, self.theme.scrollbar_selection_style);
+            }
+        }
+        for position in &self.state.search.matches {
+            mark(position.row, self.theme.scrollbar_search_style);
+        }
+        let mut diagnostics: Vec<_> = self.state.diagnostics.iter().collect();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+        for diagnostic in diagnostics {
+            let color = self.theme.diagnostic_style(diagnostic.severity).fg;
+            mark(
+                diagnostic.start.row,
+                Style::default().fg(color.unwrap_or_default()),
+            );
+        }
+
+        for (i, marker) in markers.into_iter().enumerate() {
+            let y = area.y + i as u16;
+            let style = if (thumb_start..thumb_end).contains(&i) {
+                self.theme.scrollbar_thumb_style
+            } else {
+                self.theme.scrollbar_style
+            };
+            buf.set_string(area.x, y, " ", style);
+            if let Some(marker) = marker {
+                buf.set_string(area.x, y, MARKER.to_string(), marker);
+            }
+        }
+    }
+}
+
+/// Returns the row of the text at the screen row `y` of the scrollbar.
+#[cfg(feature = "mouse-support")]
+pub(crate) fn row_at(area: Rect, y: u16, num_lines: usize) -> usize {
+    let height = area.height.max(1) as usize;
+    let y = y.saturating_sub(area.y) as usize;
+    y * num_lines / height
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::widgets::Widget;
+
+    use super::*;
+    use crate::{Diagnostic, EditorView, Index2, Lines, Severity};
+
+    #[test]
+    fn test_render_scrollbar() {
+        let text: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut state = EditorState::new(Lines::from(text.join("\n")));
+        state.set_diagnostics(vec![Diagnostic::new(
+            Index2::new(15, 0),
+            Index2::new(15, 1),
+            Severity::Error,
+            "error",
+        )]);
+        state.cursor = Index2::new(9, 0);
+        let theme = EditorTheme::default().hide_status_line();
+        let error_color = theme.diagnostic_error_style.fg.unwrap();
+        let thumb_color = theme.scrollbar_thumb_style.bg.unwrap();
+        let area = Rect::new(0, 0, 6, 5);
+        let mut buf = Buffer::empty(area);
+        EditorView::new(&mut state)
+            .theme(theme)
+            .wrap(false)
+            .scrollbar(true)
+            .render(area, &mut buf);
+
+        // The viewport shows the rows 5 to 9 out of 20.
+        let track: Vec<_> = (0..5).map(|y| buf[(5, y)].bg).collect();
+        assert_eq!(track[1], thumb_color);
+        assert_eq!(track[2], thumb_color);
+        assert_ne!(track[0], thumb_color);
+        assert_ne!(track[3], thumb_color);
+        assert_eq!(buf[(5, 3)].symbol(), "━");
+        assert_eq!(buf[(5, 3)].fg, error_color);
+        assert_eq!(buf[(4, 0)].symbol(), " ");
+    }
+}
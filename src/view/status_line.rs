@@ -7,12 +7,25 @@ use ratatui::{
 use super::theme::{DARK_GRAY, WHITE};
 
 /// An optional status line for Editor.
+///
+/// With the "serde" feature, the styles and the alignment of the status
+/// line can be serialized and deserialized.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct EditorStatusLine {
     /// Displays the current editor mode in the status line.
+    #[cfg_attr(feature = "serde", serde(skip))]
     mode: String,
     /// The current search buffer. Shown only in search mode.
+    #[cfg_attr(feature = "serde", serde(skip))]
     search: Option<String>,
+    /// The current command line. Shown only in command mode.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    command: Option<String>,
     /// The style for the content of the sidebar
     style_text: Style,
     /// The style for the line itself
@@ -29,6 +42,7 @@ impl Default for EditorStatusLine {
         Self {
             mode: String::new(),
             search: None,
+            command: None,
             style_text: Style::default().fg(WHITE).bg(DARK_GRAY).bold(),
             style_line: Style::default().fg(WHITE).bg(DARK_GRAY),
             align_left: true,
@@ -75,6 +89,15 @@ impl EditorStatusLine {
         self
     }
 
+    /// Overwrite the command line content for the status line.
+    ///
+    /// This method is used internally to dynamically set the command line.
+    #[must_use]
+    pub fn command<S: Into<String>>(mut self, command: Option<S>) -> Self {
+        self.command = command.map(Into::into);
+        self
+    }
+
     /// Set the alignment for the status line content.
     ///
     /// Set to true to align content to the left, false to align to the right.
@@ -99,7 +122,11 @@ impl Widget for EditorStatusLine {
         let mode_paragraph = Paragraph::new(Line::from(Span::from(self.mode)))
             .alignment(Alignment::Center)
             .style(self.style_text);
-        let search_text = self.search.map_or(String::new(), |s| format!("/{s}"));
+        let search_text = match (self.search, self.command) {
+            (Some(search), _) => format!("/{search}"),
+            (None, Some(command)) => format!(":{command}"),
+            (None, None) => String::new(),
+        };
         let search_paragraph = Paragraph::new(Line::from(Span::from(search_text)))
             .alignment(Alignment::Left)
             .style(self.style_line);
@@ -1,25 +1,317 @@
+use std::{
+    ffi::OsStr,
+    fmt,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
 use crate::syntect::{
-    easy::HighlightLines,
-    highlighting::{Theme, ThemeSet},
-    parsing::{SyntaxReference, SyntaxSet},
+    highlighting::{FontStyle, HighlightIterator, HighlightState, Highlighter, Theme, ThemeSet},
+    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+    LoadingError,
 };
+use jagged::index::RowIndex;
 use once_cell::sync::Lazy;
-use ratatui::style::{Color, Style};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
 use syntect::dumps::from_binary;
 
-use super::internal::InternalSpan;
+use super::{
+    highlighter::{HighlightCache, LineHighlighter},
+    internal::InternalSpan,
+    theme::EditorTheme,
+};
+use crate::{CommentTokens, Lines};
 
-pub static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
-pub static THEME_SET: Lazy<ThemeSet> = Lazy::new(load_defaults);
+pub static SYNTAX_SET: Lazy<Arc<SyntaxSet>> =
+    Lazy::new(|| Arc::new(SyntaxSet::load_defaults_newlines()));
+pub static THEME_SET: Lazy<Arc<ThemeSet>> = Lazy::new(|| Arc::new(load_defaults()));
 
 pub fn load_defaults() -> ThemeSet {
     from_binary(include_bytes!("../../assets/default.themedump"))
 }
 
+/// An error that occurs when creating a [`SyntaxHighlighter`] or loading
+/// [`SyntaxAssets`].
+#[derive(Debug)]
+pub enum SyntaxHighlighterError {
+    /// There is no theme with the given name.
+    UnknownTheme(String),
+    /// There is no syntax for the given extension, file or first line.
+    UnknownSyntax(String),
+    /// A syntax or theme file could not be loaded.
+    Loading(LoadingError),
+}
+
+impl fmt::Display for SyntaxHighlighterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTheme(theme) => write!(f, "could not find theme {theme}"),
+            Self::UnknownSyntax(syntax) => write!(f, "could not find syntax for {syntax}"),
+            Self::Loading(err) => write!(f, "could not load syntax assets: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SyntaxHighlighterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Loading(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LoadingError> for SyntaxHighlighterError {
+    fn from(err: LoadingError) -> Self {
+        Self::Loading(err)
+    }
+}
+
+/// The syntaxes and themes a [`SyntaxHighlighter`] is created from.
+///
+/// The default assets hold the syntaxes bundled with syntect and the themes
+/// bundled with edtui. Extra `.sublime-syntax` and `.tmTheme` files can be
+/// loaded from disk at runtime.
+///
+/// ## Example
+///
+/// ```no_run
+/// use edtui::SyntaxAssets;
+///
+/// let mut assets = SyntaxAssets::default();
+/// assets.add_syntaxes_from_folder("syntaxes")?;
+/// assets.add_theme_from_file("themes/custom.tmTheme")?;
+/// let syntax_highlighter = assets.detect("custom", "build.zig", "")?;
+/// # Ok::<(), edtui::SyntaxHighlighterError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyntaxAssets {
+    syntaxes: Arc<SyntaxSet>,
+    themes: Arc<ThemeSet>,
+}
+
+impl Default for SyntaxAssets {
+    fn default() -> Self {
+        Self {
+            syntaxes: Arc::clone(&SYNTAX_SET),
+            themes: Arc::clone(&THEME_SET),
+        }
+    }
+}
+
+impl SyntaxAssets {
+    /// Adds the `.sublime-syntax` files in `folder` and its subfolders.
+    ///
+    /// # Errors
+    /// Returns an error if a file could not be read or parsed.
+    pub fn add_syntaxes_from_folder(
+        &mut self,
+        folder: impl AsRef<Path>,
+    ) -> Result<(), SyntaxHighlighterError> {
+        let mut builder = SyntaxSet::clone(&self.syntaxes).into_builder();
+        builder.add_from_folder(folder, true)?;
+        self.syntaxes = Arc::new(builder.build());
+        Ok(())
+    }
+
+    /// Adds the `.tmTheme` files in `folder` and its subfolders. A theme is
+    /// named by its file name without the extension.
+    ///
+    /// # Errors
+    /// Returns an error if a file could not be read or parsed.
+    pub fn add_themes_from_folder(
+        &mut self,
+        folder: impl AsRef<Path>,
+    ) -> Result<(), SyntaxHighlighterError> {
+        let mut themes = self.cloned_themes();
+        themes.add_from_folder(folder)?;
+        self.themes = Arc::new(themes);
+        Ok(())
+    }
+
+    /// Adds the `.tmTheme` file `path`. The theme is named by its file name
+    /// without the extension.
+    ///
+    /// # Errors
+    /// Returns an error if the file could not be read or parsed.
+    pub fn add_theme_from_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), SyntaxHighlighterError> {
+        let path = path.as_ref();
+        let theme = ThemeSet::get_theme(path)?;
+        let name = path.file_stem().and_then(OsStr::to_str).unwrap_or_default();
+        let mut themes = self.cloned_themes();
+        themes.themes.insert(name.to_string(), theme);
+        self.themes = Arc::new(themes);
+        Ok(())
+    }
+
+    /// Returns the names of the themes.
+    pub fn theme_names(&self) -> impl Iterator<Item = &str> {
+        self.themes.themes.keys().map(String::as_str)
+    }
+
+    /// Returns the names of the syntaxes.
+    pub fn syntax_names(&self) -> impl Iterator<Item = &str> {
+        self.syntaxes
+            .syntaxes()
+            .iter()
+            .map(|syntax| syntax.name.as_str())
+    }
+
+    /// Creates a [`SyntaxHighlighter`] with the theme `theme` and the syntax
+    /// of the extension `extension`.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such theme or syntax.
+    pub fn highlighter(
+        &self,
+        theme: &str,
+        extension: &str,
+    ) -> Result<SyntaxHighlighter, SyntaxHighlighterError> {
+        let syntax_ref = self
+            .syntaxes
+            .find_syntax_by_extension(extension)
+            .ok_or_else(|| SyntaxHighlighterError::UnknownSyntax(extension.to_string()))?;
+        Ok(SyntaxHighlighter {
+            theme: self.theme(theme)?,
+            theme_id: ThemeId::Named(Arc::clone(&self.themes), theme.to_string()),
+            syntax_ref: syntax_ref.clone(),
+            syntax_set: Arc::clone(&self.syntaxes),
+            color_depth: ColorDepth::default(),
+        })
+    }
+
+    /// Creates a [`SyntaxHighlighter`] with the theme `theme` and the syntax
+    /// of a file, detected by the name of the file, e.g. "Makefile", by its
+    /// extension or by its first line, e.g. a shebang like
+    /// `#!/usr/bin/env python3` or an editor modeline like
+    /// `# vim: set ft=ruby:` or `-*- mode: python -*-`.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such theme or if the syntax cannot
+    /// be detected.
+    pub fn detect(
+        &self,
+        theme: &str,
+        file_name: &str,
+        first_line: &str,
+    ) -> Result<SyntaxHighlighter, SyntaxHighlighterError> {
+        let syntax_ref = self
+            .detect_syntax(file_name, first_line)
+            .ok_or_else(|| SyntaxHighlighterError::UnknownSyntax(file_name.to_string()))?;
+        Ok(SyntaxHighlighter {
+            theme: self.theme(theme)?,
+            theme_id: ThemeId::Named(Arc::clone(&self.themes), theme.to_string()),
+            syntax_ref: syntax_ref.clone(),
+            syntax_set: Arc::clone(&self.syntaxes),
+            color_depth: ColorDepth::default(),
+        })
+    }
+
+    /// Returns a copy of the themes, the themes are shared between the
+    /// clones of the assets until they change.
+    fn cloned_themes(&self) -> ThemeSet {
+        let mut themes = ThemeSet::new();
+        themes.themes.clone_from(&self.themes.themes);
+        themes
+    }
+
+    fn theme(&self, theme: &str) -> Result<Theme, SyntaxHighlighterError> {
+        self.themes
+            .themes
+            .get(theme)
+            .cloned()
+            .ok_or_else(|| SyntaxHighlighterError::UnknownTheme(theme.to_string()))
+    }
+
+    fn detect_syntax(&self, file_name: &str, first_line: &str) -> Option<&SyntaxReference> {
+        let path = Path::new(file_name);
+        let name = path.file_name().and_then(OsStr::to_str);
+        let extension = path.extension().and_then(OsStr::to_str);
+        name.and_then(|name| self.syntaxes.find_syntax_by_extension(name))
+            .or_else(|| extension.and_then(|ext| self.syntaxes.find_syntax_by_extension(ext)))
+            .or_else(|| {
+                modeline_syntax(first_line)
+                    .and_then(|token| self.syntaxes.find_syntax_by_token(token))
+            })
+            .or_else(|| self.syntaxes.find_syntax_by_first_line(first_line))
+    }
+}
+
+/// Returns the syntax named in a vim modeline, e.g. `vim: set ft=ruby:`,
+/// or an emacs modeline, e.g. `-*- mode: python -*-`.
+fn modeline_syntax(line: &str) -> Option<&str> {
+    if let Some((_, rest)) = line.split_once("-*-") {
+        let (vars, _) = rest.split_once("-*-")?;
+        return vars.split(';').find_map(|var| match var.split_once(':') {
+            Some((key, value)) => key
+                .trim()
+                .eq_ignore_ascii_case("mode")
+                .then(|| value.trim()),
+            None if !vars.contains(':') => Some(var.trim()),
+            None => None,
+        });
+    }
+
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|(i, _)| *i == 0 || line[..*i].ends_with(char::is_whitespace))
+            .map(|(i, _)| i + marker.len())
+    })?;
+    line[start..]
+        .split(|ch: char| ch == ':' || ch.is_whitespace())
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then_some(value)
+        })
+}
+
+/// Identifies the theme of a [`SyntaxHighlighter`], such that the
+/// highlighted lines are kept without comparing the theme itself.
+#[derive(Debug, Clone)]
+enum ThemeId {
+    /// A theme of a theme set, by its name.
+    Named(Arc<ThemeSet>, String),
+    /// A custom theme, numbered in the order the themes were set.
+    Custom(u64),
+}
+
+impl ThemeId {
+    /// Returns the id of a new custom theme.
+    fn custom() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self::Custom(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl PartialEq for ThemeId {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Named(set, name), Self::Named(other_set, other_name)) => {
+                Arc::ptr_eq(set, other_set) && name == other_name
+            }
+            (Self::Custom(id), Self::Custom(other_id)) => id == other_id,
+            _ => false,
+        }
+    }
+}
+
 /// Syntax highlighter settings including theme and syntax.
+#[derive(Clone)]
 pub struct SyntaxHighlighter {
     theme: Theme,
+    theme_id: ThemeId,
     syntax_ref: SyntaxReference,
+    syntax_set: Arc<SyntaxSet>,
+    color_depth: ColorDepth,
 }
 
 impl SyntaxHighlighter {
@@ -29,7 +321,9 @@ impl SyntaxHighlighter {
     /// Syntax highlighting is currently highly experimental, and there might be breaking
     /// changes in the future.
     ///
-    /// See [`Self::theme`] for a list of available themes.
+    /// See [`Self::theme`] for a list of available themes. Use [`Self::try_new`]
+    /// to handle unknown themes and extensions, and [`SyntaxAssets`] to detect the
+    /// syntax of a file or to load syntaxes and themes from disk.
     ///
     /// # Panics
     /// - Could not find `theme` in syntect.
@@ -44,28 +338,37 @@ impl SyntaxHighlighter {
     /// ```
     #[must_use]
     pub fn new(theme: &str, extension: &str) -> Self {
-        let theme = THEME_SET
-            .themes
-            .get(theme)
-            .unwrap_or_else(|| panic!("Could not find theme {theme}"))
-            .clone();
-        let syntax_ref = SYNTAX_SET
-            .find_syntax_by_extension(extension)
-            .unwrap_or_else(|| panic!("Could not find extension {extension}"))
-            .clone();
+        Self::try_new(theme, extension).unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        Self { theme, syntax_ref }
+    /// Creates a new [`SyntaxHighlighter`] with a given theme and an extension,
+    /// like [`Self::new`].
+    ///
+    /// # Errors
+    /// Returns an error if there is no such theme or extension.
+    pub fn try_new(theme: &str, extension: &str) -> Result<Self, SyntaxHighlighterError> {
+        SyntaxAssets::default().highlighter(theme, extension)
     }
 
     /// Set a custom theme. If you would like to use a predefined
-    /// theme use [`theme_by_name`].
+    /// theme use [`Self::theme`].
     #[must_use]
     pub fn custom_theme(mut self, theme: Theme) -> Self {
         self.theme = theme;
+        self.theme_id = ThemeId::custom();
         self
     }
 
-    /// Set a theme by name, e.g. , e.g. "base16-ocean.dark".
+    /// Set the colours the terminal can display. The colours of the theme
+    /// are quantised to the nearest colour of the palette. Defaults to
+    /// [`ColorDepth::TrueColor`].
+    #[must_use]
+    pub fn color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = color_depth;
+        self
+    }
+
+    /// Set a theme by name, e.g. "base16-ocean.dark".
     ///
     /// # Panics
     /// - Could not find `theme` in syntect.
@@ -115,50 +418,594 @@ impl SyntaxHighlighter {
     /// "`visual-studio-dark`"
     /// "`zenburn`"
     #[must_use]
-    pub fn theme(mut self, theme: &str) -> Self {
-        let theme_set = ThemeSet::load_defaults();
-        let theme = theme_set
+    pub fn theme(self, theme: &str) -> Self {
+        self.try_theme(theme).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Set a theme by name, like [`Self::theme`].
+    ///
+    /// # Errors
+    /// Returns an error if there is no such theme.
+    pub fn try_theme(mut self, theme: &str) -> Result<Self, SyntaxHighlighterError> {
+        self.theme = THEME_SET
             .themes
             .get(theme)
-            .unwrap_or_else(|| panic!("Could not find theme {theme}"));
-        self.theme = theme.clone();
-        self
+            .ok_or_else(|| SyntaxHighlighterError::UnknownTheme(theme.to_string()))?
+            .clone();
+        self.theme_id = ThemeId::Named(Arc::clone(&THEME_SET), theme.to_string());
+        Ok(self)
     }
 
     /// Set the active extension for syntax highlighting, e.g. "json".
     ///
     /// # Panics
-    /// - Could not find `theme` in syntect.
     /// - Could not find `extension` in syntect.
     #[must_use]
-    pub(crate) fn extension(mut self, extension: &str) -> Self {
-        let syntax_ref = SYNTAX_SET
+    pub fn extension(self, extension: &str) -> Self {
+        self.try_extension(extension)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Set the active extension for syntax highlighting, like [`Self::extension`].
+    ///
+    /// # Errors
+    /// Returns an error if there is no syntax for the extension.
+    pub fn try_extension(mut self, extension: &str) -> Result<Self, SyntaxHighlighterError> {
+        self.syntax_ref = self
+            .syntax_set
             .find_syntax_by_extension(extension)
-            .unwrap_or_else(|| panic!("Could not find extension {extension}"));
-        self.syntax_ref = syntax_ref.clone();
-        self
+            .ok_or_else(|| SyntaxHighlighterError::UnknownSyntax(extension.to_string()))?
+            .clone();
+        Ok(self)
+    }
+
+    /// Returns the name of the syntax, e.g. "Rust".
+    #[must_use]
+    pub fn syntax_name(&self) -> &str {
+        &self.syntax_ref.name
     }
 }
 
 impl SyntaxHighlighter {
-    pub(super) fn highlight_line(&self, line: &str) -> Vec<InternalSpan> {
-        // Highlight lines
-        let mut highlight_lines = HighlightLines::new(&self.syntax_ref, &self.theme);
-        let highlighted_line = highlight_lines.highlight_line(line, &SYNTAX_SET).unwrap();
+    /// Returns the comment tokens of the syntax, as defined by the
+    /// `TM_COMMENT_START` and `TM_COMMENT_END` metadata.
+    #[must_use]
+    pub fn comment_tokens(&self) -> CommentTokens {
+        let metadata = self
+            .syntax_set
+            .metadata()
+            .metadata_for_scope(&[self.syntax_ref.scope]);
+        CommentTokens {
+            line: metadata
+                .line_comment()
+                .map(|token| token.trim().to_string()),
+            block: metadata
+                .block_comment()
+                .map(|(start, end)| (start.trim().to_string(), end.trim().to_string())),
+        }
+    }
 
-        // Convert the highlighted lines into spans
+    /// Highlights a line, starting from the parser and highlighter state
+    /// at the end of the previous line. The states are advanced to the end
+    /// of the line.
+    fn highlight_next_line(
+        &self,
+        line: &[char],
+        parse_state: &mut ParseState,
+        highlight_state: &mut HighlightState,
+    ) -> Vec<InternalSpan> {
+        // The syntaxes are loaded for lines that end with a newline.
+        let mut text: String = line.iter().collect();
+        text.push('\n');
+        let ops = parse_state
+            .parse_line(&text, &self.syntax_set)
+            .unwrap_or_default();
+        let highlighter = Highlighter::new(&self.theme);
+        let default_background = highlighter.get_default().background;
+
+        // Convert the highlighted ranges into spans
         let mut spans = Vec::new();
-        for &(style, text) in &highlighted_line {
+        for (style, text) in HighlightIterator::new(highlight_state, &ops, &text, &highlighter) {
+            let text = text.strip_suffix('\n').unwrap_or(text);
+            if text.is_empty() {
+                continue;
+            }
             spans.push(InternalSpan::new(
-                text.to_string(),
-                &Style::default().fg(Color::Rgb(
-                    style.foreground.r,
-                    style.foreground.g,
-                    style.foreground.b,
-                )),
+                text,
+                &self.style(style, default_background),
             ));
         }
 
         spans
     }
+
+    /// Converts a syntect style into a ratatui style. The background is
+    /// only set where it differs from the background of the theme, such
+    /// that the text is drawn on the background of the editor.
+    fn style(
+        &self,
+        style: syntect::highlighting::Style,
+        default_background: syntect::highlighting::Color,
+    ) -> Style {
+        let color =
+            |color: syntect::highlighting::Color| self.color_depth.color(color.r, color.g, color.b);
+        let mut result = Style::default().fg(color(style.foreground));
+        if style.background != default_background && style.background.a > 0 {
+            result = result.bg(color(style.background));
+        }
+        for (font_style, modifier) in [
+            (FontStyle::BOLD, Modifier::BOLD),
+            (FontStyle::ITALIC, Modifier::ITALIC),
+            (FontStyle::UNDERLINE, Modifier::UNDERLINED),
+        ] {
+            if style.font_style.contains(font_style) {
+                result = result.add_modifier(modifier);
+            }
+        }
+        result
+    }
+}
+
+/// The colours a terminal can display.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorDepth {
+    /// 24-bit RGB colours.
+    #[default]
+    TrueColor,
+    /// The 256 colour palette of xterm. Only the colour cube and the gray
+    /// ramp are used, as terminals often redefine the first 16 colours.
+    Ansi256,
+    /// The 16 basic ANSI colours.
+    Ansi16,
+}
+
+/// The 16 basic ANSI colours, with their default RGB values in xterm.
+const ANSI16: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::Red, [205, 0, 0]),
+    (Color::Green, [0, 205, 0]),
+    (Color::Yellow, [205, 205, 0]),
+    (Color::Blue, [0, 0, 238]),
+    (Color::Magenta, [205, 0, 205]),
+    (Color::Cyan, [0, 205, 205]),
+    (Color::Gray, [229, 229, 229]),
+    (Color::DarkGray, [127, 127, 127]),
+    (Color::LightRed, [255, 0, 0]),
+    (Color::LightGreen, [0, 255, 0]),
+    (Color::LightYellow, [255, 255, 0]),
+    (Color::LightBlue, [92, 92, 255]),
+    (Color::LightMagenta, [255, 0, 255]),
+    (Color::LightCyan, [0, 255, 255]),
+    (Color::White, [255, 255, 255]),
+];
+
+/// The levels of a channel in the 6x6x6 colour cube of the 256 colours.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Returns the colour of this depth that is nearest to an RGB colour.
+    #[must_use]
+    pub fn color(self, r: u8, g: u8, b: u8) -> Color {
+        let rgb = [r, g, b];
+        match self {
+            Self::TrueColor => Color::Rgb(r, g, b),
+            Self::Ansi256 => {
+                let cube = rgb.map(|channel| nearest_level(channel, &CUBE_LEVELS));
+                let cube_index = 16 + 36 * cube[0] + 6 * cube[1] + cube[2];
+                let cube_rgb = cube.map(|level| CUBE_LEVELS[level]);
+
+                // The gray ramp runs from 8 to 238 in steps of 10.
+                let average = rgb.iter().map(|&channel| u32::from(channel)).sum::<u32>() / 3;
+                let gray = (average.saturating_sub(3) / 10).min(23) as u8;
+                let gray_level = 8 + 10 * gray;
+
+                if distance(rgb, [gray_level; 3]) < distance(rgb, cube_rgb) {
+                    Color::Indexed(232 + gray)
+                } else {
+                    Color::Indexed(cube_index as u8)
+                }
+            }
+            Self::Ansi16 => ANSI16
+                .iter()
+                .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+                .map_or(Color::Reset, |(color, _)| *color),
+        }
+    }
+}
+
+/// Returns the index of the level that is nearest to `channel`.
+fn nearest_level(channel: u8, levels: &[u8]) -> usize {
+    (0..levels.len())
+        .min_by_key(|&i| channel.abs_diff(levels[i]))
+        .unwrap_or_default()
+}
+
+/// Returns the squared distance between two RGB colours.
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// The highlighted lines of a text. Highlighting a line depends on the
+/// lines in front of it, e.g. on an open block comment. The parser and
+/// highlighter state at the start of every highlighted line is kept, such
+/// that lines are only highlighted again after they or a line in front of
+/// them were edited. Lines are highlighted up to the last rendered line.
+#[derive(Debug, Default, Clone)]
+struct LineStates {
+    /// The syntax, the theme and the colour depth the lines were
+    /// highlighted with.
+    key: Option<(Arc<SyntaxSet>, String, ThemeId, ColorDepth)>,
+    /// The parser and highlighter state at the start of every highlighted
+    /// line and of the line behind them.
+    checkpoints: Vec<(ParseState, HighlightState)>,
+    /// The spans of the highlighted lines.
+    spans: Vec<Vec<InternalSpan>>,
+}
+
+impl LineStates {
+    /// Discards the highlighting of the line `row` and the lines behind it.
+    fn invalidate(&mut self, row: usize) {
+        self.spans.truncate(row);
+        self.checkpoints.truncate(row + 1);
+    }
+
+    /// Returns the spans of the line `row`, highlighting the lines in front
+    /// of it that were not highlighted yet.
+    fn highlight(
+        &mut self,
+        syntax: &SyntaxHighlighter,
+        lines: &Lines,
+        row: usize,
+    ) -> Option<&[InternalSpan]> {
+        let is_current =
+            self.key
+                .as_ref()
+                .is_some_and(|(syntax_set, name, theme_id, color_depth)| {
+                    Arc::ptr_eq(syntax_set, &syntax.syntax_set)
+                        && *name == syntax.syntax_ref.name
+                        && *theme_id == syntax.theme_id
+                        && *color_depth == syntax.color_depth
+                });
+        if !is_current {
+            *self = Self {
+                key: Some((
+                    Arc::clone(&syntax.syntax_set),
+                    syntax.syntax_ref.name.clone(),
+                    syntax.theme_id.clone(),
+                    syntax.color_depth,
+                )),
+                ..Self::default()
+            };
+        }
+        if self.checkpoints.is_empty() {
+            let highlighter = Highlighter::new(&syntax.theme);
+            self.checkpoints.push((
+                ParseState::new(&syntax.syntax_ref),
+                HighlightState::new(&highlighter, ScopeStack::new()),
+            ));
+        }
+
+        while self.spans.len() <= row {
+            let line = lines.get(RowIndex::new(self.spans.len()))?;
+            let (mut parse_state, mut highlight_state) = self.checkpoints[self.spans.len()].clone();
+            let spans = syntax.highlight_next_line(line, &mut parse_state, &mut highlight_state);
+            self.spans.push(spans);
+            self.checkpoints.push((parse_state, highlight_state));
+        }
+        self.spans.get(row).map(Vec::as_slice)
+    }
+}
+
+impl LineHighlighter for SyntaxHighlighter {
+    fn highlight_line(
+        &self,
+        lines: &Lines,
+        row: usize,
+        _theme: &EditorTheme,
+        cache: &mut HighlightCache,
+    ) -> Option<Vec<Span<'static>>> {
+        let edited_row = cache.take_edited_row();
+        let states = cache.state(LineStates::default);
+        if let Some(edited_row) = edited_row {
+            states.invalidate(edited_row);
+        }
+        let spans = states.highlight(self, lines, row)?;
+        Some(spans.iter().cloned().map(Span::from).collect())
+    }
+
+    fn comment_tokens(&self) -> Option<CommentTokens> {
+        Some(SyntaxHighlighter::comment_tokens(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    use super::*;
+    use crate::{actions::Execute, actions::RemoveChar, EditorState, EditorView};
+
+    const SYNTAX: &str = "%YAML 1.2
+---
+name: Greeting
+file_extensions: [greet]
+scope: source.greet
+contexts:
+  main:
+    - match: hello
+      scope: keyword.greet
+";
+
+    const THEME: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Plain</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>foreground</key>
+                <string>#FFFFFF</string>
+            </dict>
+        </dict>
+        <dict>
+            <key>scope</key>
+            <string>keyword</string>
+            <key>settings</key>
+            <dict>
+                <key>foreground</key>
+                <string>#FF0000</string>
+                <key>background</key>
+                <string>#000080</string>
+                <key>fontStyle</key>
+                <string>bold italic underline</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn test_try_new() {
+        assert!(SyntaxHighlighter::try_new("dracula", "rs").is_ok());
+        assert!(matches!(
+            SyntaxHighlighter::try_new("unknown", "rs"),
+            Err(SyntaxHighlighterError::UnknownTheme(theme)) if theme == "unknown"
+        ));
+        assert!(matches!(
+            SyntaxHighlighter::try_new("dracula", "unknown"),
+            Err(SyntaxHighlighterError::UnknownSyntax(_))
+        ));
+
+        let syntax = SyntaxHighlighter::new("dracula", "rs");
+        assert!(syntax.clone().try_theme("nord").is_ok());
+        assert!(syntax.clone().try_theme("unknown").is_err());
+        assert_eq!(syntax.extension("py").syntax_name(), "Python");
+    }
+
+    #[test]
+    fn test_detect_syntax() {
+        let assets = SyntaxAssets::default();
+        let detect = |file_name, first_line| {
+            assets
+                .detect("dracula", file_name, first_line)
+                .map(|syntax| syntax.syntax_name().to_string())
+                .ok()
+        };
+        assert_eq!(detect("src/main.rs", "").as_deref(), Some("Rust"));
+        assert_eq!(detect("Makefile", "").as_deref(), Some("Makefile"));
+        assert_eq!(
+            detect("run", "#!/usr/bin/env python3").as_deref(),
+            Some("Python")
+        );
+        assert_eq!(
+            detect("run", "#!/bin/bash").as_deref(),
+            Some("Bourne Again Shell (bash)")
+        );
+        assert_eq!(
+            detect("notes", "# vim: set ft=ruby:").as_deref(),
+            Some("Ruby")
+        );
+        assert_eq!(
+            detect("notes", "// -*- mode: rust -*-").as_deref(),
+            Some("Rust")
+        );
+        assert_eq!(detect("notes", "hello"), None);
+    }
+
+    #[test]
+    fn test_modeline_syntax() {
+        assert_eq!(modeline_syntax("# vim: set ft=ruby:"), Some("ruby"));
+        assert_eq!(modeline_syntax("/* vim: filetype=c sw=4 */"), Some("c"));
+        assert_eq!(
+            modeline_syntax("; -*- mode: lisp; tab-width: 2 -*-"),
+            Some("lisp")
+        );
+        assert_eq!(modeline_syntax("# -*- python -*-"), Some("python"));
+        assert_eq!(modeline_syntax("# -*- coding: utf-8 -*-"), None);
+        assert_eq!(modeline_syntax("let envim: ft=x"), None);
+    }
+
+    #[test]
+    fn test_load_assets_from_disk() {
+        let dir = std::env::temp_dir().join(format!("edtui-syntax-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("greeting.sublime-syntax"), SYNTAX).unwrap();
+        std::fs::write(dir.join("plain.tmTheme"), THEME).unwrap();
+
+        let mut assets = SyntaxAssets::default();
+        assets.add_syntaxes_from_folder(&dir).unwrap();
+        assets
+            .add_theme_from_file(dir.join("plain.tmTheme"))
+            .unwrap();
+        let result = assets.add_theme_from_file(dir.join("missing.tmTheme"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(SyntaxHighlighterError::Loading(_))));
+
+        assert!(assets.syntax_names().any(|name| name == "Greeting"));
+        assert!(assets.theme_names().any(|name| name == "plain"));
+        assert!(SyntaxAssets::default()
+            .highlighter("plain", "greet")
+            .is_err());
+
+        let syntax = assets.highlighter("plain", "greet").unwrap();
+        let lines = Lines::from("hello world");
+        let spans = syntax
+            .highlight_line(
+                &lines,
+                0,
+                &EditorTheme::default(),
+                &mut HighlightCache::default(),
+            )
+            .unwrap();
+        assert_eq!(spans[0].content, "hello");
+        assert_eq!(
+            spans[0].style,
+            Style::default()
+                .fg(Color::Rgb(255, 0, 0))
+                .bg(Color::Rgb(0, 0, 128))
+                .add_modifier(Modifier::BOLD | Modifier::ITALIC | Modifier::UNDERLINED)
+        );
+        // The background of the theme is left to the editor.
+        assert_eq!(
+            spans[1].style,
+            Style::default().fg(Color::Rgb(255, 255, 255))
+        );
+
+        let syntax = syntax.color_depth(ColorDepth::Ansi16);
+        let spans = syntax
+            .highlight_line(
+                &lines,
+                0,
+                &EditorTheme::default(),
+                &mut HighlightCache::default(),
+            )
+            .unwrap();
+        assert_eq!(spans[0].style.fg, Some(Color::LightRed));
+        assert_eq!(spans[0].style.bg, Some(Color::Blue));
+        assert_eq!(spans[1].style.fg, Some(Color::White));
+    }
+
+    #[test]
+    fn test_color_depth() {
+        assert_eq!(ColorDepth::TrueColor.color(1, 2, 3), Color::Rgb(1, 2, 3));
+
+        assert_eq!(ColorDepth::Ansi256.color(255, 0, 0), Color::Indexed(196));
+        assert_eq!(ColorDepth::Ansi256.color(95, 135, 175), Color::Indexed(67));
+        assert_eq!(
+            ColorDepth::Ansi256.color(100, 100, 100),
+            Color::Indexed(241)
+        );
+        assert_eq!(ColorDepth::Ansi256.color(0, 0, 0), Color::Indexed(16));
+        assert_eq!(
+            ColorDepth::Ansi256.color(255, 255, 255),
+            Color::Indexed(231)
+        );
+
+        assert_eq!(ColorDepth::Ansi16.color(250, 10, 20), Color::LightRed);
+        assert_eq!(ColorDepth::Ansi16.color(40, 42, 54), Color::Black);
+        assert_eq!(ColorDepth::Ansi16.color(140, 140, 140), Color::DarkGray);
+        assert_eq!(ColorDepth::Ansi16.color(80, 250, 80), Color::LightGreen);
+    }
+
+    #[test]
+    fn test_comment_tokens() {
+        let tokens = SyntaxHighlighter::new("dracula", "rs").comment_tokens();
+        assert_eq!(tokens.line.as_deref(), Some("//"));
+
+        let tokens = SyntaxHighlighter::new("dracula", "html").comment_tokens();
+        assert_eq!(tokens.line, None);
+        assert_eq!(tokens.block, Some(("<!--".to_string(), "-->".to_string())));
+    }
+
+    #[test]
+    fn test_highlight_multi_line_constructs() {
+        let syntax = SyntaxHighlighter::new("dracula", "rs");
+        let mut lines = Lines::from("/* a\nb */\nlet c = 1;");
+        let mut cache = LineStates::default();
+        let style = |cache: &mut LineStates, lines: &Lines, row| {
+            cache.highlight(&syntax, lines, row).unwrap()[0].style
+        };
+
+        // The second line continues the block comment of the first line.
+        let comment = style(&mut cache, &lines, 0);
+        assert_eq!(style(&mut cache, &lines, 1), comment);
+        assert_ne!(style(&mut cache, &lines, 2), comment);
+        assert_eq!(cache.checkpoints.len(), 4);
+
+        // Removing the start of the comment, the lines behind it are
+        // highlighted again.
+        lines = Lines::from("a\nb */\nlet c = 1;");
+        cache.invalidate(0);
+        assert_eq!(cache.spans.len(), 0);
+        assert_ne!(style(&mut cache, &lines, 1), comment);
+        assert_eq!(cache.spans.len(), 2);
+        assert!(cache.highlight(&syntax, &lines, 3).is_none());
+    }
+
+    #[test]
+    fn test_render_invalidates_edited_lines() {
+        let mut state = EditorState::new(Lines::from("/* a\nb */"));
+        let area = Rect::new(0, 0, 10, 3);
+        let render = |state: &mut EditorState| {
+            let mut buf = Buffer::empty(area);
+            EditorView::new(state)
+                .syntax_highlighter(Some(SyntaxHighlighter::new("dracula", "rs")))
+                .render(area, &mut buf);
+            (buf[(2, 0)].fg, buf[(0, 1)].fg)
+        };
+
+        let (comment, second_line) = render(&mut state);
+        assert_eq!(second_line, comment);
+
+        RemoveChar(2).execute(&mut state);
+        let (_, second_line) = render(&mut state);
+        assert_ne!(second_line, comment);
+    }
+
+    #[test]
+    fn test_render_invalidates_replaced_lines() {
+        let mut state = EditorState::new(Lines::from("/* a\nb */"));
+        let area = Rect::new(0, 0, 10, 4);
+        let render = |state: &mut EditorState| {
+            let mut buf = Buffer::empty(area);
+            EditorView::new(state)
+                .syntax_highlighter(Some(SyntaxHighlighter::new("dracula", "rs")))
+                .render(area, &mut buf);
+            (buf[(2, 0)].fg, buf[(0, 1)].fg)
+        };
+        let (comment, _) = render(&mut state);
+
+        // A different number of lines is noticed.
+        state.lines = Lines::from("a\nb */\nc");
+        let (_, second_line) = render(&mut state);
+        assert_ne!(second_line, comment);
+
+        // The same number of lines requires an invalidation.
+        state.lines = Lines::from("/* a\nb */\nc");
+        state.invalidate_highlighting();
+        let (_, second_line) = render(&mut state);
+        assert_eq!(second_line, comment);
+    }
+
+    #[test]
+    fn test_theme_id() {
+        let syntax = SyntaxHighlighter::new("dracula", "rs");
+        assert_eq!(syntax.theme_id, syntax.clone().theme("dracula").theme_id);
+        assert_ne!(syntax.theme_id, syntax.clone().theme("nord").theme_id);
+
+        let custom = syntax.clone().custom_theme(Theme::default());
+        assert_eq!(custom.theme_id, custom.clone().theme_id);
+        assert_ne!(
+            custom.theme_id,
+            syntax.custom_theme(Theme::default()).theme_id
+        );
+    }
 }
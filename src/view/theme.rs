@@ -1,10 +1,41 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "syntax-highlighting")]
+use super::syntax_higlighting::ColorDepth;
 use super::EditorStatusLine;
+#[cfg(feature = "syntax-highlighting")]
+use crate::syntect::{
+    highlighting::{self, FontStyle, Highlighter, Theme},
+    parsing::Scope,
+};
+use crate::Severity;
 use ratatui::{
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     widgets::Block,
 };
 
 /// The theme data of the Editor.
+///
+/// With the "serde" feature, the theme can be serialized and deserialized,
+/// e.g. to load it from a TOML or JSON file. Missing styles keep their
+/// default and the surrounding block is not serialized. Styles use the
+/// format of ratatui, see [`Style`].
+///
+/// ## Example
+///
+/// ```ignore
+/// use edtui::EditorTheme;
+///
+/// let theme: EditorTheme = serde_json::from_str(r##"{
+///     "base": { "fg": "#F8F8F2", "bg": "#282A36", "add_modifier": "", "sub_modifier": "" },
+///     "status_line": null
+/// }"##)?;
+/// ```
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct EditorTheme<'a> {
     /// The base text style
     pub base: Style,
@@ -13,9 +44,55 @@ pub struct EditorTheme<'a> {
     /// The text style in visual mode when a text is selected
     pub selection_style: Style,
     /// The surrounding block
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub block: Option<Block<'a>>,
     /// An optional [`StatusLine`] displaying the editor mode
     pub status_line: Option<EditorStatusLine>,
+    /// The style of the completion popup
+    pub completion_style: Style,
+    /// The style of the selected item in the completion popup
+    pub completion_selected_style: Style,
+    /// The style of the marker in front of wrapped rows
+    pub show_break_style: Style,
+    /// The style of the line numbers
+    pub line_number_style: Style,
+    /// The style of the line number of the cursor line
+    pub current_line_number_style: Style,
+    /// The style of error diagnostics. The foreground colour is used for
+    /// the underline, the sign and the virtual text.
+    pub diagnostic_error_style: Style,
+    /// The style of warning diagnostics
+    pub diagnostic_warning_style: Style,
+    /// The style of info diagnostics
+    pub diagnostic_info_style: Style,
+    /// The style of hint diagnostics
+    pub diagnostic_hint_style: Style,
+    /// The style of inlay hints
+    pub inlay_hint_style: Style,
+    /// The style of the summary row of a closed fold
+    pub fold_style: Style,
+    /// The background of the cursor line
+    pub cursor_line_style: Style,
+    /// The background of the colour columns
+    pub color_column_style: Style,
+    /// The style of the glyphs of visible whitespace
+    pub whitespace_style: Style,
+    /// The style of the scrollbar track
+    pub scrollbar_style: Style,
+    /// The style of the scrollbar thumb, which covers the displayed rows
+    pub scrollbar_thumb_style: Style,
+    /// The style of the markers of search matches on the scrollbar
+    pub scrollbar_search_style: Style,
+    /// The style of the markers of the selection on the scrollbar
+    pub scrollbar_selection_style: Style,
+    /// The style of the bracket pair at the cursor
+    pub matching_bracket_style: Style,
+    /// The styles of brackets by their nesting depth, repeated for deeper
+    /// brackets
+    pub rainbow_bracket_styles: Vec<Style>,
+    /// The styles of named highlights, e.g. the captures `keyword` or
+    /// `string.escape` of a tree-sitter query, see [`Self::highlight_style`]
+    pub syntax_styles: BTreeMap<String, Style>,
 }
 
 impl Default for EditorTheme<'_> {
@@ -29,6 +106,35 @@ impl Default for EditorTheme<'_> {
             cursor_style: Style::default().bg(WHITE).fg(BLACK),
             selection_style: Style::default().bg(YELLOW).fg(BLACK),
             status_line: Some(EditorStatusLine::default()),
+            completion_style: Style::default().bg(DARK_GRAY).fg(WHITE),
+            completion_selected_style: Style::default().bg(YELLOW).fg(BLACK),
+            show_break_style: Style::default().fg(GRAY),
+            line_number_style: Style::default().bg(BLACK).fg(GRAY),
+            current_line_number_style: Style::default().bg(BLACK).fg(WHITE),
+            diagnostic_error_style: Style::default().bg(BLACK).fg(RED),
+            diagnostic_warning_style: Style::default().bg(BLACK).fg(YELLOW),
+            diagnostic_info_style: Style::default().bg(BLACK).fg(BLUE),
+            diagnostic_hint_style: Style::default().bg(BLACK).fg(GRAY),
+            inlay_hint_style: Style::default().fg(GRAY),
+            fold_style: Style::default().bg(DARK_GRAY).fg(GRAY),
+            cursor_line_style: Style::default().bg(DARK_GRAY),
+            color_column_style: Style::default().bg(DARK_GRAY),
+            whitespace_style: Style::default().fg(GRAY),
+            scrollbar_style: Style::default().bg(DARK_GRAY),
+            scrollbar_thumb_style: Style::default().bg(GRAY),
+            scrollbar_search_style: Style::default().fg(YELLOW),
+            scrollbar_selection_style: Style::default().fg(WHITE),
+            matching_bracket_style: Style::default().bg(GRAY).fg(WHITE),
+            rainbow_bracket_styles: vec![
+                Style::default().fg(YELLOW),
+                Style::default().fg(MAGENTA),
+                Style::default().fg(BLUE),
+                Style::default().fg(GREEN),
+            ],
+            syntax_styles: SYNTAX_STYLES
+                .iter()
+                .map(|&(name, style, _)| (name.to_string(), style))
+                .collect(),
         }
     }
 }
@@ -79,6 +185,211 @@ impl<'a> EditorTheme<'a> {
         self
     }
 
+    /// This method allows you to customize the style of the completion
+    /// popup.
+    #[must_use]
+    pub fn completion_style(mut self, style: Style) -> Self {
+        self.completion_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the selected item
+    /// in the completion popup.
+    #[must_use]
+    pub fn completion_selected_style(mut self, style: Style) -> Self {
+        self.completion_selected_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the marker in front
+    /// of the continuation rows of wrapped lines.
+    #[must_use]
+    pub fn show_break_style(mut self, style: Style) -> Self {
+        self.show_break_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the line numbers.
+    #[must_use]
+    pub fn line_number_style(mut self, style: Style) -> Self {
+        self.line_number_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the line number of
+    /// the cursor line.
+    #[must_use]
+    pub fn current_line_number_style(mut self, style: Style) -> Self {
+        self.current_line_number_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of error diagnostics.
+    #[must_use]
+    pub fn diagnostic_error_style(mut self, style: Style) -> Self {
+        self.diagnostic_error_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of warning diagnostics.
+    #[must_use]
+    pub fn diagnostic_warning_style(mut self, style: Style) -> Self {
+        self.diagnostic_warning_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of info diagnostics.
+    #[must_use]
+    pub fn diagnostic_info_style(mut self, style: Style) -> Self {
+        self.diagnostic_info_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of hint diagnostics.
+    #[must_use]
+    pub fn diagnostic_hint_style(mut self, style: Style) -> Self {
+        self.diagnostic_hint_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of inlay hints.
+    #[must_use]
+    pub fn inlay_hint_style(mut self, style: Style) -> Self {
+        self.inlay_hint_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the summary row of
+    /// closed folds.
+    #[must_use]
+    pub fn fold_style(mut self, style: Style) -> Self {
+        self.fold_style = style;
+        self
+    }
+
+    /// This method allows you to customize the background of the cursor
+    /// line. Only takes effect if [`EditorView::cursor_line`] is enabled.
+    ///
+    /// [`EditorView::cursor_line`]: crate::EditorView::cursor_line
+    #[must_use]
+    pub fn cursor_line_style(mut self, style: Style) -> Self {
+        self.cursor_line_style = style;
+        self
+    }
+
+    /// This method allows you to customize the background of the colour
+    /// columns set with [`EditorView::color_columns`].
+    ///
+    /// [`EditorView::color_columns`]: crate::EditorView::color_columns
+    #[must_use]
+    pub fn color_column_style(mut self, style: Style) -> Self {
+        self.color_column_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the glyphs of
+    /// visible whitespace set with [`EditorView::list_chars`].
+    ///
+    /// [`EditorView::list_chars`]: crate::EditorView::list_chars
+    #[must_use]
+    pub fn whitespace_style(mut self, style: Style) -> Self {
+        self.whitespace_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the scrollbar
+    /// track.
+    #[must_use]
+    pub fn scrollbar_style(mut self, style: Style) -> Self {
+        self.scrollbar_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the scrollbar
+    /// thumb.
+    #[must_use]
+    pub fn scrollbar_thumb_style(mut self, style: Style) -> Self {
+        self.scrollbar_thumb_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the markers of
+    /// search matches on the scrollbar.
+    #[must_use]
+    pub fn scrollbar_search_style(mut self, style: Style) -> Self {
+        self.scrollbar_search_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the markers of the
+    /// selection on the scrollbar.
+    #[must_use]
+    pub fn scrollbar_selection_style(mut self, style: Style) -> Self {
+        self.scrollbar_selection_style = style;
+        self
+    }
+
+    /// This method allows you to customize the style of the bracket pair
+    /// at the cursor.
+    #[must_use]
+    pub fn matching_bracket_style(mut self, style: Style) -> Self {
+        self.matching_bracket_style = style;
+        self
+    }
+
+    /// This method allows you to customize the styles of rainbow brackets.
+    /// Brackets of depth `n` use the style `n % styles.len()`.
+    #[must_use]
+    pub fn rainbow_bracket_styles(mut self, styles: Vec<Style>) -> Self {
+        self.rainbow_bracket_styles = styles;
+        self
+    }
+
+    /// This method allows you to customize the style of the highlights
+    /// named `name` and of the highlights below it without a style of
+    /// their own, e.g. `"string"` also styles `string.escape`.
+    #[must_use]
+    pub fn syntax_style(mut self, name: &str, style: Style) -> Self {
+        self.syntax_styles.insert(name.to_string(), style);
+        self
+    }
+
+    /// Returns the style of the highlight named `name`. A highlight without
+    /// a style of its own takes the style of its parent, e.g.
+    /// `function.macro` falls back to `function`.
+    #[must_use]
+    pub fn highlight_style(&self, name: &str) -> Option<Style> {
+        let mut name = name;
+        loop {
+            if let Some(style) = self.syntax_styles.get(name) {
+                return Some(*style);
+            }
+            name = &name[..name.rfind('.')?];
+        }
+    }
+
+    /// Returns the style of the signs and virtual text of diagnostics.
+    pub(crate) fn diagnostic_style(&self, severity: Severity) -> Style {
+        match severity {
+            Severity::Error => self.diagnostic_error_style,
+            Severity::Warning => self.diagnostic_warning_style,
+            Severity::Info => self.diagnostic_info_style,
+            Severity::Hint => self.diagnostic_hint_style,
+        }
+    }
+
+    /// Returns the style that underlines the text of diagnostics.
+    ///
+    /// Ratatui cannot express undercurls, the text is underlined in the
+    /// colour of the diagnostic instead. The host can redraw it with an
+    /// undercurl, see [`EditorState::draw_undercurls`](crate::EditorState::draw_undercurls).
+    pub(crate) fn diagnostic_underline_style(&self, severity: Severity) -> Style {
+        let color = self.diagnostic_style(severity).fg.unwrap_or(Color::Reset);
+        Style::default()
+            .underline_color(color)
+            .add_modifier(Modifier::UNDERLINED)
+    }
+
     /// This method allows you to customize the style of the [`StatusLine`]
     /// of the Editor. See [`StatusLine`] on how to modify its appearance.
     /// Use `hide_status_line` to hide the status line.
@@ -96,7 +407,261 @@ impl<'a> EditorTheme<'a> {
     }
 }
 
+#[cfg(feature = "syntax-highlighting")]
+impl EditorTheme<'_> {
+    /// Creates a theme that matches the colours of a syntax highlighting
+    /// theme, e.g. one of the themes of a [`SyntaxAssets`].
+    ///
+    /// The base, cursor, selection, cursor line and gutter colours are taken
+    /// from the settings of the theme, where it defines them. The syntax
+    /// styles are looked up by the scopes of their highlights, e.g. the
+    /// scope `entity.name.function` for `function`. All other styles keep
+    /// their default. The colours are quantised to `color_depth`.
+    ///
+    /// [`SyntaxAssets`]: crate::SyntaxAssets
+    #[must_use]
+    pub fn from_syntax_theme(theme: &Theme, color_depth: ColorDepth) -> Self {
+        let settings = &theme.settings;
+        let color = |color: Option<highlighting::Color>| {
+            color.map(|color| color_depth.color(color.r, color.g, color.b))
+        };
+        let default = Self::default();
+        let foreground = color(settings.foreground).or(default.base.fg);
+        let background = color(settings.background).or(default.base.bg);
+        let gutter = color(settings.gutter).or(background);
+        let with_colors = |style: Style, fg: Option<Color>, bg: Option<Color>| Style {
+            fg: fg.or(style.fg),
+            bg: bg.or(style.bg),
+            ..style
+        };
+
+        Self {
+            base: with_colors(default.base, foreground, background),
+            cursor_style: with_colors(
+                default.cursor_style,
+                background,
+                color(settings.caret).or(foreground),
+            ),
+            selection_style: with_colors(
+                default.selection_style,
+                color(settings.selection_foreground).or(foreground),
+                color(settings.selection),
+            ),
+            cursor_line_style: with_colors(
+                default.cursor_line_style,
+                None,
+                color(settings.line_highlight),
+            ),
+            line_number_style: with_colors(
+                default.line_number_style,
+                color(settings.gutter_foreground),
+                gutter,
+            ),
+            current_line_number_style: with_colors(
+                default.current_line_number_style,
+                foreground,
+                gutter,
+            ),
+            diagnostic_error_style: with_colors(default.diagnostic_error_style, None, gutter),
+            diagnostic_warning_style: with_colors(default.diagnostic_warning_style, None, gutter),
+            diagnostic_info_style: with_colors(default.diagnostic_info_style, None, gutter),
+            diagnostic_hint_style: with_colors(default.diagnostic_hint_style, None, gutter),
+            syntax_styles: syntax_styles(theme, color_depth),
+            ..default
+        }
+    }
+}
+
+/// Returns the styles of the highlights of [`SYNTAX_STYLES`] in the syntax
+/// theme `theme`. Highlights whose scope has no colour keep their default.
+#[cfg(feature = "syntax-highlighting")]
+fn syntax_styles(theme: &Theme, color_depth: ColorDepth) -> BTreeMap<String, Style> {
+    let highlighter = Highlighter::new(theme);
+    let mut styles = BTreeMap::new();
+    for &(name, default, scope) in &SYNTAX_STYLES {
+        let Ok(scope) = Scope::new(scope) else {
+            continue;
+        };
+        let style = highlighter.style_mod_for_stack(&[scope]);
+        let Some(color) = style.foreground else {
+            styles.insert(name.to_string(), default);
+            continue;
+        };
+        let mut result = Style::default().fg(color_depth.color(color.r, color.g, color.b));
+        let font_style = style.font_style.unwrap_or_default();
+        for (font_style_flag, modifier) in [
+            (FontStyle::BOLD, Modifier::BOLD),
+            (FontStyle::ITALIC, Modifier::ITALIC),
+            (FontStyle::UNDERLINE, Modifier::UNDERLINED),
+        ] {
+            if font_style.contains(font_style_flag) {
+                result = result.add_modifier(modifier);
+            }
+        }
+        styles.insert(name.to_string(), result);
+    }
+    styles
+}
+
+/// The default syntax styles by the name of their highlight, with the
+/// scope of the highlight in syntax themes.
+const SYNTAX_STYLES: [(&str, Style, &str); 12] = [
+    (
+        "attribute",
+        Style::new().fg(YELLOW),
+        "entity.other.attribute-name",
+    ),
+    ("comment", Style::new().fg(GRAY), "comment"),
+    ("constant", Style::new().fg(RED), "constant"),
+    ("constructor", Style::new().fg(YELLOW), "entity.name.type"),
+    ("escape", Style::new().fg(RED), "constant.character.escape"),
+    ("function", Style::new().fg(BLUE), "entity.name.function"),
+    ("keyword", Style::new().fg(MAGENTA), "keyword"),
+    ("label", Style::new().fg(YELLOW), "entity.name.label"),
+    ("number", Style::new().fg(RED), "constant.numeric"),
+    ("string", Style::new().fg(GREEN), "string"),
+    (
+        "string.escape",
+        Style::new().fg(RED),
+        "constant.character.escape",
+    ),
+    ("type", Style::new().fg(YELLOW), "entity.name.type"),
+];
+
 pub(crate) const WHITE: Color = Color::Rgb(255, 255, 255);
 pub(crate) const BLACK: Color = Color::Rgb(0, 0, 0);
 pub(crate) const DARK_GRAY: Color = Color::Rgb(16, 17, 22);
+pub(crate) const GRAY: Color = Color::Rgb(100, 100, 110);
 pub(crate) const YELLOW: Color = Color::Rgb(250, 204, 21);
+pub(crate) const RED: Color = Color::Rgb(239, 68, 68);
+pub(crate) const BLUE: Color = Color::Rgb(59, 130, 246);
+pub(crate) const MAGENTA: Color = Color::Rgb(217, 70, 239);
+pub(crate) const GREEN: Color = Color::Rgb(34, 197, 94);
+
+#[cfg(all(test, any(feature = "serde", feature = "syntax-highlighting")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_theme() {
+        let theme: EditorTheme = serde_json::from_str(
+            r##"{
+                "base": { "fg": "#F8F8F2", "bg": "#282A36", "add_modifier": "", "sub_modifier": "" },
+                "selection_style": { "bg": "Blue", "add_modifier": "BOLD", "sub_modifier": "" },
+                "status_line": { "align_left": false }
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(
+            theme.base,
+            Style::default()
+                .fg(Color::Rgb(248, 248, 242))
+                .bg(Color::Rgb(40, 42, 54))
+        );
+        assert_eq!(
+            theme.selection_style,
+            Style::default()
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(theme.cursor_style, EditorTheme::default().cursor_style);
+        let status_line = theme.status_line.as_ref().unwrap();
+        assert_eq!(
+            format!("{status_line:?}"),
+            format!("{:?}", EditorStatusLine::default().align_left(false))
+        );
+
+        // A serialized theme is restored, a status line can be hidden.
+        let json = serde_json::to_string(&theme.hide_status_line()).unwrap();
+        let theme: EditorTheme = serde_json::from_str(&json).unwrap();
+        assert_eq!(theme.base.bg, Some(Color::Rgb(40, 42, 54)));
+        assert!(theme.status_line.is_none());
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    #[test]
+    fn test_from_syntax_theme() {
+        use crate::view::syntax_higlighting::THEME_SET;
+
+        let mut syntax_theme = THEME_SET.themes["dracula"].clone();
+        let settings = &mut syntax_theme.settings;
+        settings.foreground = Some(highlighting::Color {
+            r: 248,
+            g: 248,
+            b: 242,
+            a: 255,
+        });
+        settings.background = Some(highlighting::Color {
+            r: 40,
+            g: 42,
+            b: 54,
+            a: 255,
+        });
+        settings.caret = Some(highlighting::Color {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        });
+        settings.selection = Some(highlighting::Color {
+            r: 68,
+            g: 71,
+            b: 90,
+            a: 255,
+        });
+        settings.selection_foreground = None;
+        settings.gutter = None;
+        settings.gutter_foreground = Some(highlighting::Color {
+            r: 98,
+            g: 114,
+            b: 164,
+            a: 255,
+        });
+
+        let theme = EditorTheme::from_syntax_theme(&syntax_theme, ColorDepth::TrueColor);
+        let (fg, bg) = (Color::Rgb(248, 248, 242), Color::Rgb(40, 42, 54));
+        assert_eq!(theme.base, Style::default().fg(fg).bg(bg));
+        assert_eq!(
+            theme.cursor_style,
+            Style::default().fg(bg).bg(Color::Rgb(255, 0, 0))
+        );
+        assert_eq!(
+            theme.selection_style,
+            Style::default().fg(fg).bg(Color::Rgb(68, 71, 90))
+        );
+        // Without a gutter colour, the gutter uses the background.
+        assert_eq!(
+            theme.line_number_style,
+            Style::default().fg(Color::Rgb(98, 114, 164)).bg(bg)
+        );
+        assert_eq!(
+            theme.diagnostic_error_style,
+            Style::default().fg(RED).bg(bg)
+        );
+        assert_eq!(
+            theme.completion_style,
+            EditorTheme::default().completion_style
+        );
+
+        // The syntax styles are looked up by scope, highlights whose scope
+        // the theme does not colour keep their default.
+        let syntax_style = |name| theme.highlight_style(name).unwrap();
+        assert_eq!(
+            syntax_style("keyword.control"),
+            Style::default().fg(Color::Rgb(255, 121, 198))
+        );
+        assert_eq!(
+            syntax_style("string"),
+            Style::default().fg(Color::Rgb(241, 250, 140))
+        );
+        assert_eq!(syntax_style("type"), Style::default().fg(YELLOW));
+        assert_eq!(theme.highlight_style("punctuation"), None);
+
+        let theme = EditorTheme::from_syntax_theme(&syntax_theme, ColorDepth::Ansi16);
+        assert_eq!(
+            theme.base,
+            Style::default().fg(Color::White).bg(Color::Black)
+        );
+    }
+}
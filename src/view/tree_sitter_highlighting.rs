@@ -0,0 +1,571 @@
+use std::{collections::HashMap, fmt, ops::Range, sync::Arc};
+
+use jagged::index::RowIndex;
+use ratatui::{style::Style, text::Span};
+use tree_sitter::{
+    InputEdit, Language, LanguageError, Parser, Point, Query, QueryCursor, QueryError,
+    StreamingIterator, Tree,
+};
+
+use super::{
+    highlighter::{EditedRows, HighlightCache, LineHighlighter},
+    theme::EditorTheme,
+};
+use crate::{CommentTokens, Lines};
+
+/// A syntax highlighter that parses the text with a tree-sitter grammar.
+/// Requires the "tree-sitter" feature.
+///
+/// The grammar and its highlight query are provided by the host, e.g. from
+/// a `tree-sitter-<language>` crate. The text is parsed once and re-parsed
+/// incrementally after edits. The captures of the query, such as
+/// `@keyword` or `@string.escape`, are styled by their name with the
+/// syntax styles of the [`EditorTheme`], see [`EditorTheme::syntax_styles`],
+/// unless they are styled with [`TreeSitterHighlighter::style`].
+///
+/// The highlighter is cheap to clone, the grammar and the query are shared.
+///
+/// ## Example
+///
+/// ```ignore
+/// use edtui::{EditorState, EditorView, TreeSitterHighlighter};
+///
+/// let highlighter = TreeSitterHighlighter::new(
+///     "rust",
+///     tree_sitter_rust::LANGUAGE,
+///     tree_sitter_rust::HIGHLIGHTS_QUERY,
+/// )?;
+/// EditorView::new(&mut EditorState::default()).highlighter(highlighter.clone());
+/// ```
+#[derive(Clone)]
+pub struct TreeSitterHighlighter {
+    grammar: Arc<Grammar>,
+    /// The style of every capture of the query that overrides the style of
+    /// the theme.
+    styles: Vec<Option<Style>>,
+    comment_tokens: Option<CommentTokens>,
+}
+
+struct Grammar {
+    name: String,
+    language: Language,
+    query: Query,
+}
+
+impl TreeSitterHighlighter {
+    /// Creates a new highlighter of the grammar `language` with the
+    /// highlight query `highlights_query`.
+    ///
+    /// # Errors
+    /// Returns an error if the grammar was generated for a version of
+    /// tree-sitter that is not supported, or if the query is invalid for
+    /// the grammar.
+    pub fn new(
+        name: &str,
+        language: impl Into<Language>,
+        highlights_query: &str,
+    ) -> Result<Self, TreeSitterError> {
+        let language = language.into();
+        Parser::new().set_language(&language)?;
+        let query = Query::new(&language, highlights_query)?;
+        let styles = vec![None; query.capture_names().len()];
+        let grammar = Grammar {
+            name: name.to_string(),
+            language,
+            query,
+        };
+        Ok(Self {
+            grammar: Arc::new(grammar),
+            styles,
+            comment_tokens: None,
+        })
+    }
+
+    /// Returns the name of the grammar.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.grammar.name
+    }
+
+    /// Sets the style of the captures named `capture` and of the captures
+    /// below it, e.g. `"string"` also styles `@string.escape`, instead of
+    /// the style of the theme. Style the more specific captures afterwards.
+    #[must_use]
+    pub fn style(mut self, capture: &str, style: Style) -> Self {
+        for (i, name) in self.grammar.query.capture_names().iter().enumerate() {
+            if is_below(name, capture) {
+                self.styles[i] = Some(style);
+            }
+        }
+        self
+    }
+
+    /// Sets the comment tokens of the language, used to toggle comments.
+    /// Grammars do not define them, e.g. `CommentTokens::line("//")`.
+    #[must_use]
+    pub fn comment_tokens(mut self, tokens: CommentTokens) -> Self {
+        self.comment_tokens = Some(tokens);
+        self
+    }
+}
+
+/// An error that occurs when creating a [`TreeSitterHighlighter`].
+#[derive(Debug)]
+pub enum TreeSitterError {
+    /// The grammar was generated for an unsupported version of tree-sitter.
+    Language(LanguageError),
+    /// The highlight query is invalid for the grammar.
+    Query(QueryError),
+}
+
+impl fmt::Display for TreeSitterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Language(err) => write!(f, "could not load grammar: {err}"),
+            Self::Query(err) => write!(f, "could not load highlight query: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TreeSitterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Language(err) => Some(err),
+            Self::Query(err) => Some(err),
+        }
+    }
+}
+
+impl From<LanguageError> for TreeSitterError {
+    fn from(err: LanguageError) -> Self {
+        Self::Language(err)
+    }
+}
+
+impl From<QueryError> for TreeSitterError {
+    fn from(err: QueryError) -> Self {
+        Self::Query(err)
+    }
+}
+
+/// Returns whether the capture `name` is `capture` or below it.
+fn is_below(name: &str, capture: &str) -> bool {
+    name.strip_prefix(capture)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// The parsed text, kept between renders.
+struct ParsedText {
+    grammar: Arc<Grammar>,
+    parser: Parser,
+    tree: Option<Tree>,
+    /// The text that was parsed, every line is followed by a `\n`.
+    source: String,
+    /// The byte offset of the start of every line.
+    line_starts: Vec<usize>,
+    /// The styles the highlighted lines were highlighted with.
+    styles: Vec<Option<Style>>,
+    /// The spans of the highlighted lines.
+    spans: HashMap<usize, Vec<Span<'static>>>,
+}
+
+impl ParsedText {
+    fn new(grammar: &Arc<Grammar>) -> Self {
+        let mut parser = Parser::new();
+        // The language was checked by `TreeSitterHighlighter::new`.
+        parser
+            .set_language(&grammar.language)
+            .expect("the language version is supported");
+        Self {
+            grammar: Arc::clone(grammar),
+            parser,
+            tree: None,
+            source: String::new(),
+            line_starts: Vec::new(),
+            styles: Vec::new(),
+            spans: HashMap::new(),
+        }
+    }
+
+    /// Parses the whole text.
+    fn parse(&mut self, lines: &Lines) {
+        self.source = source(lines, 0..lines.len());
+        self.line_starts = line_starts(&self.source, 0);
+        self.tree = self.parser.parse(&self.source, None);
+        self.spans.clear();
+    }
+
+    /// Parses the text after the rows `edits` were edited, reusing the
+    /// unchanged parts of the previous tree. Only the edited rows are
+    /// replaced in the source and only the lines whose highlighting changed
+    /// are highlighted again.
+    fn reparse(&mut self, lines: &Lines, edits: EditedRows) {
+        let num_lines = self.line_starts.len();
+        let Some(mut tree) = self.tree.take() else {
+            return self.parse(lines);
+        };
+        let (start, old_end) = (edits.start.min(num_lines), edits.old_end.min(num_lines));
+        let new_end = edits.new_end.min(lines.len());
+        if start > old_end || start > new_end || num_lines - old_end != lines.len() - new_end {
+            return self.parse(lines);
+        }
+
+        let line_start = |row: usize| self.line_starts.get(row).copied();
+        let start_byte = line_start(start).unwrap_or(self.source.len());
+        let old_end_byte = line_start(old_end).unwrap_or(self.source.len());
+        let inserted = source(lines, start..new_end);
+        let new_end_byte = start_byte + inserted.len();
+        tree.edit(&InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position: Point::new(start, 0),
+            old_end_position: Point::new(old_end, 0),
+            new_end_position: Point::new(new_end, 0),
+        });
+
+        self.source
+            .replace_range(start_byte..old_end_byte, &inserted);
+        let shifted = self.line_starts.split_off(old_end);
+        self.line_starts.truncate(start);
+        self.line_starts.extend(line_starts(&inserted, start_byte));
+        self.line_starts.extend(
+            shifted
+                .into_iter()
+                .map(|line_start| line_start - old_end_byte + new_end_byte),
+        );
+
+        self.tree = self.parser.parse(&self.source, Some(&tree));
+        self.spans = std::mem::take(&mut self.spans)
+            .into_iter()
+            .filter_map(|(row, spans)| match row {
+                row if row < start => Some((row, spans)),
+                row if row >= old_end => Some((row - old_end + new_end, spans)),
+                _ => None,
+            })
+            .collect();
+        if let Some(new_tree) = &self.tree {
+            for range in tree.changed_ranges(new_tree) {
+                for row in range.start_point.row..=range.end_point.row {
+                    self.spans.remove(&row);
+                }
+            }
+        }
+    }
+
+    /// Highlights the line `row`. Inner captures override the captures of
+    /// the nodes around them, for the same node the first capture wins.
+    fn highlight(&self, row: usize, query: &Query) -> Option<Vec<Span<'static>>> {
+        let tree = self.tree.as_ref()?;
+        let start = *self.line_starts.get(row)?;
+        let end = self
+            .line_starts
+            .get(row + 1)
+            .map_or(self.source.len(), |&next| next)
+            - 1;
+
+        let mut styles: Vec<Option<Style>> = vec![None; end - start];
+        let mut styled_nodes = Vec::new();
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(start..end);
+        let mut captures = cursor.captures(query, tree.root_node(), self.source.as_bytes());
+        while let Some((query_match, index)) = captures.next() {
+            let capture = query_match.captures[*index];
+            let Some(style) = self.styles[capture.index as usize] else {
+                continue;
+            };
+            let range = capture.node.byte_range();
+            if styled_nodes.contains(&range) {
+                continue;
+            }
+            for byte in range.start.max(start)..range.end.min(end) {
+                styles[byte - start] = Some(style);
+            }
+            styled_nodes.push(range);
+        }
+
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut current: Option<(String, Option<Style>)> = None;
+        for (i, ch) in self.source[start..end].char_indices() {
+            match &mut current {
+                Some((text, style)) if *style == styles[i] => text.push(ch),
+                _ => {
+                    spans.extend(current.take().map(styled_span));
+                    current = Some((ch.to_string(), styles[i]));
+                }
+            }
+        }
+        spans.extend(current.map(styled_span));
+        Some(spans)
+    }
+}
+
+fn styled_span((text, style): (String, Option<Style>)) -> Span<'static> {
+    Span::styled(text, style.unwrap_or_default())
+}
+
+/// Returns the rows `rows` of `lines`, every row followed by a `\n`.
+fn source(lines: &Lines, rows: Range<usize>) -> String {
+    let mut source = String::new();
+    for row in rows {
+        if let Some(line) = lines.get(RowIndex::new(row)) {
+            source.extend(line.iter());
+            source.push('\n');
+        }
+    }
+    source
+}
+
+/// Returns the byte offset of the start of every line of `source`, which
+/// starts at the byte offset `offset`.
+fn line_starts(source: &str, offset: usize) -> Vec<usize> {
+    let mut starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .map(|start| offset + start)
+        .collect();
+    // The line break of the last line does not start another one.
+    starts.pop();
+    starts
+}
+
+impl LineHighlighter for TreeSitterHighlighter {
+    fn highlight_line(
+        &self,
+        lines: &Lines,
+        row: usize,
+        theme: &EditorTheme,
+        cache: &mut HighlightCache,
+    ) -> Option<Vec<Span<'static>>> {
+        let styles: Vec<Option<Style>> = self
+            .grammar
+            .query
+            .capture_names()
+            .iter()
+            .zip(&self.styles)
+            .map(|(capture, style)| style.or_else(|| theme.highlight_style(capture)))
+            .collect();
+        let edits = cache.take_edits();
+        let text = cache.state(|| ParsedText::new(&self.grammar));
+        if !Arc::ptr_eq(&text.grammar, &self.grammar) {
+            *text = ParsedText::new(&self.grammar);
+        }
+        match edits {
+            _ if text.tree.is_none() => text.parse(lines),
+            Some(edits) => text.reparse(lines, edits),
+            None => {}
+        }
+        if text.styles != styles {
+            text.styles = styles;
+            text.spans.clear();
+        }
+
+        if let Some(spans) = text.spans.get(&row) {
+            return Some(spans.clone());
+        }
+        let spans = text.highlight(row, &self.grammar.query)?;
+        text.spans.insert(row, spans.clone());
+        Some(spans)
+    }
+
+    fn comment_tokens(&self) -> Option<CommentTokens> {
+        self.comment_tokens.clone()
+    }
+}
+
+/// A collection of the tree-sitter grammars registered by the host, by
+/// file extension. Requires the "tree-sitter" feature.
+#[derive(Clone, Default)]
+pub struct TreeSitterGrammars {
+    grammars: Vec<(Vec<String>, TreeSitterHighlighter)>,
+}
+
+impl TreeSitterGrammars {
+    /// Registers a highlighter for files with one of the `extensions`, e.g.
+    /// `["rs"]`. A grammar registered later takes precedence.
+    pub fn register(&mut self, extensions: &[&str], highlighter: TreeSitterHighlighter) {
+        let extensions = extensions.iter().map(ToString::to_string).collect();
+        self.grammars.push((extensions, highlighter));
+    }
+
+    /// Returns the highlighter of files with the extension `extension`.
+    #[must_use]
+    pub fn find_by_extension(&self, extension: &str) -> Option<TreeSitterHighlighter> {
+        self.grammars
+            .iter()
+            .rev()
+            .find(|(extensions, _)| extensions.iter().any(|ext| ext == extension))
+            .map(|(_, highlighter)| highlighter.clone())
+    }
+
+    /// Returns the highlighter of the grammar named `name`.
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<TreeSitterHighlighter> {
+        self.grammars
+            .iter()
+            .rev()
+            .find(|(_, highlighter)| highlighter.name() == name)
+            .map(|(_, highlighter)| highlighter.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+    use super::*;
+    use crate::{
+        actions::{DeleteLine, Execute, InsertChar, LineBreak},
+        view::theme::{BLUE, GRAY, GREEN, MAGENTA},
+        EditorState, EditorView, Index2,
+    };
+
+    fn rust() -> TreeSitterHighlighter {
+        TreeSitterHighlighter::new(
+            "rust",
+            tree_sitter_rust::LANGUAGE,
+            tree_sitter_rust::HIGHLIGHTS_QUERY,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_comment_tokens() {
+        let mut state = EditorState::new(Lines::from("fn main() {}"));
+        let highlighter = rust().comment_tokens(CommentTokens::line("//"));
+        let _ = EditorView::new(&mut state).highlighter(highlighter);
+        assert_eq!(state.comment_tokens(), Some(&CommentTokens::line("//")));
+
+        let _ = EditorView::new(&mut state).highlighter(rust());
+        assert_eq!(state.comment_tokens(), None);
+    }
+
+    #[test]
+    fn test_capture_styles() {
+        assert!(is_below("string.escape", "string"));
+        assert!(is_below("string", "string"));
+        assert!(!is_below("strings", "string"));
+
+        let lines = Lines::from("fn main() {}");
+        let mut cache = HighlightCache::default();
+        let mut highlight = |highlighter: &TreeSitterHighlighter, theme: &EditorTheme| {
+            let spans = highlighter.highlight_line(&lines, 0, theme, &mut cache);
+            let spans = spans.unwrap();
+            (spans[0].style, spans[2].style)
+        };
+        let (red, green) = (Style::new().fg(Color::Red), Style::new().fg(Color::Green));
+
+        // The captures are styled by the theme, `function` styles
+        // `@function` and a switched theme restyles the cached lines.
+        let theme = EditorTheme::default();
+        let (keyword, function) = highlight(&rust(), &theme);
+        assert_eq!(keyword, theme.highlight_style("keyword").unwrap());
+        assert_eq!(function, theme.highlight_style("function").unwrap());
+        let theme = theme.syntax_style("keyword", red);
+        assert_eq!(highlight(&rust(), &theme), (red, function));
+
+        // The styles of the highlighter override the theme.
+        let highlighter = rust().style("function", green);
+        assert_eq!(highlight(&highlighter, &theme), (red, green));
+    }
+
+    #[test]
+    fn test_reparse_edited_rows() {
+        let text = "fn a() {}\nlet b = \"x\";\n\nlet c = 2;\n";
+        let mut state = EditorState::new(Lines::from(text));
+        let highlighter = rust();
+        let area = Rect::new(0, 0, 20, 7);
+        let render = |state: &mut EditorState| {
+            let mut buf = Buffer::empty(area);
+            EditorView::new(state)
+                .highlighter(highlighter.clone())
+                .render(area, &mut buf);
+            buf
+        };
+        let fresh = |state: &EditorState| {
+            let mut fresh = EditorState::new(state.lines.clone());
+            fresh.cursor = state.cursor;
+            fresh
+        };
+        render(&mut state);
+
+        // Open a string that spans the lines below, split a line and
+        // remove another one, then close the string again.
+        state.mode = crate::EditorMode::Insert;
+        state.cursor = Index2::new(1, 8);
+        InsertChar('"').execute(&mut state);
+        state.cursor = Index2::new(0, 3);
+        LineBreak(1).execute(&mut state);
+        state.mode = crate::EditorMode::Normal;
+        state.cursor = Index2::new(3, 0);
+        DeleteLine(1).execute(&mut state);
+        let buf = render(&mut state);
+        assert_eq!(buf, render(&mut fresh(&state)));
+
+        state.undo();
+        state.undo();
+        let buf = render(&mut state);
+        assert_eq!(buf, render(&mut fresh(&state)));
+
+        let parsed = state
+            .view
+            .highlight_cache
+            .state(|| ParsedText::new(&highlighter.grammar));
+        let mut fresh = ParsedText::new(&highlighter.grammar);
+        fresh.parse(&state.lines);
+        assert_eq!(parsed.source, fresh.source);
+        assert_eq!(parsed.line_starts, fresh.line_starts);
+        assert_eq!(
+            parsed.tree.as_ref().map(|tree| tree.root_node().to_sexp()),
+            fresh.tree.as_ref().map(|tree| tree.root_node().to_sexp())
+        );
+    }
+
+    #[test]
+    fn test_highlight_and_reparse_on_edit() {
+        let mut state = EditorState::new(Lines::from("fn a() {}\n/* b */ let c = \"d\";"));
+        let highlighter = rust();
+        let area = Rect::new(0, 0, 25, 3);
+        let render = |state: &mut EditorState| {
+            let mut buf = Buffer::empty(area);
+            EditorView::new(state)
+                .highlighter(highlighter.clone())
+                .render(area, &mut buf);
+            buf
+        };
+
+        let buf = render(&mut state);
+        assert_eq!(buf[(1, 0)].fg, MAGENTA);
+        assert_eq!(buf[(3, 0)].fg, BLUE);
+        assert_eq!(buf[(0, 1)].fg, GRAY);
+        assert_eq!(buf[(16, 1)].fg, GREEN);
+
+        // Commenting out the first line re-parses the text.
+        state.mode = crate::EditorMode::Insert;
+        state.cursor = Index2::new(0, 0);
+        InsertChar('/').execute(&mut state);
+        InsertChar('/').execute(&mut state);
+        let buf = render(&mut state);
+        assert_eq!(buf[(4, 0)].fg, GRAY);
+        assert_eq!(buf[(16, 1)].fg, GREEN);
+    }
+
+    #[test]
+    fn test_grammars() {
+        let mut grammars = TreeSitterGrammars::default();
+        grammars.register(&["rs"], rust());
+        assert_eq!(
+            grammars
+                .find_by_extension("rs")
+                .map(|h| h.name().to_string()),
+            Some("rust".to_string())
+        );
+        assert!(grammars.find_by_extension("py").is_none());
+        assert!(grammars.find_by_name("rust").is_some());
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        let result = TreeSitterHighlighter::new("rust", tree_sitter_rust::LANGUAGE, "(unknown)");
+        assert!(matches!(result, Err(TreeSitterError::Query(_))));
+    }
+}